@@ -1,41 +1,157 @@
 struct Camera {
   view_proj : mat4x4<f32>,
+  eye : vec4<f32>,
 }
 @group(0) @binding(0) var<uniform> camera : Camera;
 
 struct ModelXform {
   model : mat4x4<f32>,
+  // rgb: debug tint, a: tint amount
+  tint : vec4<f32>,
 }
 @group(1) @binding(0) var<uniform> model_xform : ModelXform;
 
 @group(2) @binding(0) var texBase : texture_2d<f32>;
 @group(2) @binding(1) var samp    : sampler;
+@group(2) @binding(2) var texEmissive : texture_2d<f32>;
+
+struct MaterialParams {
+  base_color_factor : vec4<f32>,
+  emissive : vec4<f32>,
+  base_color_uv0 : vec4<f32>,
+  base_color_uv1 : vec4<f32>,
+  emissive_uv0 : vec4<f32>,
+  emissive_uv1 : vec4<f32>,
+  // x: unlit, y: clearcoat, z: clearcoat roughness, w: transmission
+  flags : vec4<f32>,
+}
+@group(2) @binding(3) var<uniform> material : MaterialParams;
+
+#ifdef HAS_SKIN
+@group(3) @binding(0) var<storage, read> joint_matrices : array<mat4x4<f32>>;
+#endif
+#ifdef HAS_MORPH
+struct MorphWeights {
+  target_count : u32,
+  vertex_count : u32,
+  weights : array<f32>,
+}
+@group(3) @binding(1) var<storage, read> morph_deltas : array<vec4<f32>>;
+@group(3) @binding(2) var<storage, read> morph_weights : MorphWeights;
+#endif
 
 struct VsIn {
   @location(0) pos : vec3<f32>,
   @location(1) nrm : vec3<f32>,
   @location(2) uv  : vec2<f32>,
+#ifdef HAS_COLOR_0
+  @location(3) color : vec4<f32>,
+#endif
+#ifdef HAS_TEXCOORD_1
+  @location(4) uv1 : vec2<f32>,
+#endif
+#ifdef HAS_TANGENT
+  @location(5) tangent : vec4<f32>,
+#endif
+#ifdef HAS_JOINTS_0
+  @location(6) joints : vec4<u32>,
+#endif
+#ifdef HAS_WEIGHTS_0
+  @location(7) weights : vec4<f32>,
+#endif
 }
 struct VsOut {
   @builtin(position) pos : vec4<f32>,
   @location(0) nrm : vec3<f32>,
   @location(1) uv  : vec2<f32>,
+  @location(2) color : vec4<f32>,
+  @location(3) uv1 : vec2<f32>,
+  @location(4) tangent : vec4<f32>,
+  @location(5) world_pos : vec3<f32>,
 }
 
 @vertex
-fn vs_main(in: VsIn) -> VsOut {
+fn vs_main(in: VsIn, @builtin(vertex_index) vid : u32) -> VsOut {
   var out: VsOut;
-  let world = model_xform.model * vec4<f32>(in.pos, 1.0);
+  var pos = in.pos;
+  var nrm = in.nrm;
+#ifdef HAS_MORPH
+  for (var t = 0u; t < morph_weights.target_count; t++) {
+    let w = morph_weights.weights[t];
+    if (w != 0.0) {
+      let base = (t * morph_weights.vertex_count + vid) * 2u;
+      pos += w * morph_deltas[base].xyz;
+      nrm += w * morph_deltas[base + 1u].xyz;
+    }
+  }
+#endif
+#ifdef HAS_SKIN
+  // Clamp so out-of-range joints (or the one-matrix fallback) read a defined entry.
+  let last = arrayLength(&joint_matrices) - 1u;
+  let joints = min(in.joints, vec4<u32>(last));
+  let skin = in.weights.x * joint_matrices[joints.x]
+           + in.weights.y * joint_matrices[joints.y]
+           + in.weights.z * joint_matrices[joints.z]
+           + in.weights.w * joint_matrices[joints.w];
+  let xform = model_xform.model * skin;
+#else
+  let xform = model_xform.model;
+#endif
+  let world = xform * vec4<f32>(pos, 1.0);
   out.pos = camera.view_proj * world;
-  out.nrm = normalize((model_xform.model * vec4<f32>(in.nrm, 0.0)).xyz);
+  out.world_pos = world.xyz;
+  out.nrm = normalize((xform * vec4<f32>(nrm, 0.0)).xyz);
   out.uv = in.uv;
+#ifdef HAS_COLOR_0
+  out.color = in.color;
+#else
+  out.color = vec4<f32>(1.0);
+#endif
+#ifdef HAS_TEXCOORD_1
+  out.uv1 = in.uv1;
+#else
+  out.uv1 = in.uv;
+#endif
+#ifdef HAS_TANGENT
+  out.tangent = vec4<f32>(normalize((xform * vec4<f32>(in.tangent.xyz, 0.0)).xyz), in.tangent.w);
+#else
+  out.tangent = vec4<f32>(1.0, 0.0, 0.0, 1.0);
+#endif
   return out;
 }
 
+fn material_uv(in: VsOut, row0: vec4<f32>, row1: vec4<f32>) -> vec2<f32> {
+  let uv = vec3<f32>(select(in.uv, in.uv1, row0.w > 0.5), 1.0);
+  return vec2<f32>(dot(row0.xyz, uv), dot(row1.xyz, uv));
+}
+
 @fragment
 fn fs_main(in: VsOut) -> @location(0) vec4<f32> {
-  let base = textureSample(texBase, samp, in.uv);
+  let base_uv = material_uv(in, material.base_color_uv0, material.base_color_uv1);
+  let emissive_uv = material_uv(in, material.emissive_uv0, material.emissive_uv1);
+  let base = textureSample(texBase, samp, base_uv) * material.base_color_factor * in.color;
+  let emissive = textureSample(texEmissive, samp, emissive_uv).rgb * material.emissive.rgb;
+  if (material.flags.x > 0.5) {
+    return vec4<f32>(mix(base.rgb, model_xform.tint.rgb, model_xform.tint.a), base.a);
+  }
+
+  let n = normalize(in.nrm);
+  let v = normalize(camera.eye.xyz - in.world_pos);
   let light_dir = normalize(vec3<f32>(0.5, 1.0, 0.3));
-  let lambert = max(dot(normalize(in.nrm), light_dir), 0.1);
-  return vec4<f32>(base.rgb * lambert, base.a);
+  let lambert = max(dot(n, light_dir), 0.1);
+  // No scene color is available to refract, so transmission only removes the diffuse lobe.
+  var color = base.rgb * lambert * (1.0 - material.flags.w);
+
+  let clearcoat = material.flags.y;
+  if (clearcoat > 0.0) {
+    let h = normalize(light_dir + v);
+    let roughness = max(material.flags.z, 0.03);
+    let shininess = 2.0 / (roughness * roughness * roughness * roughness) - 2.0;
+    let fresnel = 0.04 + 0.96 * pow(1.0 - max(dot(n, v), 0.0), 5.0);
+    let spec = pow(max(dot(n, h), 0.0), shininess) * max(dot(n, light_dir), 0.0);
+    color = color * (1.0 - clearcoat * fresnel) + vec3<f32>(clearcoat * fresnel * spec);
+  }
+
+  color = mix(color + emissive, model_xform.tint.rgb * lambert, model_xform.tint.a);
+  return vec4<f32>(color, base.a);
 }
@@ -0,0 +1,248 @@
+use glam::{Quat, Vec3};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    CubicSpline,
+}
+
+#[derive(Clone, Debug)]
+pub enum ChannelValues {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+    Weights(Vec<Vec<f32>>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sample {
+    Translation(Vec3),
+    Rotation(Quat),
+    Scale(Vec3),
+    Weights(Vec<f32>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Channel {
+    pub node: usize,
+    pub interpolation: Interpolation,
+    pub times: Vec<f32>,
+    pub values: ChannelValues,
+}
+
+impl Channel {
+    pub fn sample(&self, t: f32) -> Option<Sample> {
+        let (a, b, f, dt) = self.keys(t)?;
+        Some(match &self.values {
+            ChannelValues::Translation(v) => {
+                Sample::Translation(self.interpolate(v, a, b, f, dt, |x, y, f| x.lerp(y, f)))
+            }
+            ChannelValues::Scale(v) => {
+                Sample::Scale(self.interpolate(v, a, b, f, dt, |x, y, f| x.lerp(y, f)))
+            }
+            ChannelValues::Rotation(v) => Sample::Rotation(
+                self.interpolate(v, a, b, f, dt, |x, y, f| x.slerp(y, f))
+                    .normalize(),
+            ),
+            ChannelValues::Weights(tracks) => Sample::Weights(
+                tracks
+                    .iter()
+                    .map(|track| self.interpolate(track, a, b, f, dt, |x, y, f| x + (y - x) * f))
+                    .collect(),
+            ),
+        })
+    }
+
+    fn keys(&self, t: f32) -> Option<(usize, usize, f32, f32)> {
+        let last = self.times.len().checked_sub(1)?;
+        if t <= self.times[0] {
+            return Some((0, 0, 0.0, 0.0));
+        }
+        if t >= self.times[last] {
+            return Some((last, last, 0.0, 0.0));
+        }
+        let b = self.times.partition_point(|&k| k <= t);
+        let a = b - 1;
+        let dt = self.times[b] - self.times[a];
+        let f = if dt > 0.0 {
+            (t - self.times[a]) / dt
+        } else {
+            0.0
+        };
+        Some((a, b, f, dt))
+    }
+
+    fn interpolate<T>(
+        &self,
+        v: &[T],
+        a: usize,
+        b: usize,
+        f: f32,
+        dt: f32,
+        lerp: impl Fn(T, T, f32) -> T,
+    ) -> T
+    where
+        T: Copy + std::ops::Add<Output = T> + std::ops::Mul<f32, Output = T>,
+    {
+        match self.interpolation {
+            Interpolation::Step => v[a],
+            Interpolation::Linear => lerp(v[a], v[b], f),
+            Interpolation::CubicSpline => {
+                let value = |k: usize| v[k * 3 + 1];
+                if a == b {
+                    return value(a);
+                }
+                let out_tangent = v[a * 3 + 2];
+                let in_tangent = v[b * 3];
+                let f2 = f * f;
+                let f3 = f2 * f;
+                value(a) * (2.0 * f3 - 3.0 * f2 + 1.0)
+                    + out_tangent * ((f3 - 2.0 * f2 + f) * dt)
+                    + value(b) * (-2.0 * f3 + 3.0 * f2)
+                    + in_tangent * ((f3 - f2) * dt)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AnimationClip {
+    pub name: Option<String>,
+    pub duration: f32,
+    pub channels: Vec<Channel>,
+}
+
+impl AnimationClip {
+    pub fn sample(&self, t: f32) -> impl Iterator<Item = (usize, Sample)> + '_ {
+        self.channels
+            .iter()
+            .filter_map(move |c| c.sample(t).map(|s| (c.node, s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(interpolation: Interpolation, times: Vec<f32>, values: Vec<Vec3>) -> Channel {
+        Channel {
+            node: 0,
+            interpolation,
+            times,
+            values: ChannelValues::Translation(values),
+        }
+    }
+
+    fn translation(channel: &Channel, t: f32) -> Vec3 {
+        match channel.sample(t) {
+            Some(Sample::Translation(v)) => v,
+            other => panic!("unexpected sample {other:?}"),
+        }
+    }
+
+    #[test]
+    fn step_holds_the_previous_key() {
+        let c = channel(
+            Interpolation::Step,
+            vec![0.0, 1.0, 2.0],
+            vec![Vec3::ZERO, Vec3::X, Vec3::Y],
+        );
+        assert_eq!(translation(&c, 0.5), Vec3::ZERO);
+        assert_eq!(translation(&c, 1.0), Vec3::X);
+        assert_eq!(translation(&c, 1.99), Vec3::X);
+        assert_eq!(translation(&c, 5.0), Vec3::Y);
+    }
+
+    #[test]
+    fn linear_blends_between_keys_and_clamps_outside() {
+        let c = channel(
+            Interpolation::Linear,
+            vec![1.0, 3.0],
+            vec![Vec3::ZERO, Vec3::new(4.0, 0.0, 0.0)],
+        );
+        assert_eq!(translation(&c, 0.0), Vec3::ZERO);
+        assert!(translation(&c, 1.5).abs_diff_eq(Vec3::new(1.0, 0.0, 0.0), 1e-6));
+        assert!(translation(&c, 2.0).abs_diff_eq(Vec3::new(2.0, 0.0, 0.0), 1e-6));
+        assert_eq!(translation(&c, 10.0), Vec3::new(4.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn linear_rotation_slerps_and_stays_normalized() {
+        let c = Channel {
+            node: 0,
+            interpolation: Interpolation::Linear,
+            times: vec![0.0, 1.0],
+            values: ChannelValues::Rotation(vec![
+                Quat::IDENTITY,
+                Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            ]),
+        };
+        let Some(Sample::Rotation(q)) = c.sample(0.5) else {
+            panic!("expected a rotation");
+        };
+        assert!((q.length() - 1.0).abs() < 1e-6);
+        assert!(q.abs_diff_eq(Quat::from_rotation_y(std::f32::consts::FRAC_PI_4), 1e-5));
+    }
+
+    #[test]
+    fn cubic_spline_hits_keys_and_follows_tangents() {
+        // [in, value, out] per key; a unit slope on both ends makes the curve a straight line.
+        let slope = Vec3::new(2.0, 0.0, 0.0);
+        let c = channel(
+            Interpolation::CubicSpline,
+            vec![0.0, 2.0],
+            vec![
+                Vec3::ZERO,
+                Vec3::ZERO,
+                slope,
+                slope,
+                Vec3::new(4.0, 0.0, 0.0),
+                Vec3::ZERO,
+            ],
+        );
+        assert_eq!(translation(&c, 0.0), Vec3::ZERO);
+        assert_eq!(translation(&c, 2.0), Vec3::new(4.0, 0.0, 0.0));
+        assert!(translation(&c, 0.5).abs_diff_eq(Vec3::new(1.0, 0.0, 0.0), 1e-5));
+        assert!(translation(&c, 1.0).abs_diff_eq(Vec3::new(2.0, 0.0, 0.0), 1e-5));
+
+        // Flat tangents ease in and out: a quarter of the way through lands below a quarter.
+        let eased = channel(
+            Interpolation::CubicSpline,
+            vec![0.0, 1.0],
+            vec![
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::X,
+                Vec3::ZERO,
+            ],
+        );
+        let x = translation(&eased, 0.25).x;
+        assert!((x - 0.15625).abs() < 1e-6);
+    }
+
+    #[test]
+    fn weights_sample_every_track() {
+        let c = Channel {
+            node: 2,
+            interpolation: Interpolation::Linear,
+            times: vec![0.0, 1.0],
+            values: ChannelValues::Weights(vec![vec![0.0, 1.0], vec![1.0, 0.0]]),
+        };
+        assert_eq!(c.sample(0.25), Some(Sample::Weights(vec![0.25, 0.75])));
+    }
+
+    #[test]
+    fn empty_channel_yields_nothing() {
+        let c = channel(Interpolation::Linear, Vec::new(), Vec::new());
+        assert!(c.sample(0.0).is_none());
+        let clip = AnimationClip {
+            name: None,
+            duration: 0.0,
+            channels: vec![c],
+        };
+        assert_eq!(clip.sample(0.0).count(), 0);
+    }
+}
@@ -0,0 +1,139 @@
+use glam::{Mat4, Vec3};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const EMPTY: Self = Self {
+        min: Vec3::splat(f32::INFINITY),
+        max: Vec3::splat(f32::NEG_INFINITY),
+    };
+
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a [f32; 3]>) -> Self {
+        let mut aabb = Self::EMPTY;
+        for p in points {
+            aabb.extend(Vec3::from(*p));
+        }
+        aabb
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    pub fn extend(&mut self, p: Vec3) {
+        self.min = self.min.min(p);
+        self.max = self.max.max(p);
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn ray_distance(&self, origin: Vec3, dir: Vec3) -> Option<f32> {
+        if self.is_empty() {
+            return None;
+        }
+        // An axis the ray runs parallel to only needs the origin inside its slab; its slab
+        // distances would be 0 * inf = NaN when the origin sits on a face.
+        let parallel = dir.cmpeq(Vec3::ZERO);
+        if (parallel & (origin.cmplt(self.min) | origin.cmpgt(self.max))).any() {
+            return None;
+        }
+        let inv = dir.recip();
+        let (t0, t1) = ((self.min - origin) * inv, (self.max - origin) * inv);
+        let near = Vec3::select(parallel, Vec3::NEG_INFINITY, t0.min(t1));
+        let far = Vec3::select(parallel, Vec3::INFINITY, t0.max(t1));
+        let (near, far) = (near.max_element().max(0.0), far.min_element());
+        (near <= far).then_some(near)
+    }
+
+    pub fn transformed(&self, m: Mat4) -> Aabb {
+        if self.is_empty() {
+            return *self;
+        }
+        let mut out = Aabb::EMPTY;
+        for i in 0..8 {
+            let corner = Vec3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+            out.extend(m.transform_point3(corner));
+        }
+        out
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIT: Aabb = Aabb {
+        min: Vec3::ZERO,
+        max: Vec3::ONE,
+    };
+
+    #[test]
+    fn ray_distance_enters_at_the_near_face() {
+        let origin = Vec3::new(0.5, 0.5, -2.0);
+        assert_eq!(UNIT.ray_distance(origin, Vec3::Z), Some(2.0));
+        assert_eq!(UNIT.ray_distance(origin, -Vec3::Z), None);
+        // Starting inside counts as a hit at 0.
+        assert_eq!(UNIT.ray_distance(Vec3::splat(0.5), Vec3::X), Some(0.0));
+        let diagonal = Vec3::ONE.normalize();
+        let t = UNIT.ray_distance(Vec3::splat(-1.0), diagonal).unwrap();
+        assert!((t - 3f32.sqrt()).abs() < 1e-5);
+        assert_eq!(Aabb::EMPTY.ray_distance(origin, Vec3::Z), None);
+    }
+
+    #[test]
+    fn axis_parallel_rays_on_a_face_do_not_become_nan() {
+        // The origin lies on the x = 0 and y = 1 faces while the ray runs along z.
+        for x in [0.0, 1.0] {
+            assert_eq!(
+                UNIT.ray_distance(Vec3::new(x, 1.0, -1.0), Vec3::Z),
+                Some(1.0)
+            );
+        }
+        assert_eq!(UNIT.ray_distance(Vec3::new(0.0, 0.0, -1.0), -Vec3::Z), None);
+        assert_eq!(
+            UNIT.ray_distance(Vec3::new(-0.0, 0.5, -1.0), Vec3::new(-0.0, 0.0, 1.0)),
+            Some(1.0)
+        );
+        // Parallel and outside the slab misses however far the ray goes.
+        assert_eq!(UNIT.ray_distance(Vec3::new(1.5, 0.5, -1.0), Vec3::Z), None);
+    }
+
+    #[test]
+    fn flat_boxes_are_hit_from_their_normal_side() {
+        let flat = Aabb {
+            min: Vec3::new(0.0, 0.0, 0.0),
+            max: Vec3::new(1.0, 0.0, 1.0),
+        };
+        assert_eq!(
+            flat.ray_distance(Vec3::new(0.5, 2.0, 0.5), -Vec3::Y),
+            Some(2.0)
+        );
+    }
+}
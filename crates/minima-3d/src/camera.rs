@@ -0,0 +1,48 @@
+use glam::{Mat4, Vec3};
+
+use crate::depth::DepthMode;
+use crate::lod::LodView;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CameraProjection {
+    Perspective {
+        yfov: f32,
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        xmag: f32,
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Camera {
+    pub name: Option<String>,
+    pub projection: CameraProjection,
+}
+
+impl CameraProjection {
+    // The viewport aspect wins over the authored one so views never stretch.
+    pub fn matrix(&self, aspect: f32, depth: DepthMode) -> Mat4 {
+        match *self {
+            CameraProjection::Perspective {
+                yfov, znear, zfar, ..
+            } => depth.perspective(yfov, aspect, znear, zfar),
+            CameraProjection::Orthographic {
+                ymag, znear, zfar, ..
+            } => depth.orthographic(ymag * aspect, ymag, znear, zfar),
+        }
+    }
+
+    pub fn lod_view(&self, eye: Vec3) -> LodView {
+        match *self {
+            CameraProjection::Perspective { yfov, .. } => LodView::new(eye, yfov),
+            // `ymag` is half the view height.
+            CameraProjection::Orthographic { ymag, .. } => LodView::orthographic(eye, ymag * 2.0),
+        }
+    }
+}
@@ -1,8 +1,61 @@
+use glam::Mat4;
 use wgpu::{
-    Device, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
-    TextureView, TextureViewDescriptor,
+    CompareFunction, Device, Texture, TextureDescriptor, TextureDimension, TextureFormat,
+    TextureUsages, TextureView, TextureViewDescriptor,
 };
 
+// Reverse maps the near plane to 1 and far to 0, which spreads float precision evenly over distance.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum DepthMode {
+    #[default]
+    Standard,
+    Reverse,
+}
+
+impl DepthMode {
+    pub fn compare(self) -> CompareFunction {
+        match self {
+            DepthMode::Standard => CompareFunction::Less,
+            DepthMode::Reverse => CompareFunction::Greater,
+        }
+    }
+
+    pub fn clear_value(self) -> f32 {
+        match self {
+            DepthMode::Standard => 1.0,
+            DepthMode::Reverse => 0.0,
+        }
+    }
+
+    // Right-handed projections into wgpu's 0..1 clip depth, shared by every camera type so they
+    // all agree with the depth test. `far: None` is an infinite far plane.
+    pub fn perspective(self, fov_y: f32, aspect: f32, near: f32, far: Option<f32>) -> Mat4 {
+        match (self, far) {
+            (DepthMode::Standard, Some(far)) => Mat4::perspective_rh(fov_y, aspect, near, far),
+            (DepthMode::Reverse, Some(far)) => Mat4::perspective_rh(fov_y, aspect, far, near),
+            (DepthMode::Standard, None) => Mat4::perspective_infinite_rh(fov_y, aspect, near),
+            (DepthMode::Reverse, None) => {
+                Mat4::perspective_infinite_reverse_rh(fov_y, aspect, near)
+            }
+        }
+    }
+
+    pub fn orthographic(self, half_width: f32, half_height: f32, near: f32, far: f32) -> Mat4 {
+        let (near, far) = match self {
+            DepthMode::Standard => (near, far),
+            DepthMode::Reverse => (far, near),
+        };
+        Mat4::orthographic_rh(
+            -half_width,
+            half_width,
+            -half_height,
+            half_height,
+            near,
+            far,
+        )
+    }
+}
+
 pub fn create_depth(device: &Device, w: u32, h: u32) -> (TextureView, Texture) {
     let tex = device.create_texture(&TextureDescriptor {
         label: Some("depth"),
@@ -0,0 +1,323 @@
+use glam::{Mat4, Vec3};
+use std::collections::HashMap;
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline};
+
+use crate::lod::{LodView, lod_debug_color};
+use crate::model::{Material, Model};
+use crate::pipeline::Layouts;
+use crate::raycast::raycast_model;
+use crate::skin::Pose;
+use crate::texture::GpuTexture;
+use crate::vertex::VertexFormat;
+
+const MAT4_SIZE: u64 = 64;
+const NODE_UNIFORM_SIZE: u64 = MAT4_SIZE + 16;
+
+struct MeshDeform {
+    bg: BindGroup,
+    weights_buf: Option<Buffer>,
+}
+
+pub struct RenderInstance {
+    pub model: Arc<Model>,
+    pub transform: Mat4,
+    pub pose: Pose,
+    pub debug_lods: bool,
+    lods: Vec<usize>,
+    // Per-mesh replacements for the model's own material, e.g. a render target on a monitor.
+    materials: Vec<Option<Arc<Material>>>,
+    node_buf: Buffer,
+    node_bg: BindGroup,
+    node_stride: u64,
+    joint_bufs: Vec<Buffer>,
+    deforms: Vec<Option<MeshDeform>>,
+}
+
+impl RenderInstance {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        layouts: &Layouts,
+        model: Arc<Model>,
+        transform: Mat4,
+    ) -> Self {
+        let align = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let node_stride = NODE_UNIFORM_SIZE.div_ceil(align) * align;
+        let node_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("node_ubo"),
+            size: node_stride * model.meshes.len().max(1) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let node_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("node_bg"),
+            layout: &layouts.model_bgl,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &node_buf,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(NODE_UNIFORM_SIZE),
+                }),
+            }],
+        });
+
+        let joint_bufs: Vec<Buffer> = model
+            .skins
+            .iter()
+            .map(|skin| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("joint_matrices"),
+                    size: MAT4_SIZE * skin.joints.len().max(1) as u64,
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect();
+
+        // Identity, so a mesh whose skin is missing draws in its bind pose rather than vanishing.
+        let placeholder = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("deform_placeholder"),
+            contents: bytemuck::cast_slice(&Mat4::IDENTITY.to_cols_array()),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let deforms = model
+            .meshes
+            .iter()
+            .map(|mesh| {
+                if !mesh.format.needs_deform() {
+                    return None;
+                }
+                let joints = model
+                    .mesh_skin(mesh)
+                    .and(mesh.skin)
+                    .and_then(|s| joint_bufs.get(s))
+                    .unwrap_or(&placeholder);
+                let weights_buf = mesh.morph.as_ref().map(|morph| {
+                    device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("morph_weights"),
+                        size: morph.weights_buffer_size(),
+                        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    })
+                });
+                let deltas = mesh.morph.as_ref().map_or(&placeholder, |m| &m.deltas);
+                let weights = weights_buf.as_ref().unwrap_or(&placeholder);
+                let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("deform_bg"),
+                    layout: &layouts.deform_bgl,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: joints.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: deltas.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: weights.as_entire_binding(),
+                        },
+                    ],
+                });
+                Some(MeshDeform { bg, weights_buf })
+            })
+            .collect();
+
+        let pose = Pose::rest(&model);
+        let lods = vec![0; model.meshes.len()];
+        let materials = vec![None; model.meshes.len()];
+        let instance = Self {
+            model,
+            transform,
+            pose,
+            debug_lods: false,
+            lods,
+            materials,
+            node_buf,
+            node_bg,
+            node_stride,
+            joint_bufs,
+            deforms,
+        };
+        instance.upload(queue);
+        instance
+    }
+
+    pub fn update(&mut self, queue: &Queue) {
+        self.pose.compute_world(&self.model);
+        self.upload(queue);
+    }
+
+    fn upload(&self, queue: &Queue) {
+        let stride = self.node_stride as usize;
+        let mut nodes = vec![0u8; stride * self.model.meshes.len()];
+        for (i, mesh) in self.model.meshes.iter().enumerate() {
+            let mut slot = [0.0f32; NODE_UNIFORM_SIZE as usize / 4];
+            slot[..16].copy_from_slice(&self.mesh_transform(i).to_cols_array());
+            if self.debug_lods {
+                let lod = self.lods[i].min(mesh.lods.len());
+                let [r, g, b] = lod_debug_color(lod);
+                slot[16..].copy_from_slice(&[r, g, b, 0.85]);
+            }
+            nodes[i * stride..i * stride + NODE_UNIFORM_SIZE as usize]
+                .copy_from_slice(bytemuck::cast_slice(&slot));
+        }
+        if !nodes.is_empty() {
+            queue.write_buffer(&self.node_buf, 0, &nodes);
+        }
+
+        for (skin, buf) in self.model.skins.iter().zip(&self.joint_bufs) {
+            if skin.joints.is_empty() {
+                continue;
+            }
+            let joints: Vec<[f32; 16]> = self
+                .pose
+                .joint_matrices(skin)
+                .iter()
+                .map(|m| m.to_cols_array())
+                .collect();
+            queue.write_buffer(buf, 0, bytemuck::cast_slice(&joints));
+        }
+
+        for (mesh, deform) in self.model.meshes.iter().zip(&self.deforms) {
+            let (
+                Some(morph),
+                Some(MeshDeform {
+                    weights_buf: Some(buf),
+                    ..
+                }),
+            ) = (&mesh.morph, deform)
+            else {
+                continue;
+            };
+            let weights = mesh
+                .node
+                .and_then(|n| self.pose.weights.get(n))
+                .map_or(&[][..], Vec::as_slice);
+            queue.write_buffer(buf, 0, &morph.encode_weights(weights));
+        }
+    }
+
+    pub fn mesh_transform(&self, mesh: usize) -> Mat4 {
+        match self
+            .model
+            .meshes
+            .get(mesh)
+            .map(|m| (self.model.mesh_skin(m).is_some(), m.node))
+        {
+            Some((false, Some(node))) => self.transform * self.pose.world[node],
+            _ => self.transform,
+        }
+    }
+
+    pub fn raycast(&self, origin: Vec3, dir: Vec3, max_distance: f32) -> Option<f32> {
+        raycast_model(
+            &self.model,
+            self.transform,
+            &self.pose.world,
+            origin,
+            dir,
+            max_distance,
+        )
+    }
+
+    // Draws `mesh` with `material` instead of the model's; the shared model is left untouched.
+    pub fn set_material(&mut self, mesh: usize, material: Arc<Material>) {
+        if let Some(slot) = self.materials.get_mut(mesh) {
+            *slot = Some(material);
+        }
+    }
+
+    pub fn clear_material(&mut self, mesh: usize) {
+        if let Some(slot) = self.materials.get_mut(mesh) {
+            *slot = None;
+        }
+    }
+
+    // The material `mesh` is drawn with, override first.
+    pub fn material(&self, mesh: usize) -> Option<&Arc<Material>> {
+        if let Some(material) = self.materials.get(mesh)?.as_ref() {
+            return Some(material);
+        }
+        let materials = &self.model.materials;
+        let last = materials.len().checked_sub(1)?;
+        materials.get(self.model.meshes[mesh].material_id.min(last))
+    }
+
+    pub fn lod(&self, mesh: usize) -> usize {
+        self.lods.get(mesh).copied().unwrap_or(0)
+    }
+
+    pub fn lods(&self) -> &[usize] {
+        &self.lods
+    }
+
+    pub fn select_lods(&mut self, view: &LodView) {
+        let mut lods = std::mem::take(&mut self.lods);
+        self.select_lods_into(view, &mut lods);
+        self.lods = lods;
+    }
+
+    // Selection for another view; `lods` carries that view's previous choice for hysteresis.
+    pub fn select_lods_into(&self, view: &LodView, lods: &mut Vec<usize>) {
+        lods.resize(self.model.meshes.len(), 0);
+        for (i, mesh) in self.model.meshes.iter().enumerate() {
+            if mesh.lods.is_empty() {
+                continue;
+            }
+            let screen_size = view.screen_size(&mesh.bounds, self.mesh_transform(i));
+            lods[i] = view.select(&mesh.lods, screen_size, lods[i]);
+        }
+    }
+
+    // `lods` comes from `lods()` or `select_lods_into`; meshes past its end draw at full detail.
+    // Meshes whose material samples `target`, the texture being drawn into, are skipped.
+    pub fn draw(
+        &self,
+        pass: &mut RenderPass<'_>,
+        pipelines: &HashMap<VertexFormat, RenderPipeline>,
+        lods: &[usize],
+        target: Option<&Arc<GpuTexture>>,
+    ) {
+        for (slot, (mesh, deform)) in self.model.meshes.iter().zip(&self.deforms).enumerate() {
+            // Formats without a pipeline yet are skipped rather than panicking mid-pass.
+            let Some(pipeline) = pipelines.get(&mesh.format) else {
+                continue;
+            };
+            let Some(mat) = self.material(slot) else {
+                continue;
+            };
+            if target.is_some_and(|t| mat.samples(t)) {
+                continue;
+            }
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(1, &self.node_bg, &[(slot as u64 * self.node_stride) as u32]);
+            pass.set_bind_group(2, &mat.bind_group, &[]);
+            if let Some(deform) = deform {
+                pass.set_bind_group(3, &deform.bg, &[]);
+            }
+            match lods
+                .get(slot)
+                .and_then(|lod| lod.checked_sub(1))
+                .and_then(|l| mesh.lods.get(l))
+            {
+                Some(lod) => {
+                    let vbuf = lod.vbuf.as_ref().unwrap_or(&mesh.vbuf);
+                    pass.set_vertex_buffer(0, vbuf.slice(..));
+                    pass.set_index_buffer(lod.ibuf.slice(..), lod.index_format);
+                    pass.draw_indexed(0..lod.index_count, 0, 0..1);
+                }
+                None => {
+                    pass.set_vertex_buffer(0, mesh.vbuf.slice(..));
+                    pass.set_index_buffer(mesh.ibuf.slice(..), mesh.index_format);
+                    pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+                }
+            }
+        }
+    }
+}
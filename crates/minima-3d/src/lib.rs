@@ -1,9 +1,46 @@
+pub mod animation;
+pub mod bounds;
+pub mod camera;
 pub mod depth;
+pub mod instance;
+pub mod light;
+pub mod lod;
+pub mod material;
+pub mod mesh;
 pub mod model;
+pub mod morph;
+pub mod node;
+pub mod optimize;
 pub mod pipeline;
+pub mod raycast;
 pub mod render;
+pub mod shader;
+pub mod simplify;
+pub mod skin;
+pub mod texture;
+pub mod vertex;
+pub mod view;
 
-pub use depth::create_depth;
-pub use model::{GpuMesh, Material, Model, Vertex, create_model_ubo};
-pub use pipeline::{Layouts, create_bind_group_layouts, create_pipeline};
+pub use animation::{AnimationClip, Channel, ChannelValues, Interpolation, Sample};
+pub use bounds::Aabb;
+pub use camera::{Camera, CameraProjection};
+pub use depth::{DepthMode, create_depth};
+pub use instance::RenderInstance;
+pub use light::{Light, LightKind};
+pub use lod::{CpuLod, LodLevel, LodView, MeshLod, generate_lods, lod_debug_color};
+pub use material::{FallbackTextures, MaterialParams, MaterialRegistry, TextureTransform};
+pub use mesh::{CpuMesh, create_index_buffer};
+pub use model::{GpuMesh, Material, Model, recommended_xform};
+pub use morph::{GpuMorphTargets, MorphTarget};
+pub use node::{Node, Transform};
+pub use optimize::optimize_mesh;
+pub use pipeline::{
+    Layouts, create_bind_group_layouts, create_camera_ubo, create_clear_pipeline, create_pipeline,
+};
+pub use raycast::{raycast_mesh, raycast_model, raycast_triangle};
 pub use render::Renderer3D;
+pub use simplify::simplify;
+pub use skin::{Pose, Skin};
+pub use texture::GpuTexture;
+pub use vertex::{VertexFormat, VertexStreams};
+pub use view::{CameraView, ClearMode, RenderTarget, ViewRegion, ViewTarget};
@@ -0,0 +1,264 @@
+use glam::{Mat4, Vec3};
+use wgpu::{Buffer, Device, IndexFormat, util::DeviceExt};
+
+use crate::bounds::Aabb;
+use crate::mesh::{CpuMesh, create_index_buffer};
+use crate::optimize::optimize_vertex_cache_indices;
+use crate::simplify::simplify;
+use crate::vertex::VertexStreams;
+
+const LOD_COLORS: [[f32; 3]; 5] = [
+    [0.2, 0.9, 0.2],
+    [0.9, 0.9, 0.2],
+    [1.0, 0.55, 0.1],
+    [0.95, 0.2, 0.2],
+    [0.8, 0.2, 0.9],
+];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LodLevel {
+    pub index_ratio: f32,
+    pub max_error: f32,
+    pub screen_size: f32,
+}
+
+impl LodLevel {
+    pub const DEFAULT_CHAIN: [LodLevel; 3] = [
+        LodLevel {
+            index_ratio: 0.5,
+            max_error: 0.01,
+            screen_size: 0.5,
+        },
+        LodLevel {
+            index_ratio: 0.25,
+            max_error: 0.03,
+            screen_size: 0.25,
+        },
+        LodLevel {
+            index_ratio: 0.1,
+            max_error: 0.08,
+            screen_size: 0.1,
+        },
+    ];
+}
+
+#[derive(Clone, Debug)]
+pub struct CpuLod {
+    pub screen_size: f32,
+    pub vertices: Option<VertexStreams>,
+    pub indices: Vec<u32>,
+}
+
+#[derive(Debug)]
+pub struct MeshLod {
+    pub screen_size: f32,
+    pub vbuf: Option<Buffer>,
+    pub ibuf: Buffer,
+    pub index_count: u32,
+    pub index_format: IndexFormat,
+}
+
+impl MeshLod {
+    pub fn upload(device: &Device, lod: &CpuLod, base_vertex_count: usize) -> Self {
+        let vbuf = lod.vertices.as_ref().map(|streams| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("lod_vbuf"),
+                contents: &streams.interleave(),
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        });
+        let vertex_count = lod
+            .vertices
+            .as_ref()
+            .map_or(base_vertex_count, VertexStreams::len);
+        let (ibuf, index_format) = create_index_buffer(device, &lod.indices, vertex_count);
+        Self {
+            screen_size: lod.screen_size,
+            vbuf,
+            ibuf,
+            index_count: lod.indices.len() as u32,
+            index_format,
+        }
+    }
+}
+
+pub fn generate_lods(mesh: &CpuMesh, levels: &[LodLevel]) -> Vec<CpuLod> {
+    let mut lods = Vec::new();
+    let mut previous = mesh.indices.len();
+    for level in levels {
+        let target = (mesh.indices.len() as f32 * level.index_ratio) as usize / 3 * 3;
+        let source = lods
+            .last()
+            .map_or(&mesh.indices, |lod: &CpuLod| &lod.indices);
+        let mut indices = simplify(mesh.positions(), source, target, level.max_error);
+        if indices.is_empty() || indices.len() * 10 > previous * 9 {
+            break;
+        }
+        optimize_vertex_cache_indices(&mut indices, mesh.vertices.len());
+        previous = indices.len();
+        lods.push(CpuLod {
+            screen_size: level.screen_size,
+            vertices: None,
+            indices,
+        });
+    }
+    lods
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct LodView {
+    pub eye: Vec3,
+    pub projection_scale: f32,
+    pub orthographic: bool,
+    pub hysteresis: f32,
+}
+
+impl LodView {
+    pub fn new(eye: Vec3, fov_y: f32) -> Self {
+        Self {
+            eye,
+            projection_scale: 1.0 / (fov_y * 0.5).tan(),
+            orthographic: false,
+            hysteresis: 0.1,
+        }
+    }
+
+    pub fn orthographic(eye: Vec3, height: f32) -> Self {
+        Self {
+            eye,
+            projection_scale: 2.0 / height,
+            orthographic: true,
+            hysteresis: 0.1,
+        }
+    }
+
+    pub fn screen_size(&self, bounds: &Aabb, xform: Mat4) -> f32 {
+        if bounds.is_empty() {
+            return 0.0;
+        }
+        let world = bounds.transformed(xform);
+        let radius = world.extent().length() * 0.5;
+        if self.orthographic {
+            return radius * self.projection_scale;
+        }
+        let distance = world.center().distance(self.eye);
+        if distance <= radius {
+            return f32::INFINITY;
+        }
+        radius * self.projection_scale / distance
+    }
+
+    pub fn select(&self, lods: &[MeshLod], screen_size: f32, current: usize) -> usize {
+        self.select_by(lods.iter().map(|lod| lod.screen_size), screen_size, current)
+    }
+
+    // `thresholds` are the screen sizes below which each LOD takes over, finest first.
+    pub fn select_by(
+        &self,
+        thresholds: impl Iterator<Item = f32> + Clone,
+        screen_size: f32,
+        current: usize,
+    ) -> usize {
+        let level_at = |scale: f32| {
+            thresholds
+                .clone()
+                .take_while(|&threshold| screen_size < threshold * scale)
+                .count()
+        };
+        let coarser = level_at(1.0 - self.hysteresis);
+        let finer = level_at(1.0 + self.hysteresis);
+        if coarser > current {
+            coarser
+        } else if finer < current {
+            finer
+        } else {
+            current.min(thresholds.count())
+        }
+    }
+}
+
+pub fn lod_debug_color(level: usize) -> [f32; 3] {
+    LOD_COLORS[level.min(LOD_COLORS.len() - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plane(n: u32) -> CpuMesh {
+        let mut positions = Vec::new();
+        for y in 0..=n {
+            for x in 0..=n {
+                positions.push([x as f32, y as f32, 0.0]);
+            }
+        }
+        let mut indices = Vec::new();
+        for y in 0..n {
+            for x in 0..n {
+                let v = y * (n + 1) + x;
+                indices.extend_from_slice(&[v, v + 1, v + n + 2, v, v + n + 2, v + n + 1]);
+            }
+        }
+        CpuMesh::new(VertexStreams::new(positions), indices)
+    }
+
+    #[test]
+    fn lod_chain_shrinks_and_keeps_level_screen_sizes() {
+        let mesh = plane(20);
+        let lods = generate_lods(&mesh, &LodLevel::DEFAULT_CHAIN);
+        assert!(!lods.is_empty());
+        let mut previous = mesh.indices.len();
+        for (lod, level) in lods.iter().zip(&LodLevel::DEFAULT_CHAIN) {
+            assert_eq!(lod.screen_size, level.screen_size);
+            assert!(lod.vertices.is_none());
+            assert!(lod.indices.len() < previous);
+            assert!(lod.indices.len() % 3 == 0);
+            assert!(
+                lod.indices
+                    .iter()
+                    .all(|&i| (i as usize) < mesh.vertices.len())
+            );
+            previous = lod.indices.len();
+        }
+    }
+
+    #[test]
+    fn meshes_that_cannot_shrink_get_no_lods() {
+        let triangle = CpuMesh::new(
+            VertexStreams::new(vec![[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]),
+            vec![0, 1, 2],
+        );
+        assert!(generate_lods(&triangle, &LodLevel::DEFAULT_CHAIN).is_empty());
+    }
+
+    #[test]
+    fn selection_has_hysteresis_around_each_threshold() {
+        let view = LodView::new(Vec3::ZERO, 1.0);
+        let thresholds = [0.5, 0.25, 0.1];
+        let select =
+            |size: f32, current: usize| view.select_by(thresholds.into_iter(), size, current);
+
+        // Dropping a level needs 10% below the threshold, coming back needs 10% above it.
+        assert_eq!(select(0.47, 0), 0);
+        assert_eq!(select(0.44, 0), 1);
+        assert_eq!(select(0.52, 1), 1);
+        assert_eq!(select(0.56, 1), 0);
+        // Large jumps skip levels in one step.
+        assert_eq!(select(0.05, 0), 3);
+        assert_eq!(select(0.9, 3), 0);
+        assert_eq!(select(0.3, 7), 1);
+    }
+
+    #[test]
+    fn screen_size_falls_off_with_distance() {
+        let bounds = Aabb::from_points(&[[-1.0; 3], [1.0; 3]]);
+        let view = LodView::new(Vec3::new(0.0, 0.0, 10.0), std::f32::consts::FRAC_PI_2);
+        let near = view.screen_size(&bounds, Mat4::IDENTITY);
+        let far = view.screen_size(&bounds, Mat4::from_translation(Vec3::new(0.0, 0.0, -10.0)));
+        assert!((near / far - 2.0).abs() < 1e-4);
+        assert_eq!(view.screen_size(&Aabb::EMPTY, Mat4::IDENTITY), 0.0);
+
+        let ortho = LodView::orthographic(Vec3::ZERO, 4.0);
+        assert!((ortho.screen_size(&bounds, Mat4::IDENTITY) - 3f32.sqrt() * 0.5).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,291 @@
+use glam::{Vec2, Vec3, Vec4};
+use std::sync::Arc;
+use wgpu::{
+    BindGroupEntry, BindGroupLayout, BindingResource, Device, Queue, Sampler, SamplerDescriptor,
+    TextureFormat, util::DeviceExt,
+};
+
+use crate::model::Material;
+use crate::texture::GpuTexture;
+
+const CHECKER_SIZE: u32 = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextureTransform {
+    pub offset: Vec2,
+    pub rotation: f32,
+    pub scale: Vec2,
+    pub tex_coord: u32,
+}
+
+impl TextureTransform {
+    pub const IDENTITY: Self = Self {
+        offset: Vec2::ZERO,
+        rotation: 0.0,
+        scale: Vec2::ONE,
+        tex_coord: 0,
+    };
+
+    pub fn rows(&self) -> [[f32; 4]; 2] {
+        let (sin, cos) = self.rotation.sin_cos();
+        [
+            [
+                cos * self.scale.x,
+                sin * self.scale.y,
+                self.offset.x,
+                self.tex_coord as f32,
+            ],
+            [-sin * self.scale.x, cos * self.scale.y, self.offset.y, 0.0],
+        ]
+    }
+
+    pub fn apply(&self, uv: Vec2) -> Vec2 {
+        let [r0, r1] = self.rows();
+        Vec2::new(
+            r0[0] * uv.x + r0[1] * uv.y + r0[2],
+            r1[0] * uv.x + r1[1] * uv.y + r1[2],
+        )
+    }
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MaterialParams {
+    pub base_color_factor: Vec4,
+    pub base_color_transform: TextureTransform,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub emissive_factor: Vec3,
+    pub emissive_strength: f32,
+    pub emissive_transform: TextureTransform,
+    pub unlit: bool,
+    pub clearcoat_factor: f32,
+    pub clearcoat_roughness: f32,
+    pub transmission_factor: f32,
+}
+
+impl MaterialParams {
+    pub const UNIFORM_SIZE: usize = 28;
+
+    pub fn to_uniform(&self) -> [f32; Self::UNIFORM_SIZE] {
+        let mut out = [0.0; Self::UNIFORM_SIZE];
+        out[0..4].copy_from_slice(&self.base_color_factor.to_array());
+        out[4..7].copy_from_slice(&(self.emissive_factor * self.emissive_strength).to_array());
+        let [b0, b1] = self.base_color_transform.rows();
+        out[8..12].copy_from_slice(&b0);
+        out[12..16].copy_from_slice(&b1);
+        let [e0, e1] = self.emissive_transform.rows();
+        out[16..20].copy_from_slice(&e0);
+        out[20..24].copy_from_slice(&e1);
+        out[24] = if self.unlit { 1.0 } else { 0.0 };
+        out[25] = self.clearcoat_factor;
+        out[26] = self.clearcoat_roughness;
+        out[27] = self.transmission_factor;
+        out
+    }
+
+    // Everything that distinguishes two materials, including factors the shader doesn't read.
+    pub fn cache_key(&self) -> Vec<u32> {
+        let mut key: Vec<u32> = self.to_uniform().map(f32::to_bits).to_vec();
+        key.extend([self.metallic_factor, self.roughness_factor].map(f32::to_bits));
+        key
+    }
+}
+
+impl Default for MaterialParams {
+    fn default() -> Self {
+        Self {
+            base_color_factor: Vec4::ONE,
+            base_color_transform: TextureTransform::IDENTITY,
+            // Sources without PBR data (OBJ, the default material) are dielectric.
+            metallic_factor: 0.0,
+            roughness_factor: 1.0,
+            emissive_factor: Vec3::ZERO,
+            emissive_strength: 1.0,
+            emissive_transform: TextureTransform::IDENTITY,
+            unlit: false,
+            clearcoat_factor: 0.0,
+            clearcoat_roughness: 0.0,
+            transmission_factor: 0.0,
+        }
+    }
+}
+
+pub struct FallbackTextures {
+    pub white: Arc<GpuTexture>,
+    pub black: Arc<GpuTexture>,
+    pub flat_normal: Arc<GpuTexture>,
+    pub missing: Arc<GpuTexture>,
+}
+
+impl FallbackTextures {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let solid = |label: &str, format: TextureFormat, px: [u8; 4]| {
+            Arc::new(GpuTexture::from_rgba8(
+                device, queue, label, 1, 1, format, &px,
+            ))
+        };
+
+        let mut checker = Vec::with_capacity((CHECKER_SIZE * CHECKER_SIZE * 4) as usize);
+        for y in 0..CHECKER_SIZE {
+            for x in 0..CHECKER_SIZE {
+                if (x + y) % 2 == 0 {
+                    checker.extend_from_slice(&[255, 0, 255, 255]);
+                } else {
+                    checker.extend_from_slice(&[0, 0, 0, 255]);
+                }
+            }
+        }
+
+        Self {
+            white: solid(
+                "fallback_white",
+                TextureFormat::Rgba8UnormSrgb,
+                [255, 255, 255, 255],
+            ),
+            black: solid(
+                "fallback_black",
+                TextureFormat::Rgba8UnormSrgb,
+                [0, 0, 0, 255],
+            ),
+            flat_normal: solid(
+                "fallback_flat_normal",
+                TextureFormat::Rgba8Unorm,
+                [128, 128, 255, 255],
+            ),
+            missing: Arc::new(GpuTexture::from_rgba8(
+                device,
+                queue,
+                "fallback_missing",
+                CHECKER_SIZE,
+                CHECKER_SIZE,
+                TextureFormat::Rgba8UnormSrgb,
+                &checker,
+            )),
+        }
+    }
+}
+
+pub struct MaterialRegistry {
+    pub fallbacks: FallbackTextures,
+    material_bgl: BindGroupLayout,
+    sampler: Sampler,
+    default_material: Arc<Material>,
+}
+
+impl MaterialRegistry {
+    pub fn new(device: &Device, queue: &Queue, material_bgl: &BindGroupLayout) -> Self {
+        let fallbacks = FallbackTextures::new(device, queue);
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("material_sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            ..Default::default()
+        });
+        let default_material = Arc::new(create_material(
+            device,
+            material_bgl,
+            &sampler,
+            fallbacks.white.clone(),
+            fallbacks.black.clone(),
+            MaterialParams::default(),
+        ));
+        Self {
+            fallbacks,
+            material_bgl: material_bgl.clone(),
+            sampler,
+            default_material,
+        }
+    }
+
+    pub fn default_material(&self) -> Arc<Material> {
+        self.default_material.clone()
+    }
+
+    pub fn material_bgl(&self) -> &BindGroupLayout {
+        &self.material_bgl
+    }
+
+    pub fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    pub fn create_material(&self, device: &Device, base_color: Arc<GpuTexture>) -> Material {
+        self.create_material_with_params(
+            device,
+            base_color,
+            self.fallbacks.black.clone(),
+            MaterialParams::default(),
+        )
+    }
+
+    pub fn create_material_with_params(
+        &self,
+        device: &Device,
+        base_color: Arc<GpuTexture>,
+        emissive: Arc<GpuTexture>,
+        params: MaterialParams,
+    ) -> Material {
+        create_material(
+            device,
+            &self.material_bgl,
+            &self.sampler,
+            base_color,
+            emissive,
+            params,
+        )
+    }
+}
+
+fn create_material(
+    device: &Device,
+    material_bgl: &BindGroupLayout,
+    sampler: &Sampler,
+    base_color: Arc<GpuTexture>,
+    emissive: Arc<GpuTexture>,
+    params: MaterialParams,
+) -> Material {
+    let params_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("material_params"),
+        contents: bytemuck::cast_slice(&params.to_uniform()),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("material_bg"),
+        layout: material_bgl,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&base_color.view),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::Sampler(sampler),
+            },
+            BindGroupEntry {
+                binding: 2,
+                resource: BindingResource::TextureView(&emissive.view),
+            },
+            BindGroupEntry {
+                binding: 3,
+                resource: params_buf.as_entire_binding(),
+            },
+        ],
+    });
+    Material {
+        bind_group,
+        base_color,
+        emissive,
+        params,
+        params_buf,
+    }
+}
@@ -0,0 +1,85 @@
+use wgpu::{Buffer, Device, IndexFormat, util::DeviceExt};
+
+use crate::bounds::Aabb;
+use crate::lod::CpuLod;
+use crate::morph::MorphTarget;
+use crate::vertex::VertexStreams;
+
+#[derive(Clone, Debug)]
+pub struct CpuMesh {
+    pub vertices: VertexStreams,
+    pub indices: Vec<u32>,
+    pub morph_targets: Vec<MorphTarget>,
+    pub lods: Vec<CpuLod>,
+    pub bounds: Aabb,
+}
+
+impl CpuMesh {
+    pub fn new(vertices: VertexStreams, indices: Vec<u32>) -> Self {
+        let bounds = Aabb::from_points(&vertices.positions);
+        Self {
+            vertices,
+            indices,
+            morph_targets: Vec::new(),
+            lods: Vec::new(),
+            bounds,
+        }
+    }
+
+    pub fn positions(&self) -> &[[f32; 3]] {
+        &self.vertices.positions
+    }
+
+    pub fn normals(&self) -> Option<&[[f32; 3]]> {
+        self.vertices.normals.as_deref()
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        self.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]])
+    }
+}
+
+pub fn create_index_buffer(
+    device: &Device,
+    indices: &[u32],
+    vertex_count: usize,
+) -> (Buffer, IndexFormat) {
+    let (contents, format) = index_bytes(indices, vertex_count);
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("mesh_ibuf"),
+        contents: &contents,
+        usage: wgpu::BufferUsages::INDEX,
+    });
+    (buffer, format)
+}
+
+fn index_bytes(indices: &[u32], vertex_count: usize) -> (Vec<u8>, IndexFormat) {
+    if vertex_count <= u16::MAX as usize {
+        let short: Vec<u16> = indices.iter().map(|&i| i as u16).collect();
+        (bytemuck::cast_slice(&short).to_vec(), IndexFormat::Uint16)
+    } else {
+        (bytemuck::cast_slice(indices).to_vec(), IndexFormat::Uint32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_meshes_get_16_bit_indices() {
+        let indices = [0, 1, 65534, 65534, 1, 2];
+        let (bytes, format) = index_bytes(&indices, 65535);
+        assert_eq!(format, IndexFormat::Uint16);
+        let short: &[u16] = bytemuck::cast_slice(&bytes);
+        assert!(short.iter().zip(indices).all(|(&s, i)| s as u32 == i));
+    }
+
+    #[test]
+    fn large_meshes_keep_32_bit_indices() {
+        let indices = [0, 1, 65535];
+        let (bytes, format) = index_bytes(&indices, 65536);
+        assert_eq!(format, IndexFormat::Uint32);
+        assert_eq!(bytemuck::cast_slice::<u8, u32>(&bytes), indices);
+    }
+}
@@ -1,68 +1,78 @@
-use bytemuck::{Pod, Zeroable};
-use glam::Mat4;
-use wgpu::{
-    BindGroup, BindGroupLayout, Buffer, VertexAttribute, VertexBufferLayout, util::DeviceExt,
-};
-
-#[repr(C)]
-#[derive(Copy, Clone, Pod, Zeroable)]
-pub struct Vertex {
-    pub pos: [f32; 3],
-    pub nrm: [f32; 3],
-    pub uv: [f32; 2],
-}
-impl Vertex {
-    pub fn layout() -> VertexBufferLayout<'static> {
-        const ATTRS: &[VertexAttribute] = &wgpu::vertex_attr_array![
-            0 => Float32x3, // pos
-            1 => Float32x3, // nrm
-            2 => Float32x2  // uv
-        ];
-        VertexBufferLayout {
-            array_stride: std::mem::size_of::<Vertex>() as u64,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: ATTRS,
-        }
-    }
-}
+use crate::animation::AnimationClip;
+use crate::bounds::Aabb;
+use crate::camera::Camera;
+use crate::light::Light;
+use crate::lod::MeshLod;
+use crate::material::MaterialParams;
+use crate::mesh::CpuMesh;
+use crate::morph::GpuMorphTargets;
+use crate::node::Node;
+use crate::skin::Skin;
+use crate::texture::GpuTexture;
+use crate::vertex::VertexFormat;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct GpuMesh {
     pub vbuf: wgpu::Buffer,
     pub ibuf: wgpu::Buffer,
     pub index_count: u32,
+    pub index_format: wgpu::IndexFormat,
     pub material_id: usize,
+    pub format: VertexFormat,
+    pub bounds: Aabb,
+    pub cpu: Option<Arc<CpuMesh>>,
+    pub node: Option<usize>,
+    pub skin: Option<usize>,
+    pub morph: Option<GpuMorphTargets>,
+    pub lods: Vec<MeshLod>,
 }
 
 #[derive(Debug)]
 pub struct Material {
     pub bind_group: wgpu::BindGroup,
+    pub base_color: Arc<GpuTexture>,
+    pub emissive: Arc<GpuTexture>,
+    pub params: MaterialParams,
+    pub params_buf: wgpu::Buffer,
+}
+
+impl Material {
+    pub fn samples(&self, texture: &Arc<GpuTexture>) -> bool {
+        Arc::ptr_eq(&self.base_color, texture) || Arc::ptr_eq(&self.emissive, texture)
+    }
 }
 
 #[derive(Debug)]
 pub struct Model {
     pub meshes: Vec<GpuMesh>,
-    pub materials: Vec<Material>,
+    pub materials: Vec<Arc<Material>>,
+    pub nodes: Vec<Node>,
+    pub roots: Vec<usize>,
+    pub skins: Vec<Skin>,
+    pub cameras: Vec<Camera>,
+    pub lights: Vec<Light>,
+    pub animations: Vec<Arc<AnimationClip>>,
+    pub bounds: Aabb,
     pub recommended_xform: glam::Mat4,
 }
 
-pub fn create_model_ubo(
-    device: &wgpu::Device,
-    layout: &BindGroupLayout,
-    model: Mat4,
-) -> (Buffer, BindGroup) {
-    let buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("model_ubo"),
-        contents: bytemuck::cast_slice(&[model.to_cols_array()]),
-        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-    });
-    let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("model_bg"),
-        layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: buf.as_entire_binding(),
-        }],
-    });
-    (buf, bg)
+// Centers the bounds on the origin and scales the largest side to two units.
+pub fn recommended_xform(bounds: &Aabb) -> glam::Mat4 {
+    let scale = 2.0 / bounds.extent().max_element().max(1e-5);
+    glam::Mat4::from_scale(glam::Vec3::splat(scale))
+        * glam::Mat4::from_translation(-bounds.center())
+}
+
+impl Model {
+    // The skin a mesh is deformed by. Meshes with joint attributes but no usable skin render
+    // unskinned at their node instead of collapsing to the origin.
+    pub fn mesh_skin(&self, mesh: &GpuMesh) -> Option<&Skin> {
+        if !mesh.format.is_skinned() {
+            return None;
+        }
+        self.skins
+            .get(mesh.skin?)
+            .filter(|skin| !skin.joints.is_empty())
+    }
 }
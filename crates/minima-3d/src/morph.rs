@@ -0,0 +1,69 @@
+use wgpu::{Buffer, Device, util::DeviceExt};
+
+#[derive(Clone, Debug, Default)]
+pub struct MorphTarget {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+}
+
+#[derive(Debug)]
+pub struct GpuMorphTargets {
+    pub deltas: Buffer,
+    pub target_count: u32,
+    pub vertex_count: u32,
+    pub default_weights: Vec<f32>,
+}
+
+impl GpuMorphTargets {
+    pub fn new(
+        device: &Device,
+        targets: &[MorphTarget],
+        vertex_count: usize,
+        default_weights: Vec<f32>,
+    ) -> Self {
+        let mut deltas = Vec::<[f32; 4]>::with_capacity(targets.len() * vertex_count * 2);
+        for target in targets {
+            for v in 0..vertex_count {
+                let p = target.positions.get(v).copied().unwrap_or_default();
+                let n = target
+                    .normals
+                    .as_ref()
+                    .and_then(|n| n.get(v).copied())
+                    .unwrap_or_default();
+                deltas.push([p[0], p[1], p[2], 0.0]);
+                deltas.push([n[0], n[1], n[2], 0.0]);
+            }
+        }
+        let deltas = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("morph_deltas"),
+            contents: bytemuck::cast_slice(&deltas),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        Self {
+            deltas,
+            target_count: targets.len() as u32,
+            vertex_count: vertex_count as u32,
+            default_weights,
+        }
+    }
+
+    pub fn weights_buffer_size(&self) -> u64 {
+        (8 + 4 * self.target_count.max(1) as u64).div_ceil(16) * 16
+    }
+
+    pub fn encode_weights(&self, weights: &[f32]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.weights_buffer_size() as usize);
+        out.extend_from_slice(&self.target_count.to_le_bytes());
+        out.extend_from_slice(&self.vertex_count.to_le_bytes());
+        for i in 0..self.target_count as usize {
+            let w = weights
+                .get(i)
+                .or_else(|| self.default_weights.get(i))
+                .copied()
+                .unwrap_or(0.0);
+            out.extend_from_slice(&w.to_le_bytes());
+        }
+        out.resize(self.weights_buffer_size() as usize, 0);
+        out
+    }
+}
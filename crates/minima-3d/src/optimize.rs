@@ -0,0 +1,417 @@
+use glam::Vec3;
+use std::collections::HashMap;
+
+use crate::mesh::CpuMesh;
+
+const CACHE_SIZE: usize = 32;
+const OVERDRAW_CACHE_SIZE: usize = 16;
+
+pub fn optimize_mesh(mesh: &mut CpuMesh) {
+    weld_vertices(mesh);
+    optimize_vertex_cache(mesh);
+    optimize_overdraw(mesh);
+    optimize_vertex_fetch(mesh);
+}
+
+pub fn weld_vertices(mesh: &mut CpuMesh) {
+    let mut unique: HashMap<Vec<u8>, u32> = HashMap::new();
+    let mut old_of_new = Vec::new();
+    let mut new_of_old = Vec::with_capacity(mesh.vertices.len());
+    let mut key = Vec::new();
+    for v in 0..mesh.vertices.len() {
+        key.clear();
+        vertex_key(mesh, v, &mut key);
+        let next = old_of_new.len() as u32;
+        let ix = *unique.entry(key.clone()).or_insert(next);
+        if ix == next {
+            old_of_new.push(v);
+        }
+        new_of_old.push(ix);
+    }
+    if old_of_new.len() == mesh.vertices.len() {
+        return;
+    }
+    for i in &mut mesh.indices {
+        *i = new_of_old[*i as usize];
+    }
+    remap_vertices(mesh, &old_of_new);
+}
+
+pub fn optimize_vertex_cache(mesh: &mut CpuMesh) {
+    optimize_vertex_cache_indices(&mut mesh.indices, mesh.vertices.len());
+}
+
+pub fn optimize_vertex_cache_indices(indices: &mut Vec<u32>, vertex_count: usize) {
+    let tri_count = indices.len() / 3;
+    if tri_count == 0 {
+        return;
+    }
+
+    let mut valence = vec![0u32; vertex_count];
+    for &i in indices.iter() {
+        valence[i as usize] += 1;
+    }
+    let mut offsets = vec![0usize; vertex_count + 1];
+    for v in 0..vertex_count {
+        offsets[v + 1] = offsets[v] + valence[v] as usize;
+    }
+    let mut adjacency = vec![0u32; offsets[vertex_count]];
+    let mut fill = offsets.clone();
+    for (t, tri) in indices.chunks_exact(3).enumerate() {
+        for &v in tri {
+            adjacency[fill[v as usize]] = t as u32;
+            fill[v as usize] += 1;
+        }
+    }
+
+    let mut cache_pos: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vertex_scores: Vec<f32> = (0..vertex_count)
+        .map(|v| vertex_score(None, valence[v]))
+        .collect();
+    let mut tri_scores: Vec<f32> = indices
+        .chunks_exact(3)
+        .map(|tri| tri.iter().map(|&v| vertex_scores[v as usize]).sum())
+        .collect();
+    let mut emitted = vec![false; tri_count];
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+    let mut out = Vec::with_capacity(indices.len());
+    let mut cursor = 0;
+    let mut best: Option<usize> = None;
+
+    for _ in 0..tri_count {
+        let tri = match best.take() {
+            Some(t) => t,
+            None => {
+                while emitted[cursor] {
+                    cursor += 1;
+                }
+                cursor
+            }
+        };
+        emitted[tri] = true;
+        let verts = [indices[tri * 3], indices[tri * 3 + 1], indices[tri * 3 + 2]];
+        out.extend_from_slice(&verts);
+
+        for &v in &verts {
+            valence[v as usize] -= 1;
+        }
+        cache.retain(|v| !verts.contains(v));
+        for (i, &v) in verts.iter().enumerate() {
+            cache.insert(i, v);
+        }
+        let evicted = cache.split_off(cache.len().min(CACHE_SIZE));
+
+        for &v in &evicted {
+            cache_pos[v as usize] = None;
+        }
+        for (pos, &v) in cache.iter().enumerate() {
+            cache_pos[v as usize] = Some(pos);
+        }
+
+        for &v in cache.iter().chain(&evicted) {
+            let v = v as usize;
+            let score = vertex_score(cache_pos[v], valence[v]);
+            let delta = score - vertex_scores[v];
+            vertex_scores[v] = score;
+            for &t in &adjacency[offsets[v]..offsets[v + 1]] {
+                tri_scores[t as usize] += delta;
+            }
+        }
+        let mut best_score = f32::NEG_INFINITY;
+        for &v in &cache {
+            let v = v as usize;
+            for &t in &adjacency[offsets[v]..offsets[v + 1]] {
+                let t = t as usize;
+                if !emitted[t] && tri_scores[t] > best_score {
+                    best_score = tri_scores[t];
+                    best = Some(t);
+                }
+            }
+        }
+    }
+    *indices = out;
+}
+
+pub fn optimize_overdraw(mesh: &mut CpuMesh) {
+    let tri_count = mesh.indices.len() / 3;
+    if tri_count < 2 {
+        return;
+    }
+
+    let mut clusters = vec![0usize];
+    let mut cache: Vec<u32> = Vec::with_capacity(OVERDRAW_CACHE_SIZE);
+    for (t, tri) in mesh.indices.chunks_exact(3).enumerate() {
+        let mut misses = 0;
+        for &v in tri {
+            if !cache.contains(&v) {
+                misses += 1;
+                cache.insert(0, v);
+                cache.truncate(OVERDRAW_CACHE_SIZE);
+            }
+        }
+        if misses == 3 && t > 0 {
+            clusters.push(t);
+        }
+    }
+    if clusters.len() < 2 {
+        return;
+    }
+    clusters.push(tri_count);
+
+    let positions = &mesh.vertices.positions;
+    let vertex = |i: u32| Vec3::from(positions[i as usize]);
+    let mut mesh_centroid = Vec3::ZERO;
+    let mut mesh_area = 0.0;
+    let mut stats = Vec::with_capacity(clusters.len() - 1);
+    for range in clusters.windows(2) {
+        let mut centroid = Vec3::ZERO;
+        let mut normal = Vec3::ZERO;
+        let mut area = 0.0;
+        for tri in mesh.indices[range[0] * 3..range[1] * 3].chunks_exact(3) {
+            let (a, b, c) = (vertex(tri[0]), vertex(tri[1]), vertex(tri[2]));
+            let n = (b - a).cross(c - a);
+            let tri_area = n.length();
+            centroid += (a + b + c) / 3.0 * tri_area;
+            normal += n;
+            area += tri_area;
+        }
+        mesh_centroid += centroid;
+        mesh_area += area;
+        stats.push((
+            range[0],
+            range[1],
+            centroid / area.max(f32::EPSILON),
+            normal,
+        ));
+    }
+    mesh_centroid /= mesh_area.max(f32::EPSILON);
+    let mut sorted: Vec<(f32, usize, usize)> = stats
+        .into_iter()
+        .map(|(start, end, centroid, normal)| {
+            let facing = (centroid - mesh_centroid).dot(normal.normalize_or_zero());
+            (facing, start, end)
+        })
+        .collect();
+    sorted.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut out = Vec::with_capacity(mesh.indices.len());
+    for (_, start, end) in sorted {
+        out.extend_from_slice(&mesh.indices[start * 3..end * 3]);
+    }
+    mesh.indices = out;
+}
+
+pub fn optimize_vertex_fetch(mesh: &mut CpuMesh) {
+    let mut new_of_old = vec![u32::MAX; mesh.vertices.len()];
+    let mut old_of_new = Vec::with_capacity(mesh.vertices.len());
+    for i in &mut mesh.indices {
+        let old = *i as usize;
+        if new_of_old[old] == u32::MAX {
+            new_of_old[old] = old_of_new.len() as u32;
+            old_of_new.push(old);
+        }
+        *i = new_of_old[old];
+    }
+    remap_vertices(mesh, &old_of_new);
+}
+
+fn vertex_score(cache_pos: Option<usize>, valence: u32) -> f32 {
+    if valence == 0 {
+        return -1.0;
+    }
+    let cache_score = match cache_pos {
+        Some(pos) if pos < 3 => 0.75,
+        Some(pos) => (1.0 - (pos - 3) as f32 / (CACHE_SIZE - 3) as f32).powf(1.5),
+        None => 0.0,
+    };
+    cache_score + 2.0 / (valence as f32).sqrt()
+}
+
+fn vertex_key(mesh: &CpuMesh, v: usize, key: &mut Vec<u8>) {
+    let s = &mesh.vertices;
+    key.extend_from_slice(bytemuck::cast_slice(&s.positions[v]));
+    push_attr(key, &s.normals, v);
+    push_attr(key, &s.uv0, v);
+    push_attr(key, &s.colors, v);
+    push_attr(key, &s.uv1, v);
+    push_attr(key, &s.tangents, v);
+    push_attr(key, &s.joints, v);
+    push_attr(key, &s.weights, v);
+    for target in &mesh.morph_targets {
+        if let Some(p) = target.positions.get(v) {
+            key.extend_from_slice(bytemuck::cast_slice(p));
+        }
+        push_attr(key, &target.normals, v);
+    }
+}
+
+fn push_attr<T: bytemuck::Pod>(key: &mut Vec<u8>, stream: &Option<Vec<T>>, v: usize) {
+    if let Some(value) = stream.as_ref().and_then(|s| s.get(v)) {
+        key.extend_from_slice(bytemuck::bytes_of(value));
+    }
+}
+
+fn remap_vertices(mesh: &mut CpuMesh, old_of_new: &[usize]) {
+    let s = &mut mesh.vertices;
+    remap(&mut s.positions, old_of_new);
+    remap_opt(&mut s.normals, old_of_new);
+    remap_opt(&mut s.uv0, old_of_new);
+    remap_opt(&mut s.colors, old_of_new);
+    remap_opt(&mut s.uv1, old_of_new);
+    remap_opt(&mut s.tangents, old_of_new);
+    remap_opt(&mut s.joints, old_of_new);
+    remap_opt(&mut s.weights, old_of_new);
+    for target in &mut mesh.morph_targets {
+        remap(&mut target.positions, old_of_new);
+        remap_opt(&mut target.normals, old_of_new);
+    }
+}
+
+fn remap<T: Copy + Default>(stream: &mut Vec<T>, old_of_new: &[usize]) {
+    *stream = old_of_new
+        .iter()
+        .map(|&old| stream.get(old).copied().unwrap_or_default())
+        .collect();
+}
+
+fn remap_opt<T: Copy + Default>(stream: &mut Option<Vec<T>>, old_of_new: &[usize]) {
+    if let Some(stream) = stream {
+        remap(stream, old_of_new);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morph::MorphTarget;
+    use crate::vertex::VertexStreams;
+
+    // An unindexed n x n grid: every corner is duplicated per triangle, with skin and morph
+    // streams that vary per position so a wrong remap shows up in the corner keys.
+    fn grid(n: u32) -> CpuMesh {
+        let mut positions = Vec::new();
+        for y in 0..n {
+            for x in 0..n {
+                let p = |dx: u32, dy: u32| [(x + dx) as f32, (y + dy) as f32, 0.0];
+                for corner in [p(0, 0), p(1, 0), p(1, 1), p(0, 0), p(1, 1), p(0, 1)] {
+                    positions.push(corner);
+                }
+            }
+        }
+        let mut streams = VertexStreams::new(positions.clone());
+        streams.normals = Some(vec![[0.0, 0.0, 1.0]; positions.len()]);
+        streams.uv0 = Some(positions.iter().map(|p| [p[0] / n as f32, p[1]]).collect());
+        streams.joints = Some(
+            positions
+                .iter()
+                .map(|p| [p[0] as u16, p[1] as u16, 0, 0])
+                .collect(),
+        );
+        streams.weights = Some(
+            positions
+                .iter()
+                .map(|p| [0.25 + p[0] * 0.01, 0.75 - p[0] * 0.01, 0.0, 0.0])
+                .collect(),
+        );
+        let indices = (0..positions.len() as u32).collect();
+        let mut mesh = CpuMesh::new(streams, indices);
+        mesh.morph_targets = vec![MorphTarget {
+            positions: positions.iter().map(|p| [0.0, 0.0, p[0] * p[1]]).collect(),
+            normals: Some(positions.iter().map(|p| [p[1], 0.0, 0.0]).collect()),
+        }];
+        mesh
+    }
+
+    // Each triangle as the full attribute bytes of its corners, rotated to a canonical start so
+    // winding is kept but the first corner may move.
+    fn corner_triangles(mesh: &CpuMesh) -> Vec<[Vec<u8>; 3]> {
+        let mut tris: Vec<[Vec<u8>; 3]> = mesh
+            .triangles()
+            .map(|tri| {
+                let mut corners = tri.map(|v| {
+                    let mut key = Vec::new();
+                    vertex_key(mesh, v as usize, &mut key);
+                    key
+                });
+                let first = (0..3).min_by_key(|&i| corners[i].clone()).unwrap();
+                corners.rotate_left(first);
+                corners
+            })
+            .collect();
+        tris.sort();
+        tris
+    }
+
+    fn acmr(indices: &[u32]) -> f32 {
+        let mut cache: Vec<u32> = Vec::new();
+        let mut misses = 0;
+        for &v in indices {
+            if !cache.contains(&v) {
+                misses += 1;
+                cache.insert(0, v);
+                cache.truncate(CACHE_SIZE);
+            }
+        }
+        misses as f32 / (indices.len() / 3) as f32
+    }
+
+    #[test]
+    fn weld_merges_identical_corners_only() {
+        let mut mesh = grid(4);
+        let before = corner_triangles(&mesh);
+        weld_vertices(&mut mesh);
+        assert_eq!(mesh.vertices.len(), 25);
+        assert_eq!(mesh.morph_targets[0].positions.len(), 25);
+        assert_eq!(corner_triangles(&mesh), before);
+
+        // Corners that differ only in a morph delta must stay apart.
+        let mut mesh = grid(1);
+        mesh.morph_targets[0].positions[3][2] = 1.0;
+        weld_vertices(&mut mesh);
+        assert_eq!(mesh.vertices.len(), 5);
+    }
+
+    #[test]
+    fn vertex_cache_order_keeps_triangles_and_lowers_misses() {
+        let mut mesh = grid(16);
+        weld_vertices(&mut mesh);
+        // Scatter the triangles so the authored order is cache-hostile.
+        let tris: Vec<[u32; 3]> = mesh.triangles().collect();
+        mesh.indices = (0..tris.len())
+            .flat_map(|i| tris[i * 97 % tris.len()])
+            .collect();
+        let before = corner_triangles(&mesh);
+        let scattered = acmr(&mesh.indices);
+        optimize_vertex_cache(&mut mesh);
+        assert_eq!(corner_triangles(&mesh), before);
+        assert!(acmr(&mesh.indices) < scattered * 0.75);
+    }
+
+    #[test]
+    fn vertex_fetch_orders_vertices_by_first_use() {
+        let mut mesh = grid(6);
+        weld_vertices(&mut mesh);
+        mesh.indices.reverse();
+        let before = corner_triangles(&mesh);
+        optimize_vertex_fetch(&mut mesh);
+        assert_eq!(corner_triangles(&mesh), before);
+        let mut next = 0;
+        for &i in &mesh.indices {
+            assert!(i <= next);
+            if i == next {
+                next += 1;
+            }
+        }
+        assert_eq!(next as usize, mesh.vertices.len());
+        assert_eq!(mesh.vertices.joints.as_ref().unwrap().len(), next as usize);
+    }
+
+    #[test]
+    fn full_optimization_preserves_every_triangle() {
+        let mut mesh = grid(12);
+        let before = corner_triangles(&mesh);
+        optimize_mesh(&mut mesh);
+        assert_eq!(corner_triangles(&mesh), before);
+        assert_eq!(mesh.vertices.len(), 13 * 13);
+    }
+}
@@ -1,18 +1,22 @@
 use std::borrow::Cow;
 use wgpu::{
     BindGroup, BindGroupLayout, BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType,
-    ColorTargetState, CompareFunction, DepthBiasState, DepthStencilState, Device, FragmentState,
-    MultisampleState, PipelineLayoutDescriptor, PrimitiveState, PrimitiveTopology, RenderPipeline,
+    ColorTargetState, DepthBiasState, DepthStencilState, Device, FragmentState, MultisampleState,
+    PipelineLayoutDescriptor, PrimitiveState, PrimitiveTopology, RenderPipeline,
     RenderPipelineDescriptor, ShaderModuleDescriptor, ShaderSource, ShaderStages, TextureFormat,
     TextureSampleType, TextureViewDimension, VertexState,
 };
 
-use crate::model::Vertex;
+use crate::depth::DepthMode;
+use crate::shader::preprocess;
+use crate::vertex::VertexFormat;
 
 pub struct Layouts {
     pub camera_bgl: BindGroupLayout,
     pub model_bgl: BindGroupLayout,
     pub material_bgl: BindGroupLayout,
+    pub deform_bgl: BindGroupLayout,
+    pub clear_bgl: BindGroupLayout,
 }
 
 pub fn create_bind_group_layouts(device: &Device) -> Layouts {
@@ -20,7 +24,20 @@ pub fn create_bind_group_layouts(device: &Device) -> Layouts {
         label: Some("camera_bgl"),
         entries: &[BindGroupLayoutEntry {
             binding: 0,
-            visibility: ShaderStages::VERTEX,
+            visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+    let clear_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("clear_bgl"),
+        entries: &[BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
             ty: BindingType::Buffer {
                 ty: BufferBindingType::Uniform,
                 has_dynamic_offset: false,
@@ -33,10 +50,10 @@ pub fn create_bind_group_layouts(device: &Device) -> Layouts {
         label: Some("model_bgl"),
         entries: &[BindGroupLayoutEntry {
             binding: 0,
-            visibility: ShaderStages::VERTEX,
+            visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
             ty: BindingType::Buffer {
                 ty: BufferBindingType::Uniform,
-                has_dynamic_offset: false,
+                has_dynamic_offset: true,
                 min_binding_size: None,
             },
             count: None,
@@ -61,28 +78,55 @@ pub fn create_bind_group_layouts(device: &Device) -> Layouts {
                 ty: BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                 count: None,
             },
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2,
+                    sample_type: TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 3,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
         ],
     });
+    let storage = |binding| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::VERTEX,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    let deform_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("deform_bgl"),
+        entries: &[storage(0), storage(1), storage(2)],
+    });
     Layouts {
         camera_bgl,
         model_bgl,
         material_bgl,
+        deform_bgl,
+        clear_bgl,
     }
 }
 
-pub fn create_pipeline(
-    device: &Device,
-    swap_chain_format: TextureFormat,
-    layouts: &Layouts,
-) -> (RenderPipeline, BindGroup, Buffer, BindGroupLayout) {
-    let shader = device.create_shader_module(ShaderModuleDescriptor {
-        label: None,
-        source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("../shader.wgsl"))),
-    });
-
+pub fn create_camera_ubo(device: &Device, layouts: &Layouts) -> (Buffer, BindGroup) {
     let camera_buf = device.create_buffer(&wgpu::BufferDescriptor {
         label: Some("camera_ubo"),
-        size: 64,
+        size: 80,
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         mapped_at_creation: false,
     });
@@ -94,24 +138,47 @@ pub fn create_pipeline(
             resource: camera_buf.as_entire_binding(),
         }],
     });
+    (camera_buf, camera_bg)
+}
 
+pub fn create_pipeline(
+    device: &Device,
+    swap_chain_format: TextureFormat,
+    layouts: &Layouts,
+    vertex_format: VertexFormat,
+    depth_mode: DepthMode,
+) -> RenderPipeline {
+    let source = preprocess(
+        include_str!("../shader.wgsl"),
+        &vertex_format.shader_defines(),
+    );
+    let shader = device.create_shader_module(ShaderModuleDescriptor {
+        label: None,
+        source: ShaderSource::Wgsl(Cow::Owned(source)),
+    });
+
+    let mut bind_group_layouts = vec![
+        &layouts.camera_bgl,
+        &layouts.model_bgl,
+        &layouts.material_bgl,
+    ];
+    if vertex_format.needs_deform() {
+        bind_group_layouts.push(&layouts.deform_bgl);
+    }
     let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
         label: Some("pipeline_layout"),
-        bind_group_layouts: &[
-            &layouts.camera_bgl,
-            &layouts.model_bgl,
-            &layouts.material_bgl,
-        ],
+        bind_group_layouts: &bind_group_layouts,
         push_constant_ranges: &[],
     });
 
-    let rp = device.create_render_pipeline(&RenderPipelineDescriptor {
+    let attributes = vertex_format.attributes();
+    device.create_render_pipeline(&RenderPipelineDescriptor {
         label: None,
         layout: Some(&layout),
         vertex: VertexState {
             module: &shader,
             entry_point: Some("vs_main"),
-            buffers: &[Vertex::layout()],
+            buffers: &[vertex_format.layout(&attributes)],
             compilation_options: Default::default(),
         },
         fragment: Some(FragmentState {
@@ -132,14 +199,66 @@ pub fn create_pipeline(
         depth_stencil: Some(DepthStencilState {
             format: TextureFormat::Depth32Float,
             depth_write_enabled: true,
-            depth_compare: CompareFunction::Less,
+            depth_compare: depth_mode.compare(),
             stencil: Default::default(),
             bias: DepthBiasState::default(),
         }),
         multisample: MultisampleState::default(),
         multiview: None,
         cache: None,
-    });
+    })
+}
 
-    (rp, camera_bg, camera_buf, layouts.model_bgl.clone())
+// `write_color` false gives a depth-only clear that keeps whatever is already in the color target.
+pub fn create_clear_pipeline(
+    device: &Device,
+    swap_chain_format: TextureFormat,
+    layouts: &Layouts,
+    write_color: bool,
+) -> RenderPipeline {
+    let shader = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("clear_shader"),
+        source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("../clear.wgsl"))),
+    });
+    let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("clear_pipeline_layout"),
+        bind_group_layouts: &[&layouts.clear_bgl],
+        push_constant_ranges: &[],
+    });
+    let write_mask = if write_color {
+        wgpu::ColorWrites::ALL
+    } else {
+        wgpu::ColorWrites::empty()
+    };
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("clear_pipeline"),
+        layout: Some(&layout),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(ColorTargetState {
+                format: swap_chain_format,
+                blend: None,
+                write_mask,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: PrimitiveState::default(),
+        depth_stencil: Some(DepthStencilState {
+            format: TextureFormat::Depth32Float,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: Default::default(),
+            bias: DepthBiasState::default(),
+        }),
+        multisample: MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
 }
@@ -0,0 +1,143 @@
+use glam::{Mat4, Vec3};
+
+use crate::model::{GpuMesh, Model};
+
+// Only front faces (counter-clockwise, as rendered with back-face culling) are hit,
+// so rays leaving a mesh from inside pass through it.
+pub fn raycast_triangle(origin: Vec3, dir: Vec3, [a, b, c]: [Vec3; 3]) -> Option<f32> {
+    let (ab, ac) = (b - a, c - a);
+    let p = dir.cross(ac);
+    let det = ab.dot(p);
+    if det <= f32::EPSILON {
+        return None;
+    }
+    let to_origin = origin - a;
+    let u = to_origin.dot(p) / det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = to_origin.cross(ab);
+    let v = dir.dot(q) / det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = ac.dot(q) / det;
+    (t >= 0.0).then_some(t)
+}
+
+pub fn raycast_mesh(
+    mesh: &GpuMesh,
+    xform: Mat4,
+    origin: Vec3,
+    dir: Vec3,
+    max_distance: f32,
+) -> Option<f32> {
+    let inverse = xform.inverse();
+    let local_origin = inverse.transform_point3(origin);
+    let local_dir = inverse.transform_vector3(dir);
+    // Distances are measured in world space so scaled meshes compare correctly.
+    let to_world = |t: f32| xform.transform_vector3(local_dir * t).length();
+
+    let bounds_t = mesh.bounds.ray_distance(local_origin, local_dir)?;
+    if to_world(bounds_t) > max_distance {
+        return None;
+    }
+    let Some(cpu) = &mesh.cpu else {
+        return Some(to_world(bounds_t));
+    };
+
+    let mirrored = xform.determinant() < 0.0;
+    let positions = &cpu.vertices.positions;
+    let mut nearest: Option<f32> = None;
+    for tri in cpu.indices.chunks_exact(3) {
+        let mut corners: [Vec3; 3] = std::array::from_fn(|k| positions[tri[k] as usize].into());
+        if mirrored {
+            corners.swap(1, 2);
+        }
+        if let Some(t) = raycast_triangle(local_origin, local_dir, corners)
+            && nearest.is_none_or(|n| t < n)
+        {
+            nearest = Some(t);
+        }
+    }
+    nearest.map(to_world).filter(|&t| t <= max_distance)
+}
+
+// `world` holds the node world matrices of the pose the model is drawn with.
+pub fn raycast_model(
+    model: &Model,
+    transform: Mat4,
+    world: &[Mat4],
+    origin: Vec3,
+    dir: Vec3,
+    max_distance: f32,
+) -> Option<f32> {
+    let dir = dir.try_normalize()?;
+    let mut nearest = max_distance;
+    let mut hit = false;
+    for mesh in &model.meshes {
+        let xform = match (model.mesh_skin(mesh), mesh.node) {
+            (None, Some(node)) => transform * world[node],
+            _ => transform,
+        };
+        if let Some(t) = raycast_mesh(mesh, xform, origin, dir, nearest) {
+            nearest = t;
+            hit = true;
+        }
+    }
+    hit.then_some(nearest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counter-clockwise when seen from +z.
+    const TRIANGLE: [Vec3; 3] = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    ];
+
+    #[test]
+    fn front_face_hits_report_the_distance() {
+        let origin = Vec3::new(0.25, 0.25, 3.0);
+        assert_eq!(raycast_triangle(origin, -Vec3::Z, TRIANGLE), Some(3.0));
+        // The direction does not need to be normalized; t is in its units.
+        assert_eq!(
+            raycast_triangle(origin, -Vec3::Z * 2.0, TRIANGLE),
+            Some(1.5)
+        );
+        let oblique = Vec3::new(-0.75, -0.75, -1.0);
+        assert_eq!(raycast_triangle(Vec3::ONE, oblique, TRIANGLE), Some(1.0));
+    }
+
+    #[test]
+    fn back_faces_edges_and_misses() {
+        let below = Vec3::new(0.25, 0.25, -3.0);
+        assert_eq!(raycast_triangle(below, Vec3::Z, TRIANGLE), None);
+        // Behind the origin.
+        assert_eq!(
+            raycast_triangle(Vec3::new(0.25, 0.25, 3.0), Vec3::Z, TRIANGLE),
+            None
+        );
+        // Outside the hypotenuse, and parallel to the plane.
+        assert_eq!(
+            raycast_triangle(Vec3::new(0.6, 0.6, 1.0), -Vec3::Z, TRIANGLE),
+            None
+        );
+        assert_eq!(
+            raycast_triangle(Vec3::new(-1.0, 0.25, 0.0), Vec3::X, TRIANGLE),
+            None
+        );
+        // Vertices and edges are inclusive.
+        assert_eq!(
+            raycast_triangle(Vec3::new(0.0, 0.0, 1.0), -Vec3::Z, TRIANGLE),
+            Some(1.0)
+        );
+        assert_eq!(
+            raycast_triangle(Vec3::new(0.5, 0.5, 1.0), -Vec3::Z, TRIANGLE),
+            Some(1.0)
+        );
+    }
+}
@@ -1,71 +1,150 @@
-use crate::depth::create_depth;
-use crate::model::{Model, create_model_ubo};
-use crate::pipeline::{Layouts, create_pipeline};
+use crate::depth::{DepthMode, create_depth};
+use crate::instance::RenderInstance;
+use crate::model::Model;
+use crate::pipeline::{Layouts, create_clear_pipeline, create_pipeline};
+use crate::vertex::VertexFormat;
+use crate::view::{CameraView, ClearMode, ViewTarget};
+use glam::{Mat4, Vec3};
+use std::collections::HashMap;
+use std::sync::Arc;
 use wgpu::*;
 
 pub struct Renderer3D {
-    pub render_pipeline: RenderPipeline,
+    pub pipelines: HashMap<VertexFormat, RenderPipeline>,
     pub depth_view: TextureView,
     pub depth_tex: Texture,
-    pub camera_bg: BindGroup,
-    pub camera_buf: Buffer,
-    pub model_bg: BindGroup,
-    pub model_buf: Buffer,
-    pub model: Model,
+    pub main_view: CameraView,
+    pub instance: RenderInstance,
+    pub depth_mode: DepthMode,
+    clear_color: RenderPipeline,
+    clear_depth: RenderPipeline,
+    surface_format: TextureFormat,
+    width: u32,
+    height: u32,
 }
 
 impl Renderer3D {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         device: &Device,
-        _queue: &Queue,
+        queue: &Queue,
         surface_format: TextureFormat,
         width: u32,
         height: u32,
-        model: Model,
+        model: Arc<Model>,
         model_xform: glam::Mat4,
         layouts: &Layouts,
+        depth_mode: DepthMode,
     ) -> Self {
         let (depth_view, depth_tex) = create_depth(device, width, height);
 
-        let (render_pipeline, camera_bg, camera_buf, model_bgl) =
-            create_pipeline(device, surface_format, layouts);
+        let main_view = CameraView::new(device, layouts);
 
-        let (model_buf, model_bg) = create_model_ubo(device, &model_bgl, model_xform);
+        let instance = RenderInstance::new(device, queue, layouts, model, model_xform);
 
-        Self {
-            render_pipeline,
+        let mut renderer = Self {
+            pipelines: HashMap::new(),
             depth_view,
             depth_tex,
-            camera_bg,
-            camera_buf,
-            model_bg,
-            model_buf,
-            model,
+            main_view,
+            instance,
+            depth_mode,
+            clear_color: create_clear_pipeline(device, surface_format, layouts, true),
+            clear_depth: create_clear_pipeline(device, surface_format, layouts, false),
+            surface_format,
+            width,
+            height,
+        };
+        renderer.ensure_pipelines(device, layouts);
+        renderer
+    }
+
+    pub fn set_depth_mode(&mut self, device: &Device, layouts: &Layouts, depth_mode: DepthMode) {
+        if self.depth_mode != depth_mode {
+            self.depth_mode = depth_mode;
+            self.pipelines.clear();
+            self.ensure_pipelines(device, layouts);
+        }
+    }
+
+    // Builds a pipeline for every vertex format the current instance uses; call it after swapping
+    // `instance` for a model that may bring new formats. Cheap when nothing is missing.
+    pub fn ensure_pipelines(&mut self, device: &Device, layouts: &Layouts) {
+        for mesh in &self.instance.model.meshes {
+            self.pipelines.entry(mesh.format).or_insert_with(|| {
+                create_pipeline(
+                    device,
+                    self.surface_format,
+                    layouts,
+                    mesh.format,
+                    self.depth_mode,
+                )
+            });
         }
     }
 
+    pub fn surface_format(&self) -> TextureFormat {
+        self.surface_format
+    }
+
     pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
         let (dv, dt) = create_depth(device, width, height);
         self.depth_view = dv;
         self.depth_tex = dt;
+        self.width = width;
+        self.height = height;
+    }
+
+    // The main target pairs an external color view (swapchain or editor viewport) with our depth.
+    pub fn main_target<'a>(&'a self, color: &'a TextureView) -> ViewTarget<'a> {
+        ViewTarget {
+            color,
+            texture: None,
+            depth: &self.depth_view,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    pub fn prepare_view(&self, queue: &Queue, view: &CameraView, view_proj: Mat4, eye: Vec3) {
+        view.write_camera(queue, view_proj, eye);
+        view.write_clear(queue, self.depth_mode.clear_value());
     }
 
     pub fn render(&self, encoder: &mut CommandEncoder, target_view: &TextureView) {
+        self.render_view(encoder, &self.main_target(target_view), &self.main_view);
+    }
+
+    pub fn render_view(
+        &self,
+        encoder: &mut CommandEncoder,
+        target: &ViewTarget,
+        view: &CameraView,
+    ) {
+        let (x, y, w, h) = view.region.pixels(target.width, target.height);
+        let full = (x, y, w, h) == (0, 0, target.width, target.height);
+        let depth_clear = LoadOp::Clear(self.depth_mode.clear_value());
+        // Whole-target views clear through the load op; sub-regions draw a clear triangle instead.
+        let (color_load, depth_load) = match view.clear {
+            ClearMode::Color(color) if full => (LoadOp::Clear(color), depth_clear),
+            ClearMode::DepthOnly if full => (LoadOp::Load, depth_clear),
+            _ => (LoadOp::Load, LoadOp::Load),
+        };
         let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
             label: Some("scene_pass"),
             color_attachments: &[Some(RenderPassColorAttachment {
-                view: target_view,
+                view: target.color,
                 depth_slice: None,
                 resolve_target: None,
                 ops: Operations {
-                    load: LoadOp::Clear(Color::BLACK),
+                    load: color_load,
                     store: StoreOp::Store,
                 },
             })],
             depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                view: &self.depth_view,
+                view: target.depth,
                 depth_ops: Some(Operations {
-                    load: LoadOp::Clear(1.0),
+                    load: depth_load,
                     store: StoreOp::Store,
                 }),
                 stencil_ops: None,
@@ -74,16 +153,28 @@ impl Renderer3D {
             occlusion_query_set: None,
         });
 
-        r_pass.set_pipeline(&self.render_pipeline);
-        r_pass.set_bind_group(0, &self.camera_bg, &[]);
-        r_pass.set_bind_group(1, &self.model_bg, &[]);
-
-        for mesh in &self.model.meshes {
-            let mat = &self.model.materials[mesh.material_id.min(self.model.materials.len() - 1)];
-            r_pass.set_bind_group(2, &mat.bind_group, &[]);
-            r_pass.set_vertex_buffer(0, mesh.vbuf.slice(..));
-            r_pass.set_index_buffer(mesh.ibuf.slice(..), IndexFormat::Uint32);
-            r_pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+        r_pass.set_viewport(x as f32, y as f32, w as f32, h as f32, 0.0, 1.0);
+        r_pass.set_scissor_rect(x, y, w, h);
+        if !full {
+            let clear = match view.clear {
+                ClearMode::Color(_) => Some(&self.clear_color),
+                ClearMode::DepthOnly => Some(&self.clear_depth),
+                ClearMode::Keep => None,
+            };
+            if let Some(pipeline) = clear {
+                r_pass.set_pipeline(pipeline);
+                r_pass.set_bind_group(0, &view.clear_bg, &[]);
+                r_pass.draw(0..3, 0..1);
+            }
         }
+
+        r_pass.set_bind_group(0, &view.camera_bg, &[]);
+        let lods = if view.lods.is_empty() {
+            self.instance.lods()
+        } else {
+            &view.lods
+        };
+        self.instance
+            .draw(&mut r_pass, &self.pipelines, lods, target.texture);
     }
 }
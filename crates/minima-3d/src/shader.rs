@@ -0,0 +1,66 @@
+pub fn preprocess(source: &str, defines: &[&str]) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut stack: Vec<(bool, bool)> = Vec::new();
+    for line in source.lines() {
+        let trimmed = line.trim_start();
+        if let Some(name) = trimmed.strip_prefix("#ifdef ") {
+            let parent = stack.last().is_none_or(|&(active, _)| active);
+            stack.push((parent && defines.contains(&name.trim()), parent));
+        } else if let Some(name) = trimmed.strip_prefix("#ifndef ") {
+            let parent = stack.last().is_none_or(|&(active, _)| active);
+            stack.push((parent && !defines.contains(&name.trim()), parent));
+        } else if trimmed.starts_with("#else") {
+            if let Some((active, parent)) = stack.last_mut() {
+                *active = *parent && !*active;
+            }
+        } else if trimmed.starts_with("#endif") {
+            stack.pop();
+        } else if stack.last().is_none_or(|&(active, _)| active) {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::preprocess;
+
+    const SOURCE: &str = "\
+a
+#ifdef FOO
+foo
+  #ifdef BAR
+foo_bar
+  #else
+foo_not_bar
+  #endif
+#else
+not_foo
+#endif
+#ifndef BAR
+not_bar
+#endif
+z";
+
+    fn lines(defines: &[&str]) -> Vec<String> {
+        preprocess(SOURCE, defines)
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn no_defines_takes_else_and_ifndef_branches() {
+        assert_eq!(lines(&[]), ["a", "not_foo", "not_bar", "z"]);
+    }
+
+    #[test]
+    fn nested_branches_follow_their_parent() {
+        assert_eq!(lines(&["FOO"]), ["a", "foo", "foo_not_bar", "not_bar", "z"]);
+        assert_eq!(lines(&["FOO", "BAR"]), ["a", "foo", "foo_bar", "z"]);
+        // BAR alone must not leak out of the inactive FOO block.
+        assert_eq!(lines(&["BAR"]), ["a", "not_foo", "z"]);
+    }
+}
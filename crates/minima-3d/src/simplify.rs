@@ -0,0 +1,364 @@
+use glam::{DVec3, Vec3};
+use std::collections::HashMap;
+
+// Plane quadrics are weighted by triangle area; `weight` keeps the total so a collapse cost can
+// be normalized back to a squared distance.
+#[derive(Copy, Clone, Default)]
+struct Quadric {
+    q: [f64; 10],
+    weight: f64,
+}
+
+impl Quadric {
+    fn from_plane(n: DVec3, d: f64, weight: f64) -> Self {
+        Self {
+            q: [
+                n.x * n.x * weight,
+                n.x * n.y * weight,
+                n.x * n.z * weight,
+                n.x * d * weight,
+                n.y * n.y * weight,
+                n.y * n.z * weight,
+                n.y * d * weight,
+                n.z * n.z * weight,
+                n.z * d * weight,
+                d * d * weight,
+            ],
+            weight,
+        }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.q.iter_mut().zip(other.q) {
+            *a += b;
+        }
+        self.weight += other.weight;
+    }
+
+    // Mean squared distance from `p` to the accumulated planes.
+    fn error(&self, p: DVec3) -> f64 {
+        if self.weight <= 0.0 {
+            return 0.0;
+        }
+        let q = &self.q;
+        let e = q[0] * p.x * p.x
+            + 2.0 * q[1] * p.x * p.y
+            + 2.0 * q[2] * p.x * p.z
+            + 2.0 * q[3] * p.x
+            + q[4] * p.y * p.y
+            + 2.0 * q[5] * p.y * p.z
+            + 2.0 * q[6] * p.y
+            + q[7] * p.z * p.z
+            + 2.0 * q[8] * p.z
+            + q[9];
+        e.max(0.0) / self.weight
+    }
+}
+
+pub fn simplify(
+    positions: &[[f32; 3]],
+    indices: &[u32],
+    target_index_count: usize,
+    target_error: f32,
+) -> Vec<u32> {
+    let vertex_count = positions.len();
+    let pos = |v: u32| Vec3::from(positions[v as usize]).as_dvec3();
+    let mut indices = indices.to_vec();
+    if indices.len() <= target_index_count || vertex_count == 0 {
+        return indices;
+    }
+
+    // Vertices split only by attributes (UV or normal seams) share a canonical vertex.
+    let mut canonical = vec![0u32; vertex_count];
+    let mut siblings: Vec<Vec<u32>> = vec![Vec::new(); vertex_count];
+    let mut by_position: HashMap<[u32; 3], u32> = HashMap::new();
+    for (v, p) in positions.iter().enumerate() {
+        let c = *by_position.entry(p.map(f32::to_bits)).or_insert(v as u32);
+        canonical[v] = c;
+        siblings[c as usize].push(v as u32);
+    }
+
+    let mut edges: HashMap<(u32, u32), u32> = HashMap::new();
+    for tri in indices.chunks_exact(3) {
+        for k in 0..3 {
+            let (a, b) = (
+                canonical[tri[k] as usize],
+                canonical[tri[(k + 1) % 3] as usize],
+            );
+            *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+    }
+    let mut border = vec![false; vertex_count];
+    for (&(a, b), &count) in &edges {
+        if count == 1 {
+            border[a as usize] = true;
+            border[b as usize] = true;
+        }
+    }
+    let locked: Vec<bool> = (0..vertex_count)
+        .map(|v| border[canonical[v] as usize])
+        .collect();
+
+    let mut quadrics = vec![Quadric::default(); vertex_count];
+    for tri in indices.chunks_exact(3) {
+        let (a, b, c) = (pos(tri[0]), pos(tri[1]), pos(tri[2]));
+        let n = (b - a).cross(c - a);
+        let area = n.length();
+        if area <= 0.0 {
+            continue;
+        }
+        let n = n / area;
+        let q = Quadric::from_plane(n, -n.dot(a), area);
+        for &v in tri {
+            quadrics[v as usize].add(&q);
+        }
+    }
+    let (mut min, mut max) = (DVec3::splat(f64::MAX), DVec3::splat(f64::MIN));
+    for &v in &indices {
+        min = min.min(pos(v));
+        max = max.max(pos(v));
+    }
+    let error_limit = (target_error as f64 * (max - min).max_element()).powi(2);
+
+    loop {
+        if indices.len() <= target_index_count {
+            break;
+        }
+        let mut adjacency: Vec<Vec<u32>> = vec![Vec::new(); vertex_count];
+        for (t, tri) in indices.chunks_exact(3).enumerate() {
+            for &v in tri {
+                adjacency[v as usize].push(t as u32);
+            }
+        }
+
+        // A seam vertex only moves together with its siblings, each onto the matching sibling
+        // of the target along a seam edge, so both sides of the seam stay welded.
+        let collapse_pairs = |from: u32, to: u32| -> Option<Vec<(u32, u32)>> {
+            let group = &siblings[canonical[from as usize] as usize];
+            if group.len() == 1 {
+                return Some(vec![(from, to)]);
+            }
+            let target = canonical[to as usize];
+            if target == canonical[from as usize] {
+                return None;
+            }
+            group
+                .iter()
+                .map(|&s| {
+                    adjacency[s as usize]
+                        .iter()
+                        .flat_map(|&t| &indices[t as usize * 3..t as usize * 3 + 3])
+                        .find(|&&v| canonical[v as usize] == target)
+                        .map(|&t| (s, t))
+                })
+                .collect()
+        };
+
+        let mut candidates = Vec::new();
+        for tri in indices.chunks_exact(3) {
+            for k in 0..3 {
+                let (a, b) = (tri[k], tri[(k + 1) % 3]);
+                for (from, to) in [(a, b), (b, a)] {
+                    if locked[from as usize] {
+                        continue;
+                    }
+                    let Some(pairs) = collapse_pairs(from, to) else {
+                        continue;
+                    };
+                    let mut q = Quadric::default();
+                    for &(s, t) in &pairs {
+                        q.add(&quadrics[s as usize]);
+                        q.add(&quadrics[t as usize]);
+                    }
+                    candidates.push((q.error(pos(to)), pairs));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let needed = (indices.len() - target_index_count).div_ceil(3);
+        let mut removed = 0;
+        let mut touched = vec![false; vertex_count];
+        let mut remap: Vec<u32> = (0..vertex_count as u32).collect();
+        for (cost, pairs) in candidates {
+            if cost > error_limit || removed >= needed {
+                break;
+            }
+            if pairs
+                .iter()
+                .any(|&(from, to)| touched[from as usize] || touched[to as usize])
+            {
+                continue;
+            }
+            let mut collapsing = 0;
+            let flips = pairs.iter().any(|&(from, to)| {
+                let target = pos(to);
+                adjacency[from as usize].iter().any(|&t| {
+                    let tri = &indices[t as usize * 3..t as usize * 3 + 3];
+                    if tri.contains(&to) {
+                        collapsing += 1;
+                        return false;
+                    }
+                    let [a, b, c] = [tri[0], tri[1], tri[2]].map(pos);
+                    let before = (b - a).cross(c - a);
+                    let moved =
+                        [tri[0], tri[1], tri[2]].map(|v| if v == from { target } else { pos(v) });
+                    let after = (moved[1] - moved[0]).cross(moved[2] - moved[0]);
+                    before.dot(after) <= 0.0
+                })
+            });
+            if flips || collapsing == 0 {
+                continue;
+            }
+
+            for &(from, to) in &pairs {
+                remap[from as usize] = to;
+                let q = quadrics[from as usize];
+                quadrics[to as usize].add(&q);
+                for &t in &adjacency[from as usize] {
+                    for &v in &indices[t as usize * 3..t as usize * 3 + 3] {
+                        touched[v as usize] = true;
+                    }
+                }
+            }
+            removed += collapsing;
+        }
+        if removed == 0 {
+            break;
+        }
+
+        let mut next = Vec::with_capacity(indices.len());
+        for tri in indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|v| remap[v as usize]);
+            if a != b && b != c && a != c {
+                next.extend_from_slice(&[a, b, c]);
+            }
+        }
+        indices = next;
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An indexed (n + 1)^2 grid lifted by `height`. With `seam`, the vertices of that column are
+    // duplicated for the cells to its right, like a UV seam.
+    fn grid(
+        n: u32,
+        seam: Option<u32>,
+        height: impl Fn(f32, f32) -> f32,
+    ) -> (Vec<[f32; 3]>, Vec<u32>) {
+        let mut positions = Vec::new();
+        for y in 0..=n {
+            for x in 0..=n {
+                positions.push([x as f32, y as f32, height(x as f32, y as f32)]);
+            }
+        }
+        let mut duplicates = HashMap::new();
+        let mut vertex = |x: u32, y: u32, cell: u32| {
+            let v = y * (n + 1) + x;
+            if seam == Some(x) && cell >= x {
+                *duplicates.entry(v).or_insert_with(|| {
+                    positions.push(positions[v as usize]);
+                    positions.len() as u32 - 1
+                })
+            } else {
+                v
+            }
+        };
+        let mut indices = Vec::new();
+        for y in 0..n {
+            for x in 0..n {
+                let [a, b, c, d] = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)]
+                    .map(|(vx, vy)| vertex(vx, vy, x));
+                indices.extend_from_slice(&[a, b, c, a, c, d]);
+            }
+        }
+        (positions, indices)
+    }
+
+    fn triangle_normals(positions: &[[f32; 3]], indices: &[u32]) -> Vec<Vec3> {
+        indices
+            .chunks_exact(3)
+            .map(|t| {
+                let [a, b, c] = [t[0], t[1], t[2]].map(|v| Vec3::from(positions[v as usize]));
+                (b - a).cross(c - a)
+            })
+            .collect()
+    }
+
+    fn area(positions: &[[f32; 3]], indices: &[u32]) -> f32 {
+        triangle_normals(positions, indices)
+            .iter()
+            .map(|n| n.length() * 0.5)
+            .sum()
+    }
+
+    fn bumps(x: f32, y: f32) -> f32 {
+        (x * 0.7).sin() * (y * 0.5).cos() * 0.6
+    }
+
+    #[test]
+    fn planar_grid_reaches_the_target_without_holes_or_flips() {
+        let (positions, indices) = grid(16, None, |_, _| 0.0);
+        let target = indices.len() / 4;
+        let out = simplify(&positions, &indices, target, 0.001);
+        assert!(out.len() <= target, "{} > {target}", out.len());
+        assert!((area(&positions, &out) - 256.0).abs() < 1e-3);
+        assert!(triangle_normals(&positions, &out).iter().all(|n| n.z > 0.0));
+    }
+
+    #[test]
+    fn error_limit_is_relative_to_mesh_size() {
+        let (positions, indices) = grid(24, None, bumps);
+        let coarse = simplify(&positions, &indices, 0, 0.02);
+        assert!(!coarse.is_empty() && coarse.len() < indices.len() / 2);
+
+        // Scaling by a power of two is exact, so the result must not change at all.
+        let scaled: Vec<[f32; 3]> = positions.iter().map(|p| p.map(|c| c * 64.0)).collect();
+        assert_eq!(simplify(&scaled, &indices, 0, 0.02), coarse);
+
+        let fine = simplify(&positions, &indices, 0, 0.002);
+        assert!(fine.len() > coarse.len());
+    }
+
+    #[test]
+    fn seams_collapse_along_themselves_without_cracking() {
+        let n = 12;
+        let (positions, indices) = grid(n, Some(n / 2), |_, _| 0.0);
+        let out = simplify(&positions, &indices, indices.len() / 6, 0.001);
+
+        let seam_x = (n / 2) as f32;
+        let used_on_seam = |ix: &[u32]| {
+            let mut used: Vec<u32> = ix
+                .iter()
+                .copied()
+                .filter(|&v| positions[v as usize][0] == seam_x)
+                .collect();
+            used.sort();
+            used.dedup();
+            used.len()
+        };
+        assert!(used_on_seam(&out) < used_on_seam(&indices));
+
+        // Every open edge must still be on the outer boundary of the square.
+        let mut edges: HashMap<([u32; 3], [u32; 3]), u32> = HashMap::new();
+        for t in out.chunks_exact(3) {
+            for k in 0..3 {
+                let [a, b] =
+                    [t[k], t[(k + 1) % 3]].map(|v| positions[v as usize].map(f32::to_bits));
+                *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+            }
+        }
+        let on_boundary = |p: [u32; 3]| {
+            let [x, y, _] = p.map(f32::from_bits);
+            x == 0.0 || y == 0.0 || x == n as f32 || y == n as f32
+        };
+        for ((a, b), count) in edges {
+            assert!(count == 2 || (on_boundary(a) && on_boundary(b)));
+        }
+        assert!((area(&positions, &out) - (n * n) as f32).abs() < 1e-3);
+    }
+}
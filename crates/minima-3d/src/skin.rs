@@ -0,0 +1,67 @@
+use glam::Mat4;
+
+use crate::model::Model;
+use crate::node::Transform;
+
+#[derive(Clone, Debug, Default)]
+pub struct Skin {
+    pub name: Option<String>,
+    pub joints: Vec<usize>,
+    pub inverse_bind_matrices: Vec<Mat4>,
+    pub skeleton: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Pose {
+    pub locals: Vec<Transform>,
+    pub weights: Vec<Vec<f32>>,
+    pub world: Vec<Mat4>,
+}
+
+impl Pose {
+    pub fn rest(model: &Model) -> Self {
+        let mut pose = Self {
+            locals: model.nodes.iter().map(|n| n.transform).collect(),
+            weights: model.nodes.iter().map(|n| n.weights.clone()).collect(),
+            world: vec![Mat4::IDENTITY; model.nodes.len()],
+        };
+        pose.compute_world(model);
+        pose
+    }
+
+    pub fn reset(&mut self, model: &Model) {
+        for (local, node) in self.locals.iter_mut().zip(&model.nodes) {
+            *local = node.transform;
+        }
+        for (weights, node) in self.weights.iter_mut().zip(&model.nodes) {
+            weights.clone_from(&node.weights);
+        }
+    }
+
+    pub fn compute_world(&mut self, model: &Model) {
+        let mut stack: Vec<(usize, Mat4)> =
+            model.roots.iter().map(|&r| (r, Mat4::IDENTITY)).collect();
+        while let Some((ix, parent)) = stack.pop() {
+            let world = parent * self.locals[ix].to_matrix();
+            self.world[ix] = world;
+            for &child in &model.nodes[ix].children {
+                stack.push((child, world));
+            }
+        }
+    }
+
+    pub fn joint_matrices(&self, skin: &Skin) -> Vec<Mat4> {
+        skin.joints
+            .iter()
+            .enumerate()
+            .map(|(i, &joint)| {
+                let ibm = skin
+                    .inverse_bind_matrices
+                    .get(i)
+                    .copied()
+                    .unwrap_or(Mat4::IDENTITY);
+                self.world[joint] * ibm
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,50 @@
+use wgpu::{
+    Device, Queue, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureView, TextureViewDescriptor,
+    util::{DeviceExt, TextureDataOrder},
+};
+
+#[derive(Debug)]
+pub struct GpuTexture {
+    pub texture: Texture,
+    pub view: TextureView,
+}
+
+impl GpuTexture {
+    pub fn new(texture: Texture) -> Self {
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        Self { texture, view }
+    }
+
+    pub fn from_rgba8(
+        device: &Device,
+        queue: &Queue,
+        label: &str,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        pixels: &[u8],
+    ) -> Self {
+        Self::new(device.create_texture_with_data(
+            queue,
+            &TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::COPY_SRC,
+                view_formats: &[],
+            },
+            TextureDataOrder::LayerMajor,
+            pixels,
+        ))
+    }
+}
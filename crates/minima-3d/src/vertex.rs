@@ -0,0 +1,296 @@
+use std::ops::{BitOr, BitOrAssign};
+use wgpu::{VertexAttribute, VertexBufferLayout};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VertexFormat(u32);
+
+impl VertexFormat {
+    pub const BASE: Self = Self(0);
+    pub const COLOR_0: Self = Self(1 << 0);
+    pub const TEXCOORD_1: Self = Self(1 << 1);
+    pub const TANGENT: Self = Self(1 << 2);
+    pub const JOINTS_0: Self = Self(1 << 3);
+    pub const WEIGHTS_0: Self = Self(1 << 4);
+    pub const MORPH_TARGETS: Self = Self(1 << 5);
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub const fn from_bits_truncate(bits: u32) -> Self {
+        Self(bits & 0x3f)
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn is_skinned(self) -> bool {
+        self.contains(Self::JOINTS_0) && self.contains(Self::WEIGHTS_0)
+    }
+
+    pub const fn needs_deform(self) -> bool {
+        self.is_skinned() || self.contains(Self::MORPH_TARGETS)
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn stride(self) -> u64 {
+        self.attributes()
+            .last()
+            .map(|a| a.offset + a.format.size())
+            .unwrap_or(0)
+    }
+
+    pub fn attributes(self) -> Vec<VertexAttribute> {
+        let mut attrs = Vec::with_capacity(8);
+        let mut offset = 0;
+        let mut push = |location: u32, format: wgpu::VertexFormat| {
+            attrs.push(VertexAttribute {
+                format,
+                offset,
+                shader_location: location,
+            });
+            offset += format.size();
+        };
+        push(0, wgpu::VertexFormat::Float32x3);
+        push(1, wgpu::VertexFormat::Float32x3);
+        push(2, wgpu::VertexFormat::Float32x2);
+        if self.contains(Self::COLOR_0) {
+            push(3, wgpu::VertexFormat::Float32x4);
+        }
+        if self.contains(Self::TEXCOORD_1) {
+            push(4, wgpu::VertexFormat::Float32x2);
+        }
+        if self.contains(Self::TANGENT) {
+            push(5, wgpu::VertexFormat::Float32x4);
+        }
+        if self.contains(Self::JOINTS_0) {
+            push(6, wgpu::VertexFormat::Uint16x4);
+        }
+        if self.contains(Self::WEIGHTS_0) {
+            push(7, wgpu::VertexFormat::Float32x4);
+        }
+        attrs
+    }
+
+    pub fn layout(self, attributes: &[VertexAttribute]) -> VertexBufferLayout<'_> {
+        VertexBufferLayout {
+            array_stride: self.stride(),
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes,
+        }
+    }
+
+    pub fn shader_defines(self) -> Vec<&'static str> {
+        let mut defines = Vec::new();
+        if self.contains(Self::COLOR_0) {
+            defines.push("HAS_COLOR_0");
+        }
+        if self.contains(Self::TEXCOORD_1) {
+            defines.push("HAS_TEXCOORD_1");
+        }
+        if self.contains(Self::TANGENT) {
+            defines.push("HAS_TANGENT");
+        }
+        if self.contains(Self::JOINTS_0) {
+            defines.push("HAS_JOINTS_0");
+        }
+        if self.contains(Self::WEIGHTS_0) {
+            defines.push("HAS_WEIGHTS_0");
+        }
+        if self.is_skinned() {
+            defines.push("HAS_SKIN");
+        }
+        if self.contains(Self::MORPH_TARGETS) {
+            defines.push("HAS_MORPH");
+        }
+        defines
+    }
+}
+
+impl BitOr for VertexFormat {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for VertexFormat {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct VertexStreams {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub uv0: Option<Vec<[f32; 2]>>,
+    pub colors: Option<Vec<[f32; 4]>>,
+    pub uv1: Option<Vec<[f32; 2]>>,
+    pub tangents: Option<Vec<[f32; 4]>>,
+    pub joints: Option<Vec<[u16; 4]>>,
+    pub weights: Option<Vec<[f32; 4]>>,
+}
+
+impl VertexStreams {
+    pub fn new(positions: Vec<[f32; 3]>) -> Self {
+        Self {
+            positions,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn format(&self) -> VertexFormat {
+        let mut format = VertexFormat::BASE;
+        if self.colors.is_some() {
+            format |= VertexFormat::COLOR_0;
+        }
+        if self.uv1.is_some() {
+            format |= VertexFormat::TEXCOORD_1;
+        }
+        if self.tangents.is_some() {
+            format |= VertexFormat::TANGENT;
+        }
+        if self.joints.is_some() {
+            format |= VertexFormat::JOINTS_0;
+        }
+        if self.weights.is_some() {
+            format |= VertexFormat::WEIGHTS_0;
+        }
+        format
+    }
+
+    pub fn interleave(&self) -> Vec<u8> {
+        let format = self.format();
+        let mut out = Vec::with_capacity(self.len() * format.stride() as usize);
+        for i in 0..self.len() {
+            out.extend_from_slice(bytemuck::cast_slice(&self.positions[i]));
+            out.extend_from_slice(bytemuck::cast_slice(&attr(
+                &self.normals,
+                i,
+                [0.0, 1.0, 0.0],
+            )));
+            out.extend_from_slice(bytemuck::cast_slice(&attr(&self.uv0, i, [0.0, 0.0])));
+            if let Some(colors) = &self.colors {
+                out.extend_from_slice(bytemuck::cast_slice(&value(colors, i, [1.0; 4])));
+            }
+            if let Some(uv1) = &self.uv1 {
+                out.extend_from_slice(bytemuck::cast_slice(&value(uv1, i, [0.0, 0.0])));
+            }
+            if let Some(tangents) = &self.tangents {
+                out.extend_from_slice(bytemuck::cast_slice(&value(
+                    tangents,
+                    i,
+                    [1.0, 0.0, 0.0, 1.0],
+                )));
+            }
+            if let Some(joints) = &self.joints {
+                out.extend_from_slice(bytemuck::cast_slice(&value(joints, i, [0; 4])));
+            }
+            if let Some(weights) = &self.weights {
+                out.extend_from_slice(bytemuck::cast_slice(&value(
+                    weights,
+                    i,
+                    [1.0, 0.0, 0.0, 0.0],
+                )));
+            }
+        }
+        out
+    }
+}
+
+fn attr<T: Copy>(stream: &Option<Vec<T>>, i: usize, default: T) -> T {
+    stream
+        .as_ref()
+        .map(|s| value(s, i, default))
+        .unwrap_or(default)
+}
+
+fn value<T: Copy>(stream: &[T], i: usize, default: T) -> T {
+    stream.get(i).copied().unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floats(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn base_format_fills_missing_normals_and_uvs() {
+        let streams = VertexStreams::new(vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let format = streams.format();
+        assert_eq!(format, VertexFormat::BASE);
+        assert_eq!(format.stride(), 32);
+
+        let bytes = streams.interleave();
+        assert_eq!(bytes.len(), 2 * 32);
+        assert_eq!(
+            floats(&bytes[..32]),
+            [1.0, 2.0, 3.0, 0.0, 1.0, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(floats(&bytes[32..])[..3], [4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn optional_streams_land_at_their_attribute_offsets() {
+        let mut streams = VertexStreams::new(vec![[1.0, 2.0, 3.0]]);
+        streams.normals = Some(vec![[0.0, 0.0, 1.0]]);
+        streams.uv0 = Some(vec![[0.25, 0.75]]);
+        streams.colors = Some(vec![[0.1, 0.2, 0.3, 0.4]]);
+        streams.tangents = Some(vec![[1.0, 0.0, 0.0, -1.0]]);
+        streams.joints = Some(vec![[1, 2, 3, 4]]);
+        streams.weights = Some(vec![[0.5, 0.5, 0.0, 0.0]]);
+
+        let format = streams.format();
+        assert!(format.contains(VertexFormat::COLOR_0 | VertexFormat::TANGENT));
+        assert!(format.is_skinned());
+        assert!(!format.contains(VertexFormat::TEXCOORD_1));
+
+        let bytes = streams.interleave();
+        assert_eq!(bytes.len() as u64, format.stride());
+
+        let at = |location: u32| {
+            let attr = format
+                .attributes()
+                .into_iter()
+                .find(|a| a.shader_location == location)
+                .unwrap();
+            let start = attr.offset as usize;
+            &bytes[start..start + attr.format.size() as usize]
+        };
+        assert_eq!(floats(at(1)), [0.0, 0.0, 1.0]);
+        assert_eq!(floats(at(2)), [0.25, 0.75]);
+        assert_eq!(floats(at(3)), [0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(floats(at(5)), [1.0, 0.0, 0.0, -1.0]);
+        assert_eq!(bytemuck::cast_slice::<u8, u16>(at(6)), [1, 2, 3, 4]);
+        assert_eq!(floats(at(7)), [0.5, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn short_streams_fall_back_to_defaults() {
+        let mut streams = VertexStreams::new(vec![[0.0; 3], [0.0; 3]]);
+        streams.colors = Some(vec![[0.5; 4]]);
+        let bytes = streams.interleave();
+        let stride = streams.format().stride() as usize;
+        assert_eq!(floats(&bytes[stride + 32..stride + 48]), [1.0; 4]);
+    }
+}
@@ -0,0 +1,242 @@
+use glam::{Mat4, Vec3};
+use std::sync::Arc;
+use wgpu::{
+    BindGroup, Buffer, Color, Device, Queue, Texture, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureUsages, TextureView,
+};
+
+use crate::depth::create_depth;
+use crate::material::{MaterialParams, MaterialRegistry};
+use crate::model::Material;
+use crate::pipeline::{Layouts, create_camera_ubo};
+use crate::texture::GpuTexture;
+
+// Normalized rectangle within a render target, origin at the top left.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ViewRegion {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewRegion {
+    pub const FULL: Self = Self::new(0.0, 0.0, 1.0, 1.0);
+
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    // Pixel rectangle (x, y, width, height), clamped to the target and at least one pixel in size.
+    // A zero-sized target, e.g. a minimized window, counts as one pixel.
+    pub fn pixels(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let span = |start: f32, size: f32, extent: u32| {
+            let extent_f = extent.max(1) as f32;
+            let lo = (start * extent_f).round().clamp(0.0, extent_f - 1.0) as u32;
+            let hi = ((start + size) * extent_f).round().clamp(0.0, extent_f) as u32;
+            (lo, hi.max(lo + 1) - lo)
+        };
+        let (x, w) = span(self.x, self.width, width);
+        let (y, h) = span(self.y, self.height, height);
+        (x, y, w, h)
+    }
+
+    pub fn aspect(&self, width: u32, height: u32) -> f32 {
+        let (_, _, w, h) = self.pixels(width, height);
+        w as f32 / h as f32
+    }
+}
+
+impl Default for ViewRegion {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ClearMode {
+    Color(Color),
+    // Keeps the color underneath, e.g. for overlays drawn on top of another view.
+    DepthOnly,
+    Keep,
+}
+
+impl Default for ClearMode {
+    fn default() -> Self {
+        ClearMode::Color(Color::BLACK)
+    }
+}
+
+pub struct ViewTarget<'a> {
+    pub color: &'a TextureView,
+    // The texture behind `color` when materials may sample it; meshes that do are not drawn.
+    pub texture: Option<&'a Arc<GpuTexture>>,
+    pub depth: &'a TextureView,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Offscreen color + depth that can be rendered into and then sampled like any other texture.
+pub struct RenderTarget {
+    pub color: Arc<GpuTexture>,
+    pub depth_view: TextureView,
+    pub depth_tex: Texture,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl RenderTarget {
+    pub fn new(device: &Device, format: TextureFormat, width: u32, height: u32) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+        let color = Arc::new(GpuTexture::new(device.create_texture(&TextureDescriptor {
+            label: Some("render_target_color"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })));
+        let (depth_view, depth_tex) = create_depth(device, width, height);
+        Self {
+            color,
+            depth_view,
+            depth_tex,
+            width,
+            height,
+        }
+    }
+
+    // Unlit, so the picture shows as rendered, e.g. on an in-world monitor. Views into this same
+    // target skip meshes using it, since it cannot be sampled while it is being written.
+    pub fn material(&self, device: &Device, registry: &MaterialRegistry) -> Material {
+        registry.create_material_with_params(
+            device,
+            self.color.clone(),
+            registry.fallbacks.black.clone(),
+            MaterialParams {
+                unlit: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn view_target(&self) -> ViewTarget<'_> {
+        ViewTarget {
+            color: &self.color.view,
+            texture: Some(&self.color),
+            depth: &self.depth_view,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+// Per-camera uniforms; every view rendered in a frame needs its own so queued writes don't collide.
+pub struct CameraView {
+    pub camera_buf: Buffer,
+    pub camera_bg: BindGroup,
+    pub clear_buf: Buffer,
+    pub clear_bg: BindGroup,
+    pub region: ViewRegion,
+    pub clear: ClearMode,
+    // LOD per mesh of the drawn instance as seen from this view; empty uses the instance's own.
+    pub lods: Vec<usize>,
+}
+
+impl CameraView {
+    pub fn new(device: &Device, layouts: &Layouts) -> Self {
+        let (camera_buf, camera_bg) = create_camera_ubo(device, layouts);
+        let clear_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("clear_ubo"),
+            size: 32,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let clear_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("clear_bg"),
+            layout: &layouts.clear_bgl,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: clear_buf.as_entire_binding(),
+            }],
+        });
+        Self {
+            camera_buf,
+            camera_bg,
+            clear_buf,
+            clear_bg,
+            region: ViewRegion::FULL,
+            clear: ClearMode::default(),
+            lods: Vec::new(),
+        }
+    }
+
+    pub fn write_camera(&self, queue: &Queue, view_proj: Mat4, eye: Vec3) {
+        let vp = view_proj.to_cols_array();
+        let eye = eye.extend(1.0).to_array();
+        queue.write_buffer(&self.camera_buf, 0, bytemuck::cast_slice(&vp));
+        queue.write_buffer(&self.camera_buf, 64, bytemuck::cast_slice(&eye));
+    }
+
+    pub fn write_clear(&self, queue: &Queue, depth: f32) {
+        let color = match self.clear {
+            ClearMode::Color(c) => [c.r as f32, c.g as f32, c.b as f32, c.a as f32],
+            ClearMode::DepthOnly | ClearMode::Keep => [0.0; 4],
+        };
+        let data = [color, [depth, 0.0, 0.0, 0.0]];
+        queue.write_buffer(&self.clear_buf, 0, bytemuck::cast_slice(&data));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_region_covers_the_target() {
+        assert_eq!(ViewRegion::FULL.pixels(800, 600), (0, 0, 800, 600));
+        assert_eq!(ViewRegion::FULL.aspect(800, 600), 800.0 / 600.0);
+    }
+
+    #[test]
+    fn sub_regions_round_to_pixels() {
+        let right_half = ViewRegion::new(0.5, 0.0, 0.5, 1.0);
+        assert_eq!(right_half.pixels(800, 600), (400, 0, 400, 600));
+        let corner = ViewRegion::new(0.75, 0.75, 0.25, 0.25);
+        assert_eq!(corner.pixels(101, 101), (76, 76, 25, 25));
+    }
+
+    #[test]
+    fn regions_are_clamped_to_the_target() {
+        let overhang = ViewRegion::new(-0.5, 0.5, 1.0, 1.0);
+        assert_eq!(overhang.pixels(100, 100), (0, 50, 50, 50));
+        let outside = ViewRegion::new(2.0, -2.0, 0.5, 0.5);
+        assert_eq!(outside.pixels(100, 100), (99, 0, 1, 1));
+    }
+
+    #[test]
+    fn empty_regions_and_targets_keep_one_pixel() {
+        let empty = ViewRegion::new(0.5, 0.5, 0.0, 0.0);
+        assert_eq!(empty.pixels(100, 100), (50, 50, 1, 1));
+        assert_eq!(ViewRegion::FULL.pixels(0, 0), (0, 0, 1, 1));
+        assert_eq!(
+            ViewRegion::new(0.5, 0.0, 0.5, 1.0).pixels(0, 600),
+            (0, 0, 1, 600)
+        );
+        assert!(ViewRegion::FULL.aspect(0, 0).is_finite());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod player;
+
+pub use minima_3d::animation::{AnimationClip, Channel, ChannelValues, Interpolation, Sample};
+pub use player::{AnimationLayer, Animator, ClipPlayback, LayerBlend, PlaybackSettings};
@@ -0,0 +1,440 @@
+use glam::{Quat, Vec3};
+use minima_3d::{AnimationClip, Model, Pose, Sample, Transform};
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug)]
+pub struct PlaybackSettings {
+    pub speed: f32,
+    pub looping: bool,
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            looping: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ClipPlayback {
+    pub clip: Arc<AnimationClip>,
+    pub time: f32,
+    pub speed: f32,
+    pub looping: bool,
+}
+
+impl ClipPlayback {
+    pub fn new(clip: Arc<AnimationClip>, settings: PlaybackSettings) -> Self {
+        Self {
+            clip,
+            time: 0.0,
+            speed: settings.speed,
+            looping: settings.looping,
+        }
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        self.time += dt * self.speed;
+        let duration = self.clip.duration;
+        if duration <= 0.0 {
+            self.time = 0.0;
+        } else if self.looping {
+            self.time = self.time.rem_euclid(duration);
+        } else {
+            self.time = self.time.clamp(0.0, duration);
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.time >= self.clip.duration
+    }
+
+    fn sample_into(&self, locals: &mut [Transform], weights: &mut [Vec<f32>], mask: &mut [bool]) {
+        for (node, sample) in self.clip.sample(self.time) {
+            let (Some(local), Some(w)) = (locals.get_mut(node), weights.get_mut(node)) else {
+                continue;
+            };
+            match sample {
+                Sample::Translation(t) => local.translation = t,
+                Sample::Rotation(r) => local.rotation = r,
+                Sample::Scale(s) => local.scale = s,
+                Sample::Weights(values) => *w = values,
+            }
+            mask[node] = true;
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LayerBlend {
+    Override,
+    Additive,
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationLayer {
+    pub blend: LayerBlend,
+    pub weight: f32,
+    current: Option<ClipPlayback>,
+    fading_out: Option<ClipPlayback>,
+    fade_duration: f32,
+    fade_elapsed: f32,
+}
+
+impl AnimationLayer {
+    pub fn new(blend: LayerBlend, weight: f32) -> Self {
+        Self {
+            blend,
+            weight,
+            current: None,
+            fading_out: None,
+            fade_duration: 0.0,
+            fade_elapsed: 0.0,
+        }
+    }
+
+    pub fn play(&mut self, clip: Arc<AnimationClip>, settings: PlaybackSettings) {
+        self.current = Some(ClipPlayback::new(clip, settings));
+        self.fading_out = None;
+    }
+
+    pub fn cross_fade(
+        &mut self,
+        clip: Arc<AnimationClip>,
+        duration: f32,
+        settings: PlaybackSettings,
+    ) {
+        if duration <= 0.0 || self.current.is_none() {
+            self.play(clip, settings);
+            return;
+        }
+        self.fading_out = self.current.take();
+        self.current = Some(ClipPlayback::new(clip, settings));
+        self.fade_duration = duration;
+        self.fade_elapsed = 0.0;
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.fading_out = None;
+    }
+
+    pub fn current(&self) -> Option<&ClipPlayback> {
+        self.current.as_ref()
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut ClipPlayback> {
+        self.current.as_mut()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.current.is_some() || self.fading_out.is_some()
+    }
+
+    fn update(&mut self, dt: f32) {
+        if let Some(current) = &mut self.current {
+            current.advance(dt);
+        }
+        if let Some(previous) = &mut self.fading_out {
+            previous.advance(dt);
+            self.fade_elapsed += dt;
+            if self.fade_elapsed >= self.fade_duration {
+                self.fading_out = None;
+            }
+        }
+    }
+
+    fn evaluate(&self, pose: &Pose) -> Option<LayerSample> {
+        let current = self.current.as_ref()?;
+        let mut locals = pose.locals.clone();
+        let mut weights = pose.weights.clone();
+        let mut mask = vec![false; locals.len()];
+        current.sample_into(&mut locals, &mut weights, &mut mask);
+
+        if let Some(previous) = &self.fading_out {
+            let mut prev_locals = pose.locals.clone();
+            let mut prev_weights = pose.weights.clone();
+            previous.sample_into(&mut prev_locals, &mut prev_weights, &mut mask);
+            let f = (self.fade_elapsed / self.fade_duration).clamp(0.0, 1.0);
+            for i in 0..locals.len() {
+                if mask[i] {
+                    locals[i] = mix(&prev_locals[i], &locals[i], f);
+                    weights[i] = mix_weights(&prev_weights[i], &weights[i], f);
+                }
+            }
+        }
+        Some(LayerSample {
+            locals,
+            weights,
+            mask,
+        })
+    }
+}
+
+struct LayerSample {
+    locals: Vec<Transform>,
+    weights: Vec<Vec<f32>>,
+    mask: Vec<bool>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Animator {
+    pub layers: Vec<AnimationLayer>,
+}
+
+impl Animator {
+    pub fn new() -> Self {
+        Self {
+            layers: vec![AnimationLayer::new(LayerBlend::Override, 1.0)],
+        }
+    }
+
+    pub fn add_layer(&mut self, blend: LayerBlend, weight: f32) -> usize {
+        self.layers.push(AnimationLayer::new(blend, weight));
+        self.layers.len() - 1
+    }
+
+    pub fn layer_mut(&mut self, ix: usize) -> &mut AnimationLayer {
+        &mut self.layers[ix]
+    }
+
+    pub fn play(&mut self, clip: Arc<AnimationClip>, settings: PlaybackSettings) {
+        self.layers[0].play(clip, settings);
+    }
+
+    pub fn cross_fade(
+        &mut self,
+        clip: Arc<AnimationClip>,
+        duration: f32,
+        settings: PlaybackSettings,
+    ) {
+        self.layers[0].cross_fade(clip, duration, settings);
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.layers.iter().any(AnimationLayer::is_active)
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for layer in &mut self.layers {
+            layer.update(dt);
+        }
+    }
+
+    pub fn apply(&self, model: &Model, pose: &mut Pose) {
+        if !self.is_playing() {
+            return;
+        }
+        pose.reset(model);
+        let rest = pose.clone();
+        for layer in &self.layers {
+            let Some(LayerSample {
+                locals,
+                weights,
+                mask,
+            }) = layer.evaluate(&rest)
+            else {
+                continue;
+            };
+            let w = layer.weight.clamp(0.0, 1.0);
+            for i in 0..mask.len() {
+                if !mask[i] {
+                    continue;
+                }
+                match layer.blend {
+                    LayerBlend::Override => {
+                        pose.locals[i] = mix(&pose.locals[i], &locals[i], w);
+                        pose.weights[i] = mix_weights(&pose.weights[i], &weights[i], w);
+                    }
+                    LayerBlend::Additive => {
+                        let base = &rest.locals[i];
+                        let target = &mut pose.locals[i];
+                        target.translation += (locals[i].translation - base.translation) * w;
+                        let delta = locals[i].rotation * base.rotation.inverse();
+                        target.rotation =
+                            (Quat::IDENTITY.slerp(delta, w) * target.rotation).normalize();
+                        let scale = locals[i].scale / base.scale.max(Vec3::splat(1e-6));
+                        target.scale *= Vec3::ONE.lerp(scale, w);
+                        let rest_weights = &rest.weights[i];
+                        let out = &mut pose.weights[i];
+                        out.resize(out.len().max(weights[i].len()), 0.0);
+                        for (k, v) in weights[i].iter().enumerate() {
+                            out[k] += (v - rest_weights.get(k).copied().unwrap_or(0.0)) * w;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn mix(a: &Transform, b: &Transform, f: f32) -> Transform {
+    Transform {
+        translation: a.translation.lerp(b.translation, f),
+        rotation: a.rotation.slerp(b.rotation, f),
+        scale: a.scale.lerp(b.scale, f),
+    }
+}
+
+fn mix_weights(a: &[f32], b: &[f32], f: f32) -> Vec<f32> {
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let x = a.get(i).copied().unwrap_or(0.0);
+            let y = b.get(i).copied().unwrap_or(0.0);
+            x + (y - x) * f
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Mat4;
+    use minima_3d::{Aabb, Channel, ChannelValues, Interpolation, Node};
+
+    fn clip(duration: f32, translation: Vec3) -> Arc<AnimationClip> {
+        clip_with(duration, ChannelValues::Translation(vec![translation; 2]))
+    }
+
+    fn clip_with(duration: f32, values: ChannelValues) -> Arc<AnimationClip> {
+        Arc::new(AnimationClip {
+            name: None,
+            duration,
+            channels: vec![Channel {
+                node: 0,
+                interpolation: Interpolation::Linear,
+                times: vec![0.0, duration],
+                values,
+            }],
+        })
+    }
+
+    fn model(rest: Vec3) -> Model {
+        Model {
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            nodes: vec![Node {
+                transform: Transform {
+                    translation: rest,
+                    ..Transform::IDENTITY
+                },
+                ..Node::default()
+            }],
+            roots: vec![0],
+            skins: Vec::new(),
+            cameras: Vec::new(),
+            lights: Vec::new(),
+            animations: Vec::new(),
+            bounds: Aabb {
+                min: Vec3::ZERO,
+                max: Vec3::ZERO,
+            },
+            recommended_xform: Mat4::IDENTITY,
+        }
+    }
+
+    fn translation(animator: &Animator, model: &Model) -> Vec3 {
+        let mut pose = Pose::rest(model);
+        animator.apply(model, &mut pose);
+        pose.locals[0].translation
+    }
+
+    #[test]
+    fn looping_wraps_and_clamped_playback_holds_the_end() {
+        let settings = PlaybackSettings::default();
+        let mut looped = ClipPlayback::new(clip(2.0, Vec3::ZERO), settings);
+        looped.advance(2.5);
+        assert!((looped.time - 0.5).abs() < 1e-6);
+        assert!(!looped.is_finished());
+
+        let mut clamped = ClipPlayback::new(
+            clip(2.0, Vec3::ZERO),
+            PlaybackSettings {
+                looping: false,
+                ..settings
+            },
+        );
+        clamped.advance(1.5);
+        assert!(!clamped.is_finished());
+        clamped.advance(1.5);
+        assert_eq!(clamped.time, 2.0);
+        assert!(clamped.is_finished());
+    }
+
+    #[test]
+    fn speed_scales_time_and_reverses_when_negative() {
+        let mut fast = ClipPlayback::new(
+            clip(2.0, Vec3::ZERO),
+            PlaybackSettings {
+                speed: 2.0,
+                looping: true,
+            },
+        );
+        fast.advance(0.25);
+        assert!((fast.time - 0.5).abs() < 1e-6);
+
+        let mut reverse = ClipPlayback::new(
+            clip(2.0, Vec3::ZERO),
+            PlaybackSettings {
+                speed: -1.0,
+                looping: true,
+            },
+        );
+        reverse.advance(0.5);
+        assert!((reverse.time - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cross_fade_blends_from_the_old_clip_to_the_new() {
+        let model = model(Vec3::ZERO);
+        let mut animator = Animator::new();
+        let settings = PlaybackSettings::default();
+        animator.play(clip(1.0, Vec3::ZERO), settings);
+        animator.cross_fade(clip(1.0, Vec3::new(4.0, 0.0, 0.0)), 1.0, settings);
+
+        assert!(translation(&animator, &model).abs_diff_eq(Vec3::ZERO, 1e-6));
+        animator.update(0.5);
+        assert!(translation(&animator, &model).abs_diff_eq(Vec3::new(2.0, 0.0, 0.0), 1e-6));
+        animator.update(0.5);
+        assert!(translation(&animator, &model).abs_diff_eq(Vec3::new(4.0, 0.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn additive_layer_adds_its_delta_from_rest_over_the_override() {
+        let rest = Vec3::new(1.0, 0.0, 0.0);
+        let model = model(rest);
+        let mut animator = Animator::new();
+        let settings = PlaybackSettings::default();
+        animator.play(clip(1.0, Vec3::new(0.0, 2.0, 0.0)), settings);
+
+        let additive = animator.add_layer(LayerBlend::Additive, 0.5);
+        animator
+            .layer_mut(additive)
+            .play(clip(1.0, rest + Vec3::new(0.0, 0.0, 3.0)), settings);
+        assert!(translation(&animator, &model).abs_diff_eq(Vec3::new(0.0, 2.0, 1.5), 1e-6));
+
+        // Rotations compose the same way: the layer's turn away from rest, on top.
+        let turn = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        animator.layer_mut(additive).weight = 1.0;
+        animator.layer_mut(additive).play(
+            clip_with(1.0, ChannelValues::Rotation(vec![turn; 2])),
+            settings,
+        );
+        let mut pose = Pose::rest(&model);
+        animator.apply(&model, &mut pose);
+        assert!(pose.locals[0].rotation.abs_diff_eq(turn, 1e-6));
+        assert!(
+            pose.locals[0]
+                .translation
+                .abs_diff_eq(Vec3::new(0.0, 2.0, 0.0), 1e-6)
+        );
+    }
+}
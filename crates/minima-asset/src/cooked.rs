@@ -0,0 +1,1082 @@
+use anyhow::{Context, Result, bail};
+use glam::{Mat4, Quat, Vec2, Vec3, Vec4};
+use minima_3d::{
+    Aabb, AnimationClip, Camera, CameraProjection, Channel, ChannelValues, CpuLod, CpuMesh,
+    GpuMesh, GpuMorphTargets, GpuTexture, Interpolation, Light, LightKind, Material,
+    MaterialParams, MaterialRegistry, MeshLod, Model, MorphTarget, Node, Skin, TextureTransform,
+    Transform, VertexFormat, VertexStreams, create_index_buffer,
+};
+use minima_gltf::{
+    ASTC_BLOCKS, DecodedImage, ImageKey, TextureCache, TextureKey, compress_bc7, fit_to_device,
+    read_texture,
+};
+use std::sync::Arc;
+use wgpu::{AstcChannel, Device, Queue, TextureFormat, util::DeviceExt};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::format::{Header, Reader, Writer};
+use crate::mips::build_mips;
+use crate::pipeline::ImportSettings;
+
+const WHITE: u32 = u32::MAX;
+const BLACK: u32 = u32::MAX - 1;
+const MISSING: u32 = u32::MAX - 2;
+const FLAT_NORMAL: u32 = u32::MAX - 3;
+
+const TEXTURE_FORMATS: [TextureFormat; 19] = [
+    TextureFormat::Rgba8Unorm,
+    TextureFormat::Rgba8UnormSrgb,
+    TextureFormat::Rgba16Float,
+    TextureFormat::Bc1RgbaUnorm,
+    TextureFormat::Bc1RgbaUnormSrgb,
+    TextureFormat::Bc2RgbaUnorm,
+    TextureFormat::Bc2RgbaUnormSrgb,
+    TextureFormat::Bc3RgbaUnorm,
+    TextureFormat::Bc3RgbaUnormSrgb,
+    TextureFormat::Bc4RUnorm,
+    TextureFormat::Bc5RgUnorm,
+    TextureFormat::Bc7RgbaUnorm,
+    TextureFormat::Bc7RgbaUnormSrgb,
+    TextureFormat::Etc2Rgb8Unorm,
+    TextureFormat::Etc2Rgb8UnormSrgb,
+    TextureFormat::Etc2Rgb8A1Unorm,
+    TextureFormat::Etc2Rgb8A1UnormSrgb,
+    TextureFormat::Etc2Rgba8Unorm,
+    TextureFormat::Etc2Rgba8UnormSrgb,
+];
+
+pub fn write_cooked(
+    device: &Device,
+    queue: &Queue,
+    registry: &MaterialRegistry,
+    model: &Model,
+    header: &Header,
+    settings: &ImportSettings,
+) -> Result<Vec<u8>> {
+    let mut w = Writer::new(header);
+    let fallbacks = &registry.fallbacks;
+
+    let fallback_ref = |tex: &Arc<GpuTexture>| {
+        [
+            (&fallbacks.white, WHITE),
+            (&fallbacks.black, BLACK),
+            (&fallbacks.missing, MISSING),
+            (&fallbacks.flat_normal, FLAT_NORMAL),
+        ]
+        .into_iter()
+        .find(|(fallback, _)| Arc::ptr_eq(fallback, tex))
+        .map(|(_, r)| r)
+    };
+    let mut textures: Vec<&Arc<GpuTexture>> = Vec::new();
+    let mut material_refs = Vec::with_capacity(model.materials.len());
+    for material in &model.materials {
+        let refs = [&material.base_color, &material.emissive].map(|tex| {
+            fallback_ref(tex).unwrap_or_else(|| {
+                match textures.iter().position(|t| Arc::ptr_eq(t, tex)) {
+                    Some(ix) => ix as u32,
+                    None => {
+                        textures.push(tex);
+                        textures.len() as u32 - 1
+                    }
+                }
+            })
+        });
+        material_refs.push(refs);
+    }
+
+    w.u32(textures.len() as u32);
+    for tex in textures {
+        let mut image = read_texture(device, queue, &tex.texture)?;
+        if settings.generate_mips && image.mip_level_count == 1 && !image.format.is_compressed() {
+            (image.mip_level_count, image.bytes) = build_mips(&image);
+        }
+        if settings.compress_textures
+            && let Some(compressed) = compress_bc7(&image)
+        {
+            image = compressed;
+        }
+        let code = format_code(image.format)
+            .with_context(|| format!("cannot cook texture format {:?}", image.format))?;
+        // Keyed by the cooked bytes so loads share one upload through the texture cache.
+        let mut hasher = Xxh3::new();
+        hasher.update(
+            &[code, image.width, image.height, image.mip_level_count]
+                .map(u32::to_le_bytes)
+                .concat(),
+        );
+        hasher.update(&image.bytes);
+        let content = hasher.digest128();
+        w.u64(content as u64);
+        w.u64((content >> 64) as u64);
+        w.u32(code);
+        w.u32(image.width);
+        w.u32(image.height);
+        w.u32(image.mip_level_count);
+        w.blob(&image.bytes);
+    }
+
+    w.u32(model.materials.len() as u32);
+    for (material, [base_color, emissive]) in model.materials.iter().zip(material_refs) {
+        w.u32(base_color);
+        w.u32(emissive);
+        write_params(&mut w, &material.params);
+    }
+
+    w.u32(model.nodes.len() as u32);
+    for node in &model.nodes {
+        w.string(node.name.as_deref());
+        w.index(node.parent);
+        w.indices(&node.children);
+        write_transform(&mut w, &node.transform);
+        w.index(node.skin);
+        w.index(node.camera);
+        w.index(node.light);
+        w.u32(node.weights.len() as u32);
+        w.f32s(&node.weights);
+    }
+    w.indices(&model.roots);
+
+    w.u32(model.skins.len() as u32);
+    for skin in &model.skins {
+        w.string(skin.name.as_deref());
+        w.indices(&skin.joints);
+        w.u32(skin.inverse_bind_matrices.len() as u32);
+        for m in &skin.inverse_bind_matrices {
+            w.f32s(&m.to_cols_array());
+        }
+        w.index(skin.skeleton);
+    }
+
+    w.u32(model.cameras.len() as u32);
+    for camera in &model.cameras {
+        w.string(camera.name.as_deref());
+        match camera.projection {
+            CameraProjection::Perspective {
+                yfov,
+                aspect_ratio,
+                znear,
+                zfar,
+            } => {
+                w.u32(0);
+                w.f32s(&[
+                    yfov,
+                    aspect_ratio.unwrap_or(f32::NAN),
+                    znear,
+                    zfar.unwrap_or(f32::NAN),
+                ]);
+            }
+            CameraProjection::Orthographic {
+                xmag,
+                ymag,
+                znear,
+                zfar,
+            } => {
+                w.u32(1);
+                w.f32s(&[xmag, ymag, znear, zfar]);
+            }
+        }
+    }
+
+    w.u32(model.lights.len() as u32);
+    for light in &model.lights {
+        w.string(light.name.as_deref());
+        match light.kind {
+            LightKind::Directional => {
+                w.u32(0);
+                w.f32s(&[0.0, 0.0]);
+            }
+            LightKind::Point => {
+                w.u32(1);
+                w.f32s(&[0.0, 0.0]);
+            }
+            LightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => {
+                w.u32(2);
+                w.f32s(&[inner_cone_angle, outer_cone_angle]);
+            }
+        }
+        w.f32s(&light.color.to_array());
+        w.f32(light.intensity);
+        w.f32(light.range.unwrap_or(f32::NAN));
+    }
+
+    w.u32(model.meshes.len() as u32);
+    for mesh in &model.meshes {
+        let Some(cpu) = &mesh.cpu else {
+            bail!("cannot cook a mesh without retained CPU data");
+        };
+        w.u32(mesh.format.bits());
+        w.u32(mesh.material_id as u32);
+        w.index(mesh.node);
+        w.index(mesh.skin);
+        write_aabb(&mut w, &mesh.bounds);
+        w.u32(cpu.vertices.len() as u32);
+        w.blob(&cpu.vertices.interleave());
+        w.pod_blob(&cpu.indices);
+
+        if settings.retain_cpu_data {
+            w.u32(1);
+            write_streams(&mut w, &cpu.vertices);
+        } else {
+            w.u32(0);
+        }
+
+        match &mesh.morph {
+            Some(morph) => {
+                w.u32(cpu.morph_targets.len() as u32);
+                for target in &cpu.morph_targets {
+                    w.pod_blob(&target.positions);
+                    w.opt_pod_blob(target.normals.as_deref());
+                }
+                w.u32(morph.default_weights.len() as u32);
+                w.f32s(&morph.default_weights);
+            }
+            None => w.u32(u32::MAX),
+        }
+
+        w.u32(cpu.lods.len() as u32);
+        for lod in &cpu.lods {
+            w.f32(lod.screen_size);
+            match &lod.vertices {
+                Some(vertices) => {
+                    w.u32(1);
+                    write_streams(&mut w, vertices);
+                }
+                None => w.u32(0),
+            }
+            w.pod_blob(&lod.indices);
+        }
+    }
+
+    w.u32(model.animations.len() as u32);
+    for clip in &model.animations {
+        write_clip(&mut w, clip);
+    }
+
+    write_aabb(&mut w, &model.bounds);
+    w.f32s(&model.recommended_xform.to_cols_array());
+    Ok(w.bytes)
+}
+
+pub fn load_cooked_model(
+    device: &Device,
+    queue: &Queue,
+    registry: &MaterialRegistry,
+    cache: &mut TextureCache,
+    data: &[u8],
+) -> Result<Model> {
+    let (mut r, _) = Reader::new(data)?;
+    let fallbacks = &registry.fallbacks;
+
+    let texture_count = r.u32()?;
+    let mut textures = Vec::with_capacity(texture_count as usize);
+    for _ in 0..texture_count {
+        let content = r.u64()? as u128 | (r.u64()? as u128) << 64;
+        let code = r.u32()?;
+        let format =
+            code_format(code).with_context(|| format!("unknown cooked texture format {code}"))?;
+        let (width, height, mip_level_count) = (r.u32()?, r.u32()?, r.u32()?);
+        let bytes = r.blob()?;
+        let key = TextureKey {
+            source: ImageKey::Content(content),
+            srgb: format.is_srgb(),
+        };
+        textures.push(cache.try_texture(device, queue, key, || {
+            let image = DecodedImage {
+                width,
+                height,
+                format,
+                mip_level_count,
+                bytes: bytes.to_vec(),
+            };
+            fit_to_device(image, device.features())
+        })?);
+    }
+    let texture = |ix: u32| -> Result<Arc<GpuTexture>> {
+        Ok(match ix {
+            WHITE => fallbacks.white.clone(),
+            BLACK => fallbacks.black.clone(),
+            MISSING => fallbacks.missing.clone(),
+            FLAT_NORMAL => fallbacks.flat_normal.clone(),
+            ix => textures
+                .get(ix as usize)
+                .cloned()
+                .context("cooked material references a missing texture")?,
+        })
+    };
+
+    let material_count = r.u32()?;
+    let mut materials: Vec<Arc<Material>> = Vec::with_capacity(material_count as usize);
+    for _ in 0..material_count {
+        let base_color = texture(r.u32()?)?;
+        let emissive = texture(r.u32()?)?;
+        let params = read_params(&mut r)?;
+        materials.push(Arc::new(
+            registry.create_material_with_params(device, base_color, emissive, params),
+        ));
+    }
+
+    let node_count = r.u32()?;
+    let mut nodes = Vec::with_capacity(node_count as usize);
+    for _ in 0..node_count {
+        let name = r.string()?;
+        let parent = r.index()?;
+        let children = r.indices()?;
+        let transform = read_transform(&mut r)?;
+        let skin = r.index()?;
+        let camera = r.index()?;
+        let light = r.index()?;
+        let weights = read_floats(&mut r)?;
+        nodes.push(Node {
+            name,
+            parent,
+            children,
+            transform,
+            skin,
+            camera,
+            light,
+            weights,
+        });
+    }
+    let roots = r.indices()?;
+
+    let skin_count = r.u32()?;
+    let mut skins = Vec::with_capacity(skin_count as usize);
+    for _ in 0..skin_count {
+        let name = r.string()?;
+        let joints = r.indices()?;
+        let ibm_count = r.u32()?;
+        let inverse_bind_matrices = (0..ibm_count)
+            .map(|_| Ok(Mat4::from_cols_array(&r.f32s()?)))
+            .collect::<Result<_>>()?;
+        let skeleton = r.index()?;
+        skins.push(Skin {
+            name,
+            joints,
+            inverse_bind_matrices,
+            skeleton,
+        });
+    }
+
+    let camera_count = r.u32()?;
+    let mut cameras = Vec::with_capacity(camera_count as usize);
+    for _ in 0..camera_count {
+        let name = r.string()?;
+        let kind = r.u32()?;
+        let [a, b, c, d] = r.f32s()?;
+        let projection = match kind {
+            0 => CameraProjection::Perspective {
+                yfov: a,
+                aspect_ratio: (!b.is_nan()).then_some(b),
+                znear: c,
+                zfar: (!d.is_nan()).then_some(d),
+            },
+            _ => CameraProjection::Orthographic {
+                xmag: a,
+                ymag: b,
+                znear: c,
+                zfar: d,
+            },
+        };
+        cameras.push(Camera { name, projection });
+    }
+
+    let light_count = r.u32()?;
+    let mut lights = Vec::with_capacity(light_count as usize);
+    for _ in 0..light_count {
+        let name = r.string()?;
+        let kind = r.u32()?;
+        let [inner_cone_angle, outer_cone_angle] = r.f32s()?;
+        let kind = match kind {
+            0 => LightKind::Directional,
+            1 => LightKind::Point,
+            _ => LightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            },
+        };
+        let color = Vec3::from_array(r.f32s()?);
+        let intensity = r.f32()?;
+        let range = r.f32()?;
+        lights.push(Light {
+            name,
+            kind,
+            color,
+            intensity,
+            range: (!range.is_nan()).then_some(range),
+        });
+    }
+
+    let mesh_count = r.u32()?;
+    let mut meshes = Vec::with_capacity(mesh_count as usize);
+    for _ in 0..mesh_count {
+        let format = VertexFormat::from_bits_truncate(r.u32()?);
+        let material_id = r.u32()? as usize;
+        if material_id >= materials.len() {
+            bail!("cooked mesh references a missing material");
+        }
+        let node = r.index()?;
+        let skin = r.index()?;
+        if node.is_some_and(|ix| ix >= nodes.len()) {
+            bail!("cooked mesh references a missing node");
+        }
+        if skin.is_some_and(|ix| ix >= skins.len()) {
+            bail!("cooked mesh references a missing skin");
+        }
+        let bounds = read_aabb(&mut r)?;
+        let vertex_count = r.u32()? as usize;
+        let vertices = r.blob()?;
+        let indices: Vec<u32> = r.pod_vec()?;
+
+        let vbuf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mesh_vbuf"),
+            contents: vertices,
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let (ibuf, index_format) = create_index_buffer(device, &indices, vertex_count);
+
+        let streams = match r.u32()? {
+            0 => None,
+            _ => Some(read_streams(&mut r)?),
+        };
+
+        let target_count = r.u32()?;
+        let mut morph_targets = Vec::new();
+        let mut morph = None;
+        if target_count != u32::MAX {
+            for _ in 0..target_count {
+                morph_targets.push(MorphTarget {
+                    positions: r.pod_vec()?,
+                    normals: r.opt_pod_vec()?,
+                });
+            }
+            let default_weights = read_floats(&mut r)?;
+            morph = Some(GpuMorphTargets::new(
+                device,
+                &morph_targets,
+                vertex_count,
+                default_weights,
+            ));
+        }
+
+        let lod_count = r.u32()?;
+        let mut cpu_lods = Vec::with_capacity(lod_count as usize);
+        for _ in 0..lod_count {
+            let screen_size = r.f32()?;
+            let vertices = match r.u32()? {
+                0 => None,
+                _ => Some(read_streams(&mut r)?),
+            };
+            cpu_lods.push(CpuLod {
+                screen_size,
+                vertices,
+                indices: r.pod_vec()?,
+            });
+        }
+        let lods = cpu_lods
+            .iter()
+            .map(|lod| MeshLod::upload(device, lod, vertex_count))
+            .collect();
+
+        meshes.push(GpuMesh {
+            vbuf,
+            ibuf,
+            index_count: indices.len() as u32,
+            index_format,
+            material_id,
+            format,
+            bounds,
+            cpu: streams.map(|streams| {
+                let mut cpu = CpuMesh::new(streams, indices);
+                cpu.morph_targets = morph_targets;
+                cpu.lods = cpu_lods;
+                Arc::new(cpu)
+            }),
+            node,
+            skin,
+            morph,
+            lods,
+        });
+    }
+
+    let clip_count = r.u32()?;
+    let animations = (0..clip_count)
+        .map(|_| read_clip(&mut r, nodes.len()).map(Arc::new))
+        .collect::<Result<_>>()?;
+
+    let bounds = read_aabb(&mut r)?;
+    let recommended_xform = Mat4::from_cols_array(&r.f32s()?);
+
+    Ok(Model {
+        meshes,
+        materials,
+        nodes,
+        roots,
+        skins,
+        cameras,
+        lights,
+        animations,
+        bounds,
+        recommended_xform,
+    })
+}
+
+fn write_clip(w: &mut Writer, clip: &AnimationClip) {
+    w.string(clip.name.as_deref());
+    w.f32(clip.duration);
+    w.u32(clip.channels.len() as u32);
+    for channel in &clip.channels {
+        w.u32(channel.node as u32);
+        w.u32(match channel.interpolation {
+            Interpolation::Step => 0,
+            Interpolation::Linear => 1,
+            Interpolation::CubicSpline => 2,
+        });
+        w.pod_blob(&channel.times);
+        match &channel.values {
+            ChannelValues::Translation(v) => {
+                w.u32(0);
+                w.pod_blob(&v.iter().map(|v| v.to_array()).collect::<Vec<_>>());
+            }
+            ChannelValues::Rotation(v) => {
+                w.u32(1);
+                w.pod_blob(&v.iter().map(|q| q.to_array()).collect::<Vec<_>>());
+            }
+            ChannelValues::Scale(v) => {
+                w.u32(2);
+                w.pod_blob(&v.iter().map(|v| v.to_array()).collect::<Vec<_>>());
+            }
+            ChannelValues::Weights(tracks) => {
+                w.u32(3);
+                w.u32(tracks.len() as u32);
+                for track in tracks {
+                    w.pod_blob(track);
+                }
+            }
+        }
+    }
+}
+
+fn read_clip(r: &mut Reader, node_count: usize) -> Result<AnimationClip> {
+    let name = r.string()?;
+    let duration = r.f32()?;
+    let channel_count = r.u32()?;
+    let mut channels = Vec::with_capacity(channel_count as usize);
+    for _ in 0..channel_count {
+        let node = r.u32()? as usize;
+        if node >= node_count {
+            bail!("cooked animation channel targets a missing node");
+        }
+        let interpolation = match r.u32()? {
+            0 => Interpolation::Step,
+            1 => Interpolation::Linear,
+            _ => Interpolation::CubicSpline,
+        };
+        let times: Vec<f32> = r.pod_vec()?;
+        let values = match r.u32()? {
+            0 => ChannelValues::Translation(
+                r.pod_vec::<[f32; 3]>()?
+                    .into_iter()
+                    .map(Vec3::from)
+                    .collect(),
+            ),
+            1 => ChannelValues::Rotation(
+                r.pod_vec::<[f32; 4]>()?
+                    .into_iter()
+                    .map(Quat::from_array)
+                    .collect(),
+            ),
+            2 => ChannelValues::Scale(
+                r.pod_vec::<[f32; 3]>()?
+                    .into_iter()
+                    .map(Vec3::from)
+                    .collect(),
+            ),
+            _ => {
+                let track_count = r.u32()?;
+                ChannelValues::Weights(
+                    (0..track_count)
+                        .map(|_| r.pod_vec())
+                        .collect::<Result<_>>()?,
+                )
+            }
+        };
+        // Cubic splines store an in-tangent, value and out-tangent per key.
+        let per_key = match interpolation {
+            Interpolation::CubicSpline => 3,
+            _ => 1,
+        };
+        let counts: Vec<usize> = match &values {
+            ChannelValues::Translation(v) | ChannelValues::Scale(v) => vec![v.len()],
+            ChannelValues::Rotation(v) => vec![v.len()],
+            ChannelValues::Weights(tracks) => tracks.iter().map(Vec::len).collect(),
+        };
+        if times.is_empty() {
+            bail!("cooked animation channel has no keyframes");
+        }
+        if let Some(&count) = counts.iter().find(|&&n| n != times.len() * per_key) {
+            bail!("{} keyframes but {count} output values", times.len());
+        }
+        channels.push(Channel {
+            node,
+            interpolation,
+            times,
+            values,
+        });
+    }
+    Ok(AnimationClip {
+        name,
+        duration,
+        channels,
+    })
+}
+
+fn write_transform(w: &mut Writer, t: &Transform) {
+    w.f32s(&t.translation.to_array());
+    w.f32s(&t.rotation.to_array());
+    w.f32s(&t.scale.to_array());
+}
+
+fn read_transform(r: &mut Reader) -> Result<Transform> {
+    Ok(Transform {
+        translation: Vec3::from_array(r.f32s()?),
+        rotation: Quat::from_array(r.f32s()?),
+        scale: Vec3::from_array(r.f32s()?),
+    })
+}
+
+fn write_aabb(w: &mut Writer, aabb: &Aabb) {
+    w.f32s(&aabb.min.to_array());
+    w.f32s(&aabb.max.to_array());
+}
+
+fn read_aabb(r: &mut Reader) -> Result<Aabb> {
+    Ok(Aabb {
+        min: Vec3::from_array(r.f32s()?),
+        max: Vec3::from_array(r.f32s()?),
+    })
+}
+
+fn read_floats(r: &mut Reader) -> Result<Vec<f32>> {
+    let len = r.u32()?;
+    (0..len).map(|_| r.f32()).collect()
+}
+
+// ASTC formats follow the fixed table, two codes (unorm, sRGB) per block size.
+fn format_code(format: TextureFormat) -> Option<u32> {
+    if let TextureFormat::Astc { block, channel } = format {
+        let ix = ASTC_BLOCKS.iter().position(|b| *b == block)?;
+        let srgb = matches!(channel, AstcChannel::UnormSrgb);
+        return Some((TEXTURE_FORMATS.len() + ix * 2 + srgb as usize) as u32);
+    }
+    TEXTURE_FORMATS
+        .iter()
+        .position(|f| *f == format)
+        .map(|ix| ix as u32)
+}
+
+fn code_format(code: u32) -> Option<TextureFormat> {
+    let code = code as usize;
+    if let Some(format) = TEXTURE_FORMATS.get(code) {
+        return Some(*format);
+    }
+    let ix = code - TEXTURE_FORMATS.len();
+    Some(TextureFormat::Astc {
+        block: *ASTC_BLOCKS.get(ix / 2)?,
+        channel: if ix.is_multiple_of(2) {
+            AstcChannel::Unorm
+        } else {
+            AstcChannel::UnormSrgb
+        },
+    })
+}
+
+fn write_texture_transform(w: &mut Writer, t: &TextureTransform) {
+    w.f32s(&t.offset.to_array());
+    w.f32(t.rotation);
+    w.f32s(&t.scale.to_array());
+    w.u32(t.tex_coord);
+}
+
+fn read_texture_transform(r: &mut Reader) -> Result<TextureTransform> {
+    Ok(TextureTransform {
+        offset: Vec2::from_array(r.f32s()?),
+        rotation: r.f32()?,
+        scale: Vec2::from_array(r.f32s()?),
+        tex_coord: r.u32()?,
+    })
+}
+
+fn write_params(w: &mut Writer, p: &MaterialParams) {
+    w.f32s(&p.base_color_factor.to_array());
+    write_texture_transform(w, &p.base_color_transform);
+    w.f32s(&[p.metallic_factor, p.roughness_factor]);
+    w.f32s(&p.emissive_factor.to_array());
+    w.f32(p.emissive_strength);
+    write_texture_transform(w, &p.emissive_transform);
+    w.u32(p.unlit as u32);
+    w.f32s(&[
+        p.clearcoat_factor,
+        p.clearcoat_roughness,
+        p.transmission_factor,
+    ]);
+}
+
+fn read_params(r: &mut Reader) -> Result<MaterialParams> {
+    let base_color_factor = Vec4::from_array(r.f32s()?);
+    let base_color_transform = read_texture_transform(r)?;
+    let [metallic_factor, roughness_factor] = r.f32s()?;
+    let emissive_factor = Vec3::from_array(r.f32s()?);
+    let emissive_strength = r.f32()?;
+    let emissive_transform = read_texture_transform(r)?;
+    let unlit = r.u32()? != 0;
+    let [clearcoat_factor, clearcoat_roughness, transmission_factor] = r.f32s()?;
+    Ok(MaterialParams {
+        base_color_factor,
+        base_color_transform,
+        metallic_factor,
+        roughness_factor,
+        emissive_factor,
+        emissive_strength,
+        emissive_transform,
+        unlit,
+        clearcoat_factor,
+        clearcoat_roughness,
+        transmission_factor,
+    })
+}
+
+fn write_streams(w: &mut Writer, s: &VertexStreams) {
+    w.pod_blob(&s.positions);
+    w.opt_pod_blob(s.normals.as_deref());
+    w.opt_pod_blob(s.uv0.as_deref());
+    w.opt_pod_blob(s.colors.as_deref());
+    w.opt_pod_blob(s.uv1.as_deref());
+    w.opt_pod_blob(s.tangents.as_deref());
+    w.opt_pod_blob(s.joints.as_deref());
+    w.opt_pod_blob(s.weights.as_deref());
+}
+
+fn read_streams(r: &mut Reader) -> Result<VertexStreams> {
+    Ok(VertexStreams {
+        positions: r.pod_vec()?,
+        normals: r.opt_pod_vec()?,
+        uv0: r.opt_pod_vec()?,
+        colors: r.opt_pod_vec()?,
+        uv1: r.opt_pod_vec()?,
+        tangents: r.opt_pod_vec()?,
+        joints: r.opt_pod_vec()?,
+        weights: r.opt_pod_vec()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Header;
+    use crate::test_util;
+    use minima_3d::LightKind;
+
+    const TRIANGLE: [[f32; 3]; 3] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+
+    fn header() -> Header {
+        Header {
+            settings: 0,
+            key: 0,
+            stamps: Vec::new(),
+        }
+    }
+
+    // Exact settings, so textures read back byte for byte.
+    fn settings() -> ImportSettings {
+        ImportSettings {
+            generate_mips: false,
+            compress_textures: false,
+            ..ImportSettings::default()
+        }
+    }
+
+    fn upload_mesh(
+        device: &Device,
+        cpu: CpuMesh,
+        node: Option<usize>,
+        skin: Option<usize>,
+    ) -> GpuMesh {
+        let vertex_count = cpu.vertices.len();
+        let vbuf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mesh_vbuf"),
+            contents: &cpu.vertices.interleave(),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let (ibuf, index_format) = create_index_buffer(device, &cpu.indices, vertex_count);
+        let morph = (!cpu.morph_targets.is_empty())
+            .then(|| GpuMorphTargets::new(device, &cpu.morph_targets, vertex_count, vec![0.25]));
+        GpuMesh {
+            vbuf,
+            ibuf,
+            index_count: cpu.indices.len() as u32,
+            index_format,
+            material_id: 0,
+            format: cpu.vertices.format(),
+            bounds: cpu.bounds,
+            node,
+            skin,
+            morph,
+            lods: cpu
+                .lods
+                .iter()
+                .map(|lod| MeshLod::upload(device, lod, vertex_count))
+                .collect(),
+            cpu: Some(Arc::new(cpu)),
+        }
+    }
+
+    fn model(device: &Device, queue: &Queue, registry: &MaterialRegistry) -> Model {
+        let pixels: Vec<u8> = (0..16u8).flat_map(|i| [i * 16, 255 - i, i, 255]).collect();
+        let albedo = Arc::new(GpuTexture::from_rgba8(
+            device,
+            queue,
+            "albedo",
+            4,
+            4,
+            TextureFormat::Rgba8UnormSrgb,
+            &pixels,
+        ));
+        let params = MaterialParams {
+            base_color_factor: Vec4::new(0.5, 0.25, 1.0, 1.0),
+            metallic_factor: 0.3,
+            unlit: true,
+            ..MaterialParams::default()
+        };
+        let material = registry.create_material_with_params(
+            device,
+            albedo,
+            registry.fallbacks.black.clone(),
+            params,
+        );
+
+        let mut vertices = VertexStreams::new(TRIANGLE.to_vec());
+        vertices.normals = Some(vec![[0.0, 0.0, 1.0]; 3]);
+        vertices.joints = Some(vec![[0, 1, 0, 0]; 3]);
+        vertices.weights = Some(vec![[0.75, 0.25, 0.0, 0.0]; 3]);
+        let mut cpu = CpuMesh::new(vertices, vec![0, 1, 2]);
+        cpu.morph_targets = vec![MorphTarget {
+            positions: vec![[0.0, 0.0, 1.0]; 3],
+            normals: None,
+        }];
+        cpu.lods = vec![CpuLod {
+            screen_size: 0.1,
+            vertices: None,
+            indices: vec![0, 1, 2],
+        }];
+
+        let nodes = vec![
+            Node {
+                name: Some("root".into()),
+                children: vec![1],
+                camera: Some(0),
+                light: Some(0),
+                ..Node::default()
+            },
+            Node {
+                name: Some("joint".into()),
+                parent: Some(0),
+                transform: Transform {
+                    translation: Vec3::new(1.0, 2.0, 3.0),
+                    rotation: Quat::from_rotation_z(0.5),
+                    scale: Vec3::splat(2.0),
+                },
+                skin: Some(0),
+                weights: vec![0.25],
+                ..Node::default()
+            },
+        ];
+        let clip = AnimationClip {
+            name: Some("wave".into()),
+            duration: 1.0,
+            channels: vec![
+                Channel {
+                    node: 1,
+                    interpolation: Interpolation::Linear,
+                    times: vec![0.0, 1.0],
+                    values: ChannelValues::Rotation(vec![
+                        Quat::IDENTITY,
+                        Quat::from_rotation_x(1.0),
+                    ]),
+                },
+                Channel {
+                    node: 1,
+                    interpolation: Interpolation::CubicSpline,
+                    times: vec![0.0, 1.0],
+                    values: ChannelValues::Weights(vec![vec![0.0, 0.0, 1.0, 0.0, 0.5, 0.0]]),
+                },
+            ],
+        };
+        let bounds = Aabb {
+            min: Vec3::ZERO,
+            max: Vec3::new(1.0, 1.0, 0.0),
+        };
+        Model {
+            meshes: vec![upload_mesh(device, cpu, Some(1), Some(0))],
+            materials: vec![Arc::new(material)],
+            nodes,
+            roots: vec![0],
+            skins: vec![Skin {
+                name: Some("rig".into()),
+                joints: vec![0, 1],
+                inverse_bind_matrices: vec![Mat4::IDENTITY, Mat4::from_translation(-Vec3::X)],
+                skeleton: Some(0),
+            }],
+            cameras: vec![Camera {
+                name: Some("eye".into()),
+                projection: CameraProjection::Perspective {
+                    yfov: 0.8,
+                    aspect_ratio: None,
+                    znear: 0.1,
+                    zfar: Some(100.0),
+                },
+            }],
+            lights: vec![Light {
+                name: Some("lamp".into()),
+                kind: LightKind::Spot {
+                    inner_cone_angle: 0.2,
+                    outer_cone_angle: 0.6,
+                },
+                color: Vec3::new(1.0, 0.5, 0.25),
+                intensity: 20.0,
+                range: None,
+            }],
+            animations: vec![Arc::new(clip)],
+            recommended_xform: minima_3d::recommended_xform(&bounds),
+            bounds,
+        }
+    }
+
+    #[test]
+    fn cooked_model_round_trips_through_the_gpu() {
+        let Some((device, queue)) = test_util::gpu() else {
+            return;
+        };
+        let layouts = minima_3d::create_bind_group_layouts(&device);
+        let registry = MaterialRegistry::new(&device, &queue, &layouts.material_bgl);
+        let original = model(&device, &queue, &registry);
+        let bytes = write_cooked(
+            &device,
+            &queue,
+            &registry,
+            &original,
+            &header(),
+            &settings(),
+        )
+        .unwrap();
+        let mut cache = TextureCache::new();
+        let loaded = load_cooked_model(&device, &queue, &registry, &mut cache, &bytes).unwrap();
+
+        assert_eq!(loaded.meshes.len(), 1);
+        let (a, b) = (&original.meshes[0], &loaded.meshes[0]);
+        assert_eq!(
+            (
+                a.index_count,
+                a.material_id,
+                a.format,
+                a.bounds,
+                a.node,
+                a.skin
+            ),
+            (
+                b.index_count,
+                b.material_id,
+                b.format,
+                b.bounds,
+                b.node,
+                b.skin
+            )
+        );
+        assert_eq!(format!("{:?}", a.cpu), format!("{:?}", b.cpu));
+        let morph = |mesh: &GpuMesh| mesh.morph.as_ref().map(|m| m.default_weights.clone());
+        assert_eq!(morph(a), morph(b));
+        assert_eq!(a.lods.len(), b.lods.len());
+
+        assert_eq!(loaded.materials.len(), 1);
+        let (a, b) = (&original.materials[0], &loaded.materials[0]);
+        assert_eq!(a.params, b.params);
+        assert!(Arc::ptr_eq(&b.emissive, &registry.fallbacks.black));
+        let texels = |material: &Material| {
+            read_texture(&device, &queue, &material.base_color.texture)
+                .unwrap()
+                .bytes
+        };
+        assert_eq!(texels(a), texels(b));
+
+        assert_eq!(
+            format!("{:?}", original.nodes),
+            format!("{:?}", loaded.nodes)
+        );
+        assert_eq!(original.roots, loaded.roots);
+        assert_eq!(
+            format!("{:?}", original.skins),
+            format!("{:?}", loaded.skins)
+        );
+        assert_eq!(original.cameras, loaded.cameras);
+        assert_eq!(
+            format!("{:?}", original.lights),
+            format!("{:?}", loaded.lights)
+        );
+        assert_eq!(
+            format!("{:?}", original.animations),
+            format!("{:?}", loaded.animations)
+        );
+        assert_eq!(original.bounds, loaded.bounds);
+        assert_eq!(original.recommended_xform, loaded.recommended_xform);
+    }
+
+    #[test]
+    fn truncated_or_inconsistent_cooks_are_errors() {
+        let Some((device, queue)) = test_util::gpu() else {
+            return;
+        };
+        let layouts = minima_3d::create_bind_group_layouts(&device);
+        let registry = MaterialRegistry::new(&device, &queue, &layouts.material_bgl);
+        let mut cache = TextureCache::new();
+        let mut load =
+            |bytes: &[u8]| load_cooked_model(&device, &queue, &registry, &mut cache, bytes);
+        let cook = |model: &Model| {
+            write_cooked(&device, &queue, &registry, model, &header(), &settings()).unwrap()
+        };
+
+        let bytes = cook(&model(&device, &queue, &registry));
+        for len in [0, 16, bytes.len() / 3, bytes.len() / 2, bytes.len() - 1] {
+            assert!(
+                load(&bytes[..len]).is_err(),
+                "{len} of {} bytes",
+                bytes.len()
+            );
+        }
+
+        let mut model = model(&device, &queue, &registry);
+        model.meshes[0].node = Some(7);
+        let err = load(&cook(&model)).err().unwrap();
+        assert!(err.to_string().contains("missing node"), "{err}");
+
+        model.meshes[0].node = Some(1);
+        model.meshes[0].skin = Some(3);
+        let err = load(&cook(&model)).err().unwrap();
+        assert!(err.to_string().contains("missing skin"), "{err}");
+
+        model.meshes[0].skin = Some(0);
+        let mut clip = (*model.animations[0]).clone();
+        clip.channels[0].node = 9;
+        model.animations[0] = Arc::new(clip.clone());
+        let err = load(&cook(&model)).err().unwrap();
+        assert!(err.to_string().contains("missing node"), "{err}");
+
+        // Cubic splines need three values per key.
+        clip.channels[0].node = 1;
+        clip.channels[1].values = ChannelValues::Weights(vec![vec![0.0, 1.0]]);
+        model.animations[0] = Arc::new(clip);
+        let err = load(&cook(&model)).err().unwrap();
+        assert!(err.to_string().contains("output values"), "{err}");
+    }
+}
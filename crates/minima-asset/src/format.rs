@@ -0,0 +1,324 @@
+use anyhow::{Result, bail};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+pub const MAGIC: [u8; 4] = *b"MNMC";
+pub const VERSION: u32 = 5;
+// Magic, version, settings key, content key and the byte length of the stamp table.
+pub const HEADER_SIZE: usize = 32;
+
+// Size and modification time of one input file, so a load can tell the cook is current from
+// metadata alone. Paths are relative to the source file's directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stamp {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: u64,
+}
+
+impl Stamp {
+    pub fn read(base: &Path, path: PathBuf) -> Self {
+        let meta = std::fs::metadata(base.join(&path)).ok();
+        let size = meta.as_ref().map_or(u64::MAX, |m| m.len());
+        let modified = meta
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(u64::MAX, |d| d.as_nanos() as u64);
+        Self {
+            path,
+            size,
+            modified,
+        }
+    }
+
+    pub fn is_current(&self, base: &Path) -> bool {
+        *self == Self::read(base, self.path.clone())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Header {
+    pub settings: u64,
+    pub key: u64,
+    pub stamps: Vec<Stamp>,
+}
+
+#[derive(Default)]
+pub struct Writer {
+    pub bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new(header: &Header) -> Self {
+        let mut stamps = Self::default();
+        for stamp in &header.stamps {
+            stamps.string(Some(&stamp.path.to_string_lossy()));
+            stamps.u64(stamp.size);
+            stamps.u64(stamp.modified);
+        }
+        let mut w = Self::default();
+        w.bytes.extend_from_slice(&MAGIC);
+        w.u32(VERSION);
+        w.u64(header.settings);
+        w.u64(header.key);
+        w.u32(header.stamps.len() as u32);
+        w.u32(stamps.bytes.len() as u32);
+        w.bytes.extend_from_slice(&stamps.bytes);
+        w
+    }
+
+    pub fn u32(&mut self, v: u32) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, v: u64) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, v: f32) {
+        self.bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn f32s(&mut self, v: &[f32]) {
+        for &x in v {
+            self.f32(x);
+        }
+    }
+
+    pub fn index(&mut self, v: Option<usize>) {
+        self.u32(v.map_or(u32::MAX, |v| v as u32));
+    }
+
+    pub fn indices(&mut self, v: &[usize]) {
+        self.u32(v.len() as u32);
+        for &x in v {
+            self.u32(x as u32);
+        }
+    }
+
+    pub fn string(&mut self, v: Option<&str>) {
+        match v {
+            Some(s) => self.blob(s.as_bytes()),
+            None => self.u64(u64::MAX),
+        }
+    }
+
+    pub fn blob(&mut self, v: &[u8]) {
+        self.u64(v.len() as u64);
+        self.bytes.extend_from_slice(v);
+    }
+
+    pub fn pod_blob<T: bytemuck::Pod>(&mut self, v: &[T]) {
+        self.blob(bytemuck::cast_slice(v));
+    }
+
+    pub fn opt_pod_blob<T: bytemuck::Pod>(&mut self, v: Option<&[T]>) {
+        match v {
+            Some(v) => self.pod_blob(v),
+            None => self.u64(u64::MAX),
+        }
+    }
+}
+
+pub struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Result<(Self, Header)> {
+        let Some((settings, key, count, len)) = parse_fixed(data) else {
+            bail!("not a cooked minima asset or wrong format version");
+        };
+        let mut r = Self {
+            data,
+            pos: HEADER_SIZE,
+        };
+        let stamps = r.stamps(count)?;
+        if r.pos != HEADER_SIZE + len {
+            bail!("cooked asset has a malformed stamp table");
+        }
+        Ok((
+            r,
+            Header {
+                settings,
+                key,
+                stamps,
+            },
+        ))
+    }
+
+    // Offset of the first byte after the header.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn stamps(&mut self, count: usize) -> Result<Vec<Stamp>> {
+        (0..count)
+            .map(|_| {
+                Ok(Stamp {
+                    path: PathBuf::from(self.string()?.unwrap_or_default()),
+                    size: self.u64()?,
+                    modified: self.u64()?,
+                })
+            })
+            .collect()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let Some(end) = self.pos.checked_add(len) else {
+            bail!("cooked asset has an invalid length");
+        };
+        let Some(bytes) = self.data.get(self.pos..end) else {
+            bail!("cooked asset is truncated");
+        };
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    pub fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    pub fn f32s<const N: usize>(&mut self) -> Result<[f32; N]> {
+        let mut out = [0.0; N];
+        for v in &mut out {
+            *v = self.f32()?;
+        }
+        Ok(out)
+    }
+
+    pub fn index(&mut self) -> Result<Option<usize>> {
+        let v = self.u32()?;
+        Ok((v != u32::MAX).then_some(v as usize))
+    }
+
+    pub fn indices(&mut self) -> Result<Vec<usize>> {
+        let len = self.u32()?;
+        (0..len).map(|_| Ok(self.u32()? as usize)).collect()
+    }
+
+    pub fn string(&mut self) -> Result<Option<String>> {
+        Ok(self
+            .opt_blob()?
+            .map(|b| String::from_utf8_lossy(b).into_owned()))
+    }
+
+    pub fn blob(&mut self) -> Result<&'a [u8]> {
+        match self.opt_blob()? {
+            Some(b) => Ok(b),
+            None => bail!("cooked asset is missing required data"),
+        }
+    }
+
+    pub fn opt_blob(&mut self) -> Result<Option<&'a [u8]>> {
+        let len = self.u64()?;
+        if len == u64::MAX {
+            return Ok(None);
+        }
+        self.take(len as usize).map(Some)
+    }
+
+    pub fn pod_vec<T: bytemuck::Pod>(&mut self) -> Result<Vec<T>> {
+        Ok(bytemuck::pod_collect_to_vec(self.blob()?))
+    }
+
+    pub fn opt_pod_vec<T: bytemuck::Pod>(&mut self) -> Result<Option<Vec<T>>> {
+        Ok(self.opt_blob()?.map(bytemuck::pod_collect_to_vec))
+    }
+}
+
+fn parse_fixed(data: &[u8]) -> Option<(u64, u64, usize, usize)> {
+    if data.len() < HEADER_SIZE || data[..4] != MAGIC {
+        return None;
+    }
+    let u32_at = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+    let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+    (u32_at(4) == VERSION).then(|| {
+        (
+            u64_at(8),
+            u64_at(16),
+            u32_at(24) as usize,
+            u32_at(28) as usize,
+        )
+    })
+}
+
+// Reads just the header of a cooked file, without touching the body.
+pub fn read_header(path: &Path) -> Option<Header> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut data = vec![0u8; HEADER_SIZE];
+    file.read_exact(&mut data).ok()?;
+    let (.., len) = parse_fixed(&data)?;
+    data.resize(HEADER_SIZE + len, 0);
+    file.read_exact(&mut data[HEADER_SIZE..]).ok()?;
+    Reader::new(&data).ok().map(|(_, header)| header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Header {
+        Header {
+            settings: 7,
+            key: 0xdead_beef,
+            stamps: vec![Stamp {
+                path: PathBuf::from("textures/albedo.png"),
+                size: 1234,
+                modified: 99,
+            }],
+        }
+    }
+
+    #[test]
+    fn header_round_trips_and_body_follows_it() {
+        let mut w = Writer::new(&header());
+        w.u32(42);
+        let (mut r, read) = Reader::new(&w.bytes).unwrap();
+        assert_eq!(read, header());
+        assert_eq!(r.u32().unwrap(), 42);
+    }
+
+    #[test]
+    fn read_header_stops_before_the_body() {
+        let dir = std::env::temp_dir().join(format!("minima-format-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("header.mnm");
+        let mut w = Writer::new(&header());
+        w.blob(&[1; 64]);
+        std::fs::write(&path, &w.bytes).unwrap();
+        assert_eq!(read_header(&path), Some(header()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn oversized_lengths_fail_instead_of_overflowing() {
+        let mut w = Writer::new(&Header::default());
+        w.u64(u64::MAX - 1);
+        let (mut r, _) = Reader::new(&w.bytes).unwrap();
+        assert!(r.blob().is_err());
+
+        let mut r = Reader {
+            data: &w.bytes,
+            pos: usize::MAX - 2,
+        };
+        assert!(r.u32().is_err());
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let mut bytes = Writer::new(&header()).bytes;
+        bytes[4..8].copy_from_slice(&(VERSION - 1).to_le_bytes());
+        assert!(Reader::new(&bytes).is_err());
+    }
+}
@@ -0,0 +1,13 @@
+mod cooked;
+mod format;
+mod mips;
+mod pipeline;
+#[cfg(test)]
+mod test_util;
+
+pub use cooked::{load_cooked_model, write_cooked};
+pub use format::{Header, Stamp, read_header};
+pub use mips::{build_mips, mip_level_count};
+pub use pipeline::{
+    AssetPipeline, COOKED_EXTENSION, CookJob, CookProgress, CookReport, CookStatus, ImportSettings,
+};
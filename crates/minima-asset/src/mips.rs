@@ -0,0 +1,89 @@
+use half::f16;
+use minima_gltf::{DecodedImage, linear_to_srgb, srgb_to_linear};
+use wgpu::TextureFormat;
+
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+pub fn build_mips(image: &DecodedImage) -> (u32, Vec<u8>) {
+    let levels = mip_level_count(image.width, image.height);
+    let mut bytes = image.bytes.clone();
+    let mut pixels = decode(image.format, &image.bytes);
+    let (mut width, mut height) = (image.width as usize, image.height as usize);
+    for _ in 1..levels {
+        let (w, h) = ((width / 2).max(1), (height / 2).max(1));
+        let mut next = Vec::with_capacity(w * h);
+        for y in 0..h {
+            for x in 0..w {
+                let mut sum = [0.0f32; 4];
+                for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let px = (x * 2 + sx).min(width - 1);
+                    let py = (y * 2 + sy).min(height - 1);
+                    let p = pixels[py * width + px];
+                    for c in 0..4 {
+                        sum[c] += p[c] * 0.25;
+                    }
+                }
+                next.push(sum);
+            }
+        }
+        bytes.extend_from_slice(&encode(image.format, &next));
+        pixels = next;
+        (width, height) = (w, h);
+    }
+    (levels, bytes)
+}
+
+fn decode(format: TextureFormat, bytes: &[u8]) -> Vec<[f32; 4]> {
+    match format {
+        TextureFormat::Rgba16Float => bytes
+            .chunks_exact(8)
+            .map(|p| {
+                let c = |i: usize| f16::from_le_bytes([p[i], p[i + 1]]).to_f32();
+                [c(0), c(2), c(4), c(6)]
+            })
+            .collect(),
+        _ => {
+            let srgb = format.is_srgb();
+            bytes
+                .chunks_exact(4)
+                .map(|p| {
+                    let c = |i: usize| {
+                        let v = p[i] as f32 / 255.0;
+                        if srgb && i < 3 { srgb_to_linear(v) } else { v }
+                    };
+                    [c(0), c(1), c(2), c(3)]
+                })
+                .collect()
+        }
+    }
+}
+
+fn encode(format: TextureFormat, pixels: &[[f32; 4]]) -> Vec<u8> {
+    match format {
+        TextureFormat::Rgba16Float => pixels
+            .iter()
+            .flat_map(|p| p.map(|v| f16::from_f32(v).to_le_bytes()))
+            .flatten()
+            .collect(),
+        _ => {
+            let srgb = format.is_srgb();
+            pixels
+                .iter()
+                .flat_map(|p| {
+                    let mut out = [0u8; 4];
+                    for (i, v) in p.iter().enumerate() {
+                        let v = if srgb && i < 3 {
+                            linear_to_srgb(*v)
+                        } else {
+                            *v
+                        };
+                        out[i] = (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+                    }
+                    out
+                })
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,669 @@
+use anyhow::{Context, Result};
+use minima_3d::{LodLevel, MaterialRegistry, Model};
+use minima_gltf::{LoadOptions, TextureCache, load_gltf_model};
+use minima_obj::{load_obj_model, texture_path};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use wgpu::{Device, Features, Queue};
+use xxhash_rust::xxh3::{Xxh3, xxh3_64};
+
+use crate::cooked::{load_cooked_model, write_cooked};
+use crate::format::{Header, Reader, Stamp, VERSION, Writer, read_header};
+
+pub const COOKED_EXTENSION: &str = "mnm";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportSettings {
+    pub generate_mips: bool,
+    pub retain_cpu_data: bool,
+    pub optimize_meshes: bool,
+    pub lod_levels: Vec<LodLevel>,
+    pub compress_textures: bool,
+}
+
+impl Default for ImportSettings {
+    fn default() -> Self {
+        Self {
+            generate_mips: true,
+            retain_cpu_data: true,
+            optimize_meshes: true,
+            lod_levels: LodLevel::DEFAULT_CHAIN.to_vec(),
+            compress_textures: true,
+        }
+    }
+}
+
+impl ImportSettings {
+    // Mesh options of a runtime load replace the pipeline's; texture settings are kept.
+    pub fn with_load_options(&self, options: &LoadOptions) -> Self {
+        Self {
+            retain_cpu_data: options.retain_cpu_data,
+            optimize_meshes: options.optimize_meshes,
+            lod_levels: options.lod_levels.clone(),
+            ..self.clone()
+        }
+    }
+
+    pub fn load_options(&self) -> LoadOptions {
+        LoadOptions {
+            retain_cpu_data: self.retain_cpu_data,
+            optimize_meshes: self.optimize_meshes,
+            lod_levels: self.lod_levels.clone(),
+        }
+    }
+
+    // Compressed sources are kept or decoded depending on what the device samples, so its
+    // compression features are part of the key.
+    fn key(&self, features: Features) -> u64 {
+        let mut hasher = Xxh3::new();
+        hasher.update(&VERSION.to_le_bytes());
+        hasher.update(&[
+            self.generate_mips as u8,
+            self.retain_cpu_data as u8,
+            self.optimize_meshes as u8,
+            self.compress_textures as u8,
+        ]);
+        hasher.update(&[
+            features.contains(Features::TEXTURE_COMPRESSION_BC) as u8,
+            features.contains(Features::TEXTURE_COMPRESSION_ETC2) as u8,
+            features.contains(Features::TEXTURE_COMPRESSION_ASTC) as u8,
+        ]);
+        for level in &self.lod_levels {
+            for v in [level.index_ratio, level.max_error, level.screen_size] {
+                hasher.update(&v.to_le_bytes());
+            }
+        }
+        hasher.digest()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CookStatus {
+    Cooked,
+    UpToDate,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CookReport {
+    pub cooked: Vec<PathBuf>,
+    pub up_to_date: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CookProgress {
+    pub done: usize,
+    pub total: usize,
+    pub current: Option<PathBuf>,
+}
+
+// A `cook_all` running on its own thread; poll it from the frame loop.
+pub struct CookJob {
+    progress: Arc<Mutex<CookProgress>>,
+    handle: Option<JoinHandle<CookReport>>,
+}
+
+impl CookJob {
+    pub fn progress(&self) -> CookProgress {
+        self.progress.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    // Returns the report once the worker is done, without blocking.
+    pub fn try_finish(&mut self) -> Option<CookReport> {
+        if !self.is_finished() {
+            return None;
+        }
+        let handle = self.handle.take()?;
+        Some(handle.join().unwrap_or_else(|_| CookReport {
+            failed: vec![(PathBuf::new(), "asset cook panicked".to_owned())],
+            ..CookReport::default()
+        }))
+    }
+}
+
+enum Freshness {
+    Current,
+    // The content is unchanged but the stamps aren't, e.g. after a checkout touched the files.
+    Restamp(Header),
+    Stale(Header),
+}
+
+#[derive(Clone, Debug)]
+pub struct AssetPipeline {
+    pub source_dir: PathBuf,
+    pub cache_dir: PathBuf,
+    pub settings: ImportSettings,
+}
+
+impl AssetPipeline {
+    pub fn new(source_dir: impl Into<PathBuf>, cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            source_dir: source_dir.into(),
+            cache_dir: cache_dir.into(),
+            settings: ImportSettings::default(),
+        }
+    }
+
+    pub fn is_supported(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                ["gltf", "glb", "obj"]
+                    .iter()
+                    .any(|s| ext.eq_ignore_ascii_case(s))
+            })
+    }
+
+    pub fn cooked_path(&self, source: &Path) -> PathBuf {
+        let source = source
+            .canonicalize()
+            .unwrap_or_else(|_| source.to_path_buf());
+        let source_dir = self
+            .source_dir
+            .canonicalize()
+            .unwrap_or_else(|_| self.source_dir.clone());
+        let mut name = match source.strip_prefix(&source_dir) {
+            Ok(relative) => relative.as_os_str().to_owned(),
+            // Sources outside the tree are keyed by their full path so equal names can't collide.
+            Err(_) => {
+                let hash = xxh3_64(source.as_os_str().as_encoded_bytes());
+                let mut name = OsString::from(format!("external/{hash:016x}-"));
+                name.push(source.file_name().unwrap_or_default());
+                name
+            }
+        };
+        name.push(".");
+        name.push(COOKED_EXTENSION);
+        self.cache_dir.join(name)
+    }
+
+    // `features` are those of the device the source is cooked for.
+    pub fn source_key(&self, source: &Path, features: Features) -> Result<u64> {
+        let bytes =
+            std::fs::read(source).with_context(|| format!("reading {}", source.display()))?;
+        let deps = dependencies(source, &bytes);
+        Ok(content_key(
+            self.settings.key(features),
+            source,
+            &bytes,
+            &deps,
+        ))
+    }
+
+    pub fn is_up_to_date(&self, source: &Path, features: Features) -> Result<bool> {
+        Ok(!matches!(
+            self.freshness(source, &self.settings, features)?,
+            Freshness::Stale(_)
+        ))
+    }
+
+    // Stamps are checked first so a current cook costs a few `stat`s; the source is only read
+    // and hashed when one of them changed.
+    fn freshness(
+        &self,
+        source: &Path,
+        settings: &ImportSettings,
+        features: Features,
+    ) -> Result<Freshness> {
+        let settings_key = settings.key(features);
+        let base = source_base(source);
+        let old = read_header(&self.cooked_path(source)).filter(|h| h.settings == settings_key);
+        if let Some(old) = &old
+            && !old.stamps.is_empty()
+            && old.stamps.iter().all(|s| s.is_current(base))
+        {
+            return Ok(Freshness::Current);
+        }
+
+        // Stamp before reading, so an edit racing the read leaves the stamps stale, not the key.
+        let mut stamps = vec![Stamp::read(base, source_name(source))];
+        let bytes =
+            std::fs::read(source).with_context(|| format!("reading {}", source.display()))?;
+        let deps = dependencies(source, &bytes);
+        stamps.extend(deps.iter().map(|dep| Stamp::read(base, dep.clone())));
+        let header = Header {
+            settings: settings_key,
+            key: content_key(settings_key, source, &bytes, &deps),
+            stamps,
+        };
+        Ok(match old {
+            Some(old) if old.key == header.key => Freshness::Restamp(header),
+            _ => Freshness::Stale(header),
+        })
+    }
+
+    pub async fn cook(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        registry: &MaterialRegistry,
+        source: &Path,
+    ) -> Result<CookStatus> {
+        self.cook_with(device, queue, registry, source, &self.settings)
+            .await
+    }
+
+    async fn cook_with(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        registry: &MaterialRegistry,
+        source: &Path,
+        settings: &ImportSettings,
+    ) -> Result<CookStatus> {
+        let cooked_path = self.cooked_path(source);
+        let header = match self.freshness(source, settings, device.features())? {
+            Freshness::Current => return Ok(CookStatus::UpToDate),
+            Freshness::Restamp(header) => {
+                restamp(&cooked_path, &header)
+                    .with_context(|| format!("updating {}", cooked_path.display()))?;
+                return Ok(CookStatus::UpToDate);
+            }
+            Freshness::Stale(header) => header,
+        };
+
+        let model = load_source(device, queue, registry, source, settings).await?;
+        let bytes = write_cooked(device, queue, registry, &model, &header, settings)
+            .with_context(|| format!("cooking {}", source.display()))?;
+        if let Some(dir) = cooked_path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        }
+        std::fs::write(&cooked_path, bytes)
+            .with_context(|| format!("writing {}", cooked_path.display()))?;
+        Ok(CookStatus::Cooked)
+    }
+
+    pub async fn cook_all(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        registry: &MaterialRegistry,
+    ) -> CookReport {
+        self.cook_all_with_progress(device, queue, registry, |_| {})
+            .await
+    }
+
+    pub async fn cook_all_with_progress(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        registry: &MaterialRegistry,
+        mut progress: impl FnMut(CookProgress),
+    ) -> CookReport {
+        let mut report = CookReport::default();
+        let mut sources = Vec::new();
+        collect_sources(&self.source_dir, &mut sources);
+        sources.sort();
+        let total = sources.len();
+        for (done, source) in sources.into_iter().enumerate() {
+            progress(CookProgress {
+                done,
+                total,
+                current: Some(source.clone()),
+            });
+            match self.cook(device, queue, registry, &source).await {
+                Ok(CookStatus::Cooked) => report.cooked.push(source),
+                Ok(CookStatus::UpToDate) => report.up_to_date.push(source),
+                Err(err) => report.failed.push((source, format!("{err:#}"))),
+            }
+        }
+        progress(CookProgress {
+            done: total,
+            total,
+            current: None,
+        });
+        report
+    }
+
+    // Runs `cook_all` on a worker thread so the caller's frame loop keeps going.
+    pub fn spawn_cook_all(
+        &self,
+        device: Device,
+        queue: Queue,
+        registry: Arc<MaterialRegistry>,
+    ) -> CookJob {
+        let pipeline = self.clone();
+        let progress = Arc::new(Mutex::new(CookProgress::default()));
+        let shared = progress.clone();
+        let handle = std::thread::spawn(move || {
+            pollster::block_on(
+                pipeline.cook_all_with_progress(&device, &queue, &registry, |p| {
+                    if let Ok(mut shared) = shared.lock() {
+                        *shared = p;
+                    }
+                }),
+            )
+        });
+        CookJob {
+            progress,
+            handle: Some(handle),
+        }
+    }
+
+    // Cooks with `options` in place of the pipeline's mesh settings, then loads the result with
+    // its textures shared through `textures`.
+    pub async fn load(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        registry: &MaterialRegistry,
+        textures: &mut TextureCache,
+        source: &Path,
+        options: &LoadOptions,
+    ) -> Result<Model> {
+        let settings = self.settings.with_load_options(options);
+        self.cook_with(device, queue, registry, source, &settings)
+            .await?;
+        let cooked_path = self.cooked_path(source);
+        let bytes = std::fs::read(&cooked_path)
+            .with_context(|| format!("reading {}", cooked_path.display()))?;
+        load_cooked_model(device, queue, registry, textures, &bytes)
+            .with_context(|| format!("loading {}", cooked_path.display()))
+    }
+}
+
+async fn load_source(
+    device: &Device,
+    queue: &Queue,
+    registry: &MaterialRegistry,
+    source: &Path,
+    settings: &ImportSettings,
+) -> Result<Model> {
+    let mut textures = TextureCache::new();
+    let options = LoadOptions {
+        retain_cpu_data: true,
+        ..settings.load_options()
+    };
+    if is_obj(source) {
+        load_obj_model(device, queue, registry, &mut textures, source, &options).await
+    } else {
+        load_gltf_model(device, queue, registry, &mut textures, source, &options).await
+    }
+}
+
+fn content_key(settings_key: u64, source: &Path, bytes: &[u8], deps: &[PathBuf]) -> u64 {
+    let mut hasher = Xxh3::new();
+    hasher.update(&settings_key.to_le_bytes());
+    hasher.update(bytes);
+    let base = source_base(source);
+    for dep in deps {
+        hasher.update(dep.as_os_str().as_encoded_bytes());
+        match std::fs::read(base.join(dep)) {
+            Ok(data) => {
+                hasher.update(&(data.len() as u64).to_le_bytes());
+                hasher.update(&data);
+            }
+            Err(_) => hasher.update(&u64::MAX.to_le_bytes()),
+        }
+    }
+    hasher.digest()
+}
+
+fn restamp(cooked_path: &Path, header: &Header) -> Result<()> {
+    let data = std::fs::read(cooked_path)?;
+    let (r, _) = Reader::new(&data)?;
+    let mut w = Writer::new(header);
+    w.bytes.extend_from_slice(&data[r.position()..]);
+    std::fs::write(cooked_path, w.bytes)?;
+    Ok(())
+}
+
+fn source_base(source: &Path) -> &Path {
+    source.parent().unwrap_or(Path::new(""))
+}
+
+fn source_name(source: &Path) -> PathBuf {
+    source.file_name().map(PathBuf::from).unwrap_or_default()
+}
+
+fn is_obj(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("obj"))
+}
+
+fn collect_sources(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_sources(&path, out);
+        } else if AssetPipeline::is_supported(&path) {
+            out.push(path);
+        }
+    }
+}
+
+fn dependencies(source: &Path, bytes: &[u8]) -> Vec<PathBuf> {
+    if is_obj(source) {
+        return obj_dependencies(source, bytes);
+    }
+    let Ok(gltf) = gltf::Gltf::from_slice_without_validation(bytes) else {
+        return Vec::new();
+    };
+    let buffers = gltf.buffers().filter_map(|b| match b.source() {
+        gltf::buffer::Source::Uri(uri) => Some(uri),
+        gltf::buffer::Source::Bin => None,
+    });
+    let images = gltf.images().filter_map(|i| match i.source() {
+        gltf::image::Source::Uri { uri, .. } => Some(uri),
+        gltf::image::Source::View { .. } => None,
+    });
+    buffers
+        .chain(images)
+        .filter(|uri| !uri.starts_with("data:"))
+        .filter_map(|uri| urlencoding::decode(uri).ok())
+        .map(|uri| PathBuf::from(uri.into_owned()))
+        .collect()
+}
+
+fn obj_dependencies(source: &Path, bytes: &[u8]) -> Vec<PathBuf> {
+    let base = source.parent().unwrap_or(Path::new(""));
+    let mut deps = Vec::new();
+    for lib in statements(bytes, "mtllib") {
+        let mtl = PathBuf::from(lib);
+        if let Ok(data) = std::fs::read(base.join(&mtl)) {
+            // Resolved as the loader does, so the stamp follows the file it reads.
+            for map in statements(&data, "map_Kd") {
+                deps.push(texture_path(Path::new(""), map));
+            }
+        }
+        deps.push(mtl);
+    }
+    deps
+}
+
+fn statements<'a>(bytes: &'a [u8], keyword: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    std::str::from_utf8(bytes)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(move |line| {
+            let rest = line.trim().strip_prefix(keyword)?;
+            rest.starts_with(char::is_whitespace)
+                .then(|| rest.split_whitespace().last())
+                .flatten()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("minima-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const GLTF: &str =
+        r#"{"asset":{"version":"2.0"},"buffers":[{"uri":"data.bin","byteLength":4}]}"#;
+
+    fn write_cooked_stub(path: &Path, header: &Header, body: &[u8]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut w = Writer::new(header);
+        w.bytes.extend_from_slice(body);
+        std::fs::write(path, w.bytes).unwrap();
+    }
+
+    fn set_mtime(path: &Path, secs: u64) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn stamps_short_circuit_and_content_changes_invalidate() {
+        let tmp = TempDir::new("freshness");
+        let source = tmp.0.join("assets/model.gltf");
+        std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+        std::fs::write(&source, GLTF).unwrap();
+        std::fs::write(tmp.0.join("assets/data.bin"), [1, 2, 3, 4]).unwrap();
+        let pipeline = AssetPipeline::new(tmp.0.join("assets"), tmp.0.join("cache"));
+        let cooked = pipeline.cooked_path(&source);
+
+        let Freshness::Stale(header) = pipeline
+            .freshness(&source, &pipeline.settings, Features::empty())
+            .unwrap()
+        else {
+            panic!("nothing is cooked yet");
+        };
+        let names: Vec<_> = header.stamps.iter().map(|s| s.path.clone()).collect();
+        assert_eq!(
+            names,
+            [PathBuf::from("model.gltf"), PathBuf::from("data.bin")]
+        );
+        write_cooked_stub(&cooked, &header, b"body");
+        assert!(pipeline.is_up_to_date(&source, Features::empty()).unwrap());
+
+        // Same bytes with a new mtime only needs the header rewritten.
+        set_mtime(&source, 1_000);
+        let Freshness::Restamp(header) = pipeline
+            .freshness(&source, &pipeline.settings, Features::empty())
+            .unwrap()
+        else {
+            panic!("unchanged content should be restamped");
+        };
+        restamp(&cooked, &header).unwrap();
+        assert!(matches!(
+            pipeline
+                .freshness(&source, &pipeline.settings, Features::empty())
+                .unwrap(),
+            Freshness::Current
+        ));
+        let data = std::fs::read(&cooked).unwrap();
+        let (r, _) = Reader::new(&data).unwrap();
+        assert_eq!(&data[r.position()..], b"body");
+
+        std::fs::write(tmp.0.join("assets/data.bin"), [9, 9, 9, 9, 9]).unwrap();
+        assert!(!pipeline.is_up_to_date(&source, Features::empty()).unwrap());
+    }
+
+    #[test]
+    fn obj_textures_resolve_against_the_obj_like_the_loader() {
+        let tmp = TempDir::new("obj-deps");
+        std::fs::create_dir_all(tmp.0.join("materials")).unwrap();
+        std::fs::write(
+            tmp.0.join("materials/crate.mtl"),
+            "newmtl wood\nmap_Kd -s 1 1 1 textures\\wood.png\n",
+        )
+        .unwrap();
+        let source = tmp.0.join("crate.obj");
+        let obj = b"mtllib materials/crate.mtl\nv 0 0 0\n";
+
+        let deps = dependencies(&source, obj);
+        assert_eq!(
+            deps,
+            [
+                PathBuf::from("textures/wood.png"),
+                PathBuf::from("materials/crate.mtl")
+            ]
+        );
+        assert_eq!(
+            tmp.0.join(&deps[0]),
+            texture_path(&tmp.0, "textures\\wood.png")
+        );
+    }
+
+    #[test]
+    fn load_options_change_the_settings_key() {
+        let settings = ImportSettings::default();
+        assert_eq!(
+            settings.with_load_options(&settings.load_options()),
+            settings
+        );
+        let plain = settings.with_load_options(&LoadOptions::default());
+        assert!(plain.lod_levels.is_empty());
+        assert_ne!(
+            plain.key(Features::empty()),
+            settings.key(Features::empty())
+        );
+    }
+
+    #[test]
+    fn device_compression_support_changes_the_settings_key() {
+        let settings = ImportSettings::default();
+        let keys: Vec<u64> = [
+            Features::empty(),
+            Features::TEXTURE_COMPRESSION_BC,
+            Features::TEXTURE_COMPRESSION_ETC2,
+            Features::TEXTURE_COMPRESSION_ASTC,
+        ]
+        .into_iter()
+        .map(|features| settings.key(features))
+        .collect();
+        for (i, a) in keys.iter().enumerate() {
+            assert!(keys[i + 1..].iter().all(|b| a != b));
+        }
+        // Features that do not affect cooking leave the key alone.
+        assert_eq!(
+            settings.key(Features::DEPTH_CLIP_CONTROL),
+            settings.key(Features::empty())
+        );
+    }
+
+    #[test]
+    fn sources_outside_the_tree_are_keyed_by_full_path() {
+        let tmp = TempDir::new("cooked-path");
+        let pipeline = AssetPipeline::new(tmp.0.join("assets"), tmp.0.join("cache"));
+        std::fs::create_dir_all(tmp.0.join("assets/sub")).unwrap();
+        let inside = tmp.0.join("assets/sub/a.glb");
+        assert_eq!(
+            pipeline.cooked_path(&inside),
+            tmp.0.join("cache/sub/a.glb.mnm")
+        );
+
+        let a = tmp.0.join("one/a.glb");
+        let b = tmp.0.join("two/a.glb");
+        for path in [&a, &b] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        let (ca, cb) = (pipeline.cooked_path(&a), pipeline.cooked_path(&b));
+        assert_ne!(ca, cb);
+        assert!(ca.starts_with(tmp.0.join("cache/external")));
+        assert!(ca.to_string_lossy().ends_with("-a.glb.mnm"));
+    }
+}
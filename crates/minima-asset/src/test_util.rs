@@ -0,0 +1,9 @@
+// GPU-backed tests return early on machines without an adapter.
+pub fn gpu() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::default();
+    let Ok(adapter) = pollster::block_on(instance.request_adapter(&Default::default())) else {
+        eprintln!("no adapter, skipping");
+        return None;
+    };
+    pollster::block_on(adapter.request_device(&Default::default())).ok()
+}
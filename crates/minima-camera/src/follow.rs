@@ -0,0 +1,187 @@
+use glam::Vec3;
+
+use crate::{OrbitCamera, Ray, forward_from_yaw_pitch};
+
+const MIN_DISTANCE: f32 = 0.1;
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+// Critically damped spring; `frequency` is in radians per second.
+fn spring(value: &mut f32, velocity: &mut f32, target: f32, frequency: f32, dt: f32) {
+    let offset = *value - target;
+    let decay = (-frequency * dt).exp();
+    let temp = (*velocity + frequency * offset) * dt;
+    *velocity = (*velocity - frequency * temp) * decay;
+    *value = target + (offset + temp) * decay;
+}
+
+fn spring3(value: &mut Vec3, velocity: &mut Vec3, target: Vec3, frequency: f32, dt: f32) {
+    for c in 0..3 {
+        spring(&mut value[c], &mut velocity[c], target[c], frequency, dt);
+    }
+}
+
+pub struct FollowCamera {
+    // Index of the followed model in the scene; it is tracked and kept out of its own way.
+    pub entity: usize,
+    pub target_offset: Vec3,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub follow_frequency: f32,
+    pub distance_frequency: f32,
+    pub collision_radius: f32,
+    focus: Vec3,
+    focus_velocity: Vec3,
+    current_distance: f32,
+    distance_velocity: f32,
+    active: bool,
+}
+
+impl FollowCamera {
+    pub fn new(distance: f32) -> Self {
+        Self {
+            entity: 0,
+            target_offset: Vec3::new(0.0, 1.5, 0.0),
+            distance,
+            yaw: 0.0,
+            pitch: -0.3,
+            follow_frequency: 8.0,
+            distance_frequency: 4.0,
+            collision_radius: 0.2,
+            focus: Vec3::ZERO,
+            focus_velocity: Vec3::ZERO,
+            current_distance: distance,
+            distance_velocity: 0.0,
+            active: false,
+        }
+    }
+
+    // Mouse or stick input; callers scale stick deflection by dt.
+    pub fn orbit(&mut self, d_yaw: f32, d_pitch: f32) {
+        self.yaw += d_yaw;
+        self.pitch = (self.pitch + d_pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).max(MIN_DISTANCE);
+    }
+
+    // The next update starts from the camera's current view instead of snapping.
+    pub fn deactivate(&mut self) {
+        self.active = false;
+    }
+
+    // `cast` returns the distance to the first scene hit along the ray within the limit.
+    pub fn update(
+        &mut self,
+        cam: &mut OrbitCamera,
+        target: Vec3,
+        dt: f32,
+        mut cast: impl FnMut(&Ray, f32) -> Option<f32>,
+    ) {
+        let goal = target + self.target_offset;
+        if !self.active {
+            let reach = cam.eye.distance(goal).max(MIN_DISTANCE);
+            self.yaw = cam.yaw;
+            self.pitch = cam.pitch;
+            self.focus = cam.eye + cam.forward() * reach;
+            self.focus_velocity = Vec3::ZERO;
+            self.current_distance = reach;
+            self.distance_velocity = 0.0;
+            self.active = true;
+        }
+
+        spring3(
+            &mut self.focus,
+            &mut self.focus_velocity,
+            goal,
+            self.follow_frequency,
+            dt,
+        );
+
+        let back = -forward_from_yaw_pitch(self.yaw, self.pitch);
+        let ray = Ray {
+            origin: self.focus,
+            direction: back,
+        };
+        let allowed = cast(&ray, self.distance + self.collision_radius)
+            .map_or(self.distance, |hit| {
+                (hit - self.collision_radius).clamp(MIN_DISTANCE, self.distance)
+            });
+        if allowed < self.current_distance {
+            // Pull in immediately so geometry never ends up between camera and target.
+            self.current_distance = allowed;
+            self.distance_velocity = 0.0;
+        } else {
+            spring(
+                &mut self.current_distance,
+                &mut self.distance_velocity,
+                allowed,
+                self.distance_frequency,
+                dt,
+            );
+        }
+
+        cam.yaw = self.yaw;
+        cam.pitch = self.pitch;
+        cam.focus(self.focus, self.current_distance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(frequency: f32, dt: f32, steps: usize) -> Vec<f32> {
+        let (mut value, mut velocity) = (0.0, 0.0);
+        (0..steps)
+            .map(|_| {
+                spring(&mut value, &mut velocity, 1.0, frequency, dt);
+                value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spring_settles_without_overshoot() {
+        let values = run(8.0, 1.0 / 60.0, 240);
+        assert!(values.windows(2).all(|w| w[1] >= w[0]));
+        assert!(values.iter().all(|&v| v <= 1.0));
+        assert!((values.last().unwrap() - 1.0).abs() < 1e-4);
+        // Critically damped from rest: x(t) = 1 - (1 + wt) e^-wt.
+        let t = 0.25f32;
+        let expected = 1.0 - (1.0 + 8.0 * t) * (-8.0 * t).exp();
+        assert!((values[14] - expected).abs() < 1e-4, "{}", values[14]);
+    }
+
+    #[test]
+    fn spring_is_frame_rate_independent() {
+        let coarse = run(4.0, 0.1, 5)[4];
+        let fine = run(4.0, 0.01, 50)[49];
+        assert!((coarse - fine).abs() < 1e-4, "{coarse} vs {fine}");
+        // A long frame settles instead of overshooting.
+        let mut value = 0.0;
+        let mut velocity = 0.0;
+        spring(&mut value, &mut velocity, 1.0, 8.0, 5.0);
+        assert!((value - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn collision_pulls_the_camera_in_front_of_the_hit() {
+        let mut cam = OrbitCamera::new(Vec3::new(0.0, 1.5, 5.0), std::f32::consts::FRAC_PI_2, 0.0);
+        let mut follow = FollowCamera::new(5.0);
+        follow.update(&mut cam, Vec3::ZERO, 0.0, |_, _| None);
+        assert!((cam.eye.distance(follow.focus) - 5.0).abs() < 1e-4);
+
+        follow.update(&mut cam, Vec3::ZERO, 1.0 / 60.0, |_, max| {
+            assert!((max - 5.2).abs() < 1e-5);
+            Some(2.0)
+        });
+        assert!((cam.eye.distance(follow.focus) - 1.8).abs() < 1e-4);
+
+        // Once clear, the distance eases back out rather than snapping.
+        follow.update(&mut cam, Vec3::ZERO, 1.0 / 60.0, |_, _| None);
+        let distance = cam.eye.distance(follow.focus);
+        assert!(distance > 1.8 && distance < 5.0);
+    }
+}
@@ -1,8 +1,67 @@
-use glam::{Mat4, Vec3};
+mod follow;
+mod path;
+mod view;
+
+pub use follow::FollowCamera;
+pub use path::{CameraPath, PathKey, PathPlayer, PathSample, SplineKind};
+pub use view::{CameraMatrices, Ray, ScreenRect};
+
+use glam::{Mat4, Vec2, Vec3};
+use minima_3d::{DepthMode, LodView};
 use wgpu::{Buffer, Queue};
-use winit::event::{DeviceEvent, ElementState, KeyEvent, WindowEvent};
+use winit::event::{
+    DeviceEvent, ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent,
+};
 use winit::keyboard::KeyCode;
 
+pub const FOV_Y: f32 = std::f32::consts::FRAC_PI_4;
+pub const NEAR: f32 = 0.1;
+pub const FAR: f32 = 100.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+    Perspective {
+        fov_y: f32,
+        near: f32,
+        far: Option<f32>,
+    },
+    Orthographic {
+        height: f32,
+        near: f32,
+        far: f32,
+    },
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Projection::Perspective {
+            fov_y: FOV_Y,
+            near: NEAR,
+            far: Some(FAR),
+        }
+    }
+}
+
+impl Projection {
+    pub fn matrix(&self, aspect: f32, depth: DepthMode) -> Mat4 {
+        match *self {
+            Projection::Perspective { fov_y, near, far } => {
+                depth.perspective(fov_y, aspect, near, far)
+            }
+            Projection::Orthographic { height, near, far } => {
+                depth.orthographic(height * 0.5 * aspect, height * 0.5, near, far)
+            }
+        }
+    }
+
+    pub fn lod_view(&self, eye: Vec3) -> LodView {
+        match *self {
+            Projection::Perspective { fov_y, .. } => LodView::new(eye, fov_y),
+            Projection::Orthographic { height, .. } => LodView::orthographic(eye, height),
+        }
+    }
+}
+
 pub fn forward_from_yaw_pitch(yaw: f32, pitch: f32) -> Vec3 {
     let cp = pitch.cos();
     let sp = pitch.sin();
@@ -11,19 +70,144 @@ pub fn forward_from_yaw_pitch(yaw: f32, pitch: f32) -> Vec3 {
     Vec3::new(cy * cp, sp, -sy * cp)
 }
 
+const DEFAULT_DISTANCE: f32 = 5.0;
+const MIN_DISTANCE: f32 = 0.05;
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CameraMode {
+    #[default]
+    Fly,
+    Orbit,
+    Follow,
+}
+
+// Both modes share eye/yaw/pitch; in orbit mode the eye sits `distance` behind `target`.
 pub struct OrbitCamera {
     pub eye: Vec3,
     pub yaw: f32,
     pub pitch: f32,
+    pub target: Vec3,
+    pub distance: f32,
+    pub mode: CameraMode,
+    pub projection: Projection,
 }
 
 impl OrbitCamera {
     pub fn new(eye: Vec3, yaw: f32, pitch: f32) -> Self {
-        Self { eye, yaw, pitch }
+        let distance = DEFAULT_DISTANCE;
+        Self {
+            eye,
+            yaw,
+            pitch,
+            target: eye + forward_from_yaw_pitch(yaw, pitch) * distance,
+            distance,
+            mode: CameraMode::Fly,
+            projection: Projection::default(),
+        }
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        forward_from_yaw_pitch(self.yaw, self.pitch)
+    }
+
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == CameraMode::Orbit && self.mode != CameraMode::Orbit {
+            self.target = self.eye + self.forward() * self.distance;
+        }
+        self.mode = mode;
+    }
+
+    pub fn focus(&mut self, target: Vec3, distance: f32) {
+        self.target = target;
+        self.distance = distance.max(MIN_DISTANCE);
+        self.sync_eye();
+    }
+
+    pub fn rotate(&mut self, d_yaw: f32, d_pitch: f32) {
+        self.yaw += d_yaw;
+        self.pitch = (self.pitch + d_pitch).clamp(-MAX_PITCH, MAX_PITCH);
+        if self.mode == CameraMode::Orbit {
+            self.sync_eye();
+        }
+    }
+
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let forward = self.forward();
+        let right = forward.cross(Vec3::Y).normalize_or_zero();
+        let up = right.cross(forward);
+        self.target += (up * dy - right * dx) * self.distance;
+        self.sync_eye();
+    }
+
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).max(MIN_DISTANCE);
+        self.zoom_projection(factor);
+        self.sync_eye();
+    }
+
+    // Moving an orthographic camera does not change what it sees, so zooming scales its height.
+    pub fn zoom_projection(&mut self, factor: f32) {
+        if let Projection::Orthographic { height, .. } = &mut self.projection {
+            *height = (*height * factor).max(MIN_DISTANCE);
+        }
+    }
+
+    fn sync_eye(&mut self) {
+        self.eye = self.target - self.forward() * self.distance;
+    }
+}
+
+// Rates are per second and 0 means instant; rotation_smoothing is a time constant in seconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ControllerSettings {
+    pub base_speed: f32,
+    pub acceleration: f32,
+    pub deceleration: f32,
+    pub rotation_smoothing: f32,
+    pub scroll_speed_factor: f32,
+    // Radians per second at full stick deflection.
+    pub stick_look_speed: f32,
+    pub stick_deadzone: f32,
+}
+
+impl Default for ControllerSettings {
+    fn default() -> Self {
+        Self {
+            base_speed: 3.0,
+            acceleration: 0.0,
+            deceleration: 0.0,
+            rotation_smoothing: 0.0,
+            scroll_speed_factor: 1.1,
+            stick_look_speed: 2.5,
+            stick_deadzone: 0.15,
+        }
     }
 }
 
+// Fraction of the remaining gap closed in `dt`. An infinite rate, from a smoothing time of 0, is
+// instant too; `exp` would turn it into NaN when `dt` is 0.
+fn approach(rate: f32, dt: f32) -> f32 {
+    if rate <= 0.0 || rate == f32::INFINITY {
+        1.0
+    } else {
+        1.0 - (-rate * dt).exp()
+    }
+}
+
+// Rescales the live range so output starts at 0 just outside the deadzone.
+fn apply_deadzone(stick: Vec2, deadzone: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+    let scaled = ((length - deadzone) / (1.0 - deadzone).max(f32::EPSILON)).min(1.0);
+    stick * (scaled / length)
+}
+
 pub struct CameraController {
+    pub settings: ControllerSettings,
+    pub follow: FollowCamera,
     move_forward: bool,
     move_back: bool,
     move_left: bool,
@@ -31,12 +215,22 @@ pub struct CameraController {
     move_up: bool,
     move_down: bool,
     boost_speed: bool,
-    base_speed: f32,
+    rotating: bool,
+    panning: bool,
+    velocity: Vec3,
+    pending_rotation: Vec2,
+    stick_move: Vec2,
+    stick_look: Vec2,
 }
 
 impl CameraController {
     pub fn new(base_speed: f32) -> Self {
         Self {
+            settings: ControllerSettings {
+                base_speed,
+                ..Default::default()
+            },
+            follow: FollowCamera::new(DEFAULT_DISTANCE),
             move_forward: false,
             move_back: false,
             move_left: false,
@@ -44,52 +238,154 @@ impl CameraController {
             move_up: false,
             move_down: false,
             boost_speed: false,
-            base_speed,
+            rotating: false,
+            panning: false,
+            velocity: Vec3::ZERO,
+            pending_rotation: Vec2::ZERO,
+            stick_move: Vec2::ZERO,
+            stick_look: Vec2::ZERO,
         }
     }
 
-    pub fn handle_window_event(&mut self, event: &WindowEvent, _cam: &mut OrbitCamera) {
+    // Raw gamepad sticks in -1..1 with +y pushed away from the player, as gilrs reports them.
+    // They hold until the next call, so feed the current state once per frame.
+    pub fn set_stick_input(&mut self, movement: Vec2, look: Vec2) {
+        let deadzone = self.settings.stick_deadzone;
+        self.stick_move = apply_deadzone(movement, deadzone);
+        self.stick_look = apply_deadzone(look, deadzone);
+    }
+
+    // Releases and focus changes must be delivered even while the camera is not capturing input,
+    // or a button let go over the UI stays held.
+    pub fn handle_window_event(&mut self, event: &WindowEvent, cam: &mut OrbitCamera) {
         match event {
-            WindowEvent::KeyboardInput {
-                event:
-                    KeyEvent {
-                        physical_key: winit::keyboard::PhysicalKey::Code(code),
-                        state,
-                        repeat,
-                        ..
-                    },
-                ..
-            } => {
-                if *repeat {
-                    return;
-                }
+            WindowEvent::Focused(false) => {
+                self.release_all();
+                return;
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
                 let pressed = *state == ElementState::Pressed;
-                match code {
-                    KeyCode::KeyW => self.move_forward = pressed,
-                    KeyCode::KeyS => self.move_back = pressed,
-                    KeyCode::KeyA => self.move_left = pressed,
-                    KeyCode::KeyD => self.move_right = pressed,
-                    KeyCode::KeyJ => self.move_up = pressed,
-                    KeyCode::KeyK => self.move_down = pressed,
-                    KeyCode::ShiftLeft => self.boost_speed = pressed,
+                match button {
+                    MouseButton::Left => self.rotating = pressed,
+                    MouseButton::Middle => self.panning = pressed,
                     _ => {}
                 }
+                return;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 / 40.0,
+                };
+                let factor = 0.9_f32.powf(lines);
+                match cam.mode {
+                    CameraMode::Orbit => cam.zoom(factor),
+                    CameraMode::Follow => {
+                        self.follow.zoom(factor);
+                        cam.zoom_projection(factor);
+                    }
+                    CameraMode::Fly
+                        if matches!(cam.projection, Projection::Orthographic { .. }) =>
+                    {
+                        cam.zoom_projection(factor);
+                    }
+                    CameraMode::Fly => {
+                        let speed = self.settings.base_speed
+                            * self.settings.scroll_speed_factor.powf(lines);
+                        self.settings.base_speed = speed.clamp(0.01, 1000.0);
+                    }
+                }
+                return;
             }
             _ => {}
         }
+        if let WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    physical_key: winit::keyboard::PhysicalKey::Code(code),
+                    state,
+                    repeat,
+                    ..
+                },
+            ..
+        } = event
+        {
+            if *repeat {
+                return;
+            }
+            let pressed = *state == ElementState::Pressed;
+            match code {
+                KeyCode::KeyW => self.move_forward = pressed,
+                KeyCode::KeyS => self.move_back = pressed,
+                KeyCode::KeyA => self.move_left = pressed,
+                KeyCode::KeyD => self.move_right = pressed,
+                KeyCode::KeyJ => self.move_up = pressed,
+                KeyCode::KeyK => self.move_down = pressed,
+                KeyCode::ShiftLeft => self.boost_speed = pressed,
+                KeyCode::Tab if pressed => cam.set_mode(match cam.mode {
+                    CameraMode::Fly => CameraMode::Orbit,
+                    CameraMode::Orbit => CameraMode::Follow,
+                    CameraMode::Follow => CameraMode::Fly,
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    // Drops every held key and button, and any rotation not yet applied.
+    pub fn release_all(&mut self) {
+        self.move_forward = false;
+        self.move_back = false;
+        self.move_left = false;
+        self.move_right = false;
+        self.move_up = false;
+        self.move_down = false;
+        self.boost_speed = false;
+        self.rotating = false;
+        self.panning = false;
+        self.pending_rotation = Vec2::ZERO;
+        self.stick_move = Vec2::ZERO;
+        self.stick_look = Vec2::ZERO;
     }
 
     pub fn handle_device_event(&mut self, event: &DeviceEvent, cam: &mut OrbitCamera) {
         if let DeviceEvent::MouseMotion { delta: (dx, dy) } = event {
+            let (dx, dy) = (*dx as f32, *dy as f32);
             let sensitivity = 0.0025;
-            cam.yaw -= (*dx as f32) * sensitivity;
-            cam.pitch -= (*dy as f32) * sensitivity;
-            let max_pitch = std::f32::consts::FRAC_PI_2 - 0.01;
-            cam.pitch = cam.pitch.clamp(-max_pitch, max_pitch);
+            let rotate = match cam.mode {
+                CameraMode::Fly | CameraMode::Follow => true,
+                CameraMode::Orbit if self.panning => {
+                    cam.pan(dx * 0.002, dy * 0.002);
+                    false
+                }
+                CameraMode::Orbit => self.rotating,
+            };
+            if rotate {
+                self.pending_rotation -= Vec2::new(dx, dy) * sensitivity;
+            }
         }
     }
 
+    // In follow mode the caller positions the camera with `follow.update`, which needs the
+    // target and scene geometry.
     pub fn update(&mut self, cam: &mut OrbitCamera, dt: f32) {
+        // Right turns the view right and up looks up, matching the mouse.
+        self.pending_rotation +=
+            Vec2::new(-self.stick_look.x, self.stick_look.y) * self.settings.stick_look_speed * dt;
+        let step = self.pending_rotation * approach(1.0 / self.settings.rotation_smoothing, dt);
+        self.pending_rotation -= step;
+        if cam.mode == CameraMode::Follow {
+            self.follow.orbit(step.x, step.y);
+        } else {
+            self.follow.deactivate();
+            cam.rotate(step.x, step.y);
+        }
+
+        if cam.mode != CameraMode::Fly {
+            self.velocity = Vec3::ZERO;
+            return;
+        }
+
         let mut movement = Vec3::ZERO;
 
         let forward = forward_from_yaw_pitch(cam.yaw, cam.pitch);
@@ -121,15 +417,27 @@ impl CameraController {
         if self.move_down {
             movement -= Vec3::Y;
         }
+        movement += flat_forward * self.stick_move.y + right * self.stick_move.x;
 
+        // Keys always ask for full speed; a partly deflected stick moves proportionally slower.
+        let mut target = Vec3::ZERO;
         if movement.length_squared() > 0.0 {
-            movement = movement.normalize();
-            let mut speed = self.base_speed;
+            let mut speed = self.settings.base_speed;
             if self.boost_speed {
                 speed *= 5.0;
             }
-            cam.eye += movement * speed * dt;
+            target = movement.clamp_length_max(1.0) * speed;
         }
+        let rate = if target.length_squared() >= self.velocity.length_squared() {
+            self.settings.acceleration
+        } else {
+            self.settings.deceleration
+        };
+        self.velocity = self.velocity.lerp(target, approach(rate, dt));
+        if self.velocity.length_squared() < 1e-8 {
+            self.velocity = Vec3::ZERO;
+        }
+        cam.eye += self.velocity * dt;
     }
 }
 
@@ -137,17 +445,257 @@ pub fn update_camera_buffer(
     queue: &Queue,
     camera_buf: &Buffer,
     camera: &OrbitCamera,
+    depth: DepthMode,
     width: u32,
     height: u32,
 ) {
-    let forward = forward_from_yaw_pitch(camera.yaw, camera.pitch);
-    let target = camera.eye + forward;
-    let up = Vec3::Y;
+    let vp = CameraMatrices::new(camera, depth, width, height)
+        .view_proj
+        .to_cols_array();
+    let eye = camera.eye.extend(1.0).to_array();
+    queue.write_buffer(camera_buf, 0, bytemuck::cast_slice(&vp));
+    queue.write_buffer(camera_buf, 64, bytemuck::cast_slice(&eye));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::DeviceId;
+
+    fn mouse(button: MouseButton, state: ElementState) -> WindowEvent {
+        WindowEvent::MouseInput {
+            device_id: DeviceId::dummy(),
+            state,
+            button,
+        }
+    }
+
+    fn scroll(lines: f32) -> WindowEvent {
+        WindowEvent::MouseWheel {
+            device_id: DeviceId::dummy(),
+            delta: MouseScrollDelta::LineDelta(0.0, lines),
+            phase: winit::event::TouchPhase::Moved,
+        }
+    }
+
+    fn ortho(height: f32) -> Projection {
+        Projection::Orthographic {
+            height,
+            near: NEAR,
+            far: FAR,
+        }
+    }
+
+    fn ortho_height(cam: &OrbitCamera) -> f32 {
+        match cam.projection {
+            Projection::Orthographic { height, .. } => height,
+            Projection::Perspective { .. } => panic!("expected an orthographic camera"),
+        }
+    }
+
+    #[test]
+    fn releases_and_focus_loss_clear_held_input() {
+        let mut cam = OrbitCamera::new(Vec3::ZERO, 0.0, 0.0);
+        cam.set_mode(CameraMode::Orbit);
+        let mut controller = CameraController::new(1.0);
+
+        controller.handle_window_event(&mouse(MouseButton::Left, ElementState::Pressed), &mut cam);
+        controller
+            .handle_window_event(&mouse(MouseButton::Middle, ElementState::Pressed), &mut cam);
+        assert!(controller.rotating && controller.panning);
+        controller.handle_window_event(&mouse(MouseButton::Left, ElementState::Released), &mut cam);
+        controller.handle_window_event(
+            &mouse(MouseButton::Middle, ElementState::Released),
+            &mut cam,
+        );
+        assert!(!controller.rotating && !controller.panning);
+
+        controller.rotating = true;
+        controller.move_forward = true;
+        controller.boost_speed = true;
+        controller.pending_rotation = Vec2::ONE;
+        controller.handle_window_event(&WindowEvent::Focused(false), &mut cam);
+        assert!(!controller.rotating && !controller.move_forward && !controller.boost_speed);
+        assert_eq!(controller.pending_rotation, Vec2::ZERO);
 
-    let view = Mat4::look_at_rh(camera.eye, target, up);
-    let aspect = (width.max(1) as f32) / (height.max(1) as f32);
-    let proj = Mat4::perspective_rh_gl(45.0_f32.to_radians(), aspect, 0.1, 100.0);
+        // With nothing held, mouse motion no longer turns an orbit camera.
+        let yaw = cam.yaw;
+        controller.handle_device_event(&DeviceEvent::MouseMotion { delta: (50.0, 0.0) }, &mut cam);
+        controller.update(&mut cam, 0.1);
+        assert_eq!(cam.yaw, yaw);
+    }
+
+    #[test]
+    fn scroll_scales_the_orthographic_height() {
+        let mut controller = CameraController::new(1.0);
+        for mode in [CameraMode::Fly, CameraMode::Orbit, CameraMode::Follow] {
+            let mut cam = OrbitCamera::new(Vec3::ZERO, 0.0, 0.0);
+            cam.set_mode(mode);
+            cam.projection = ortho(10.0);
+            controller.handle_window_event(&scroll(1.0), &mut cam);
+            assert!((ortho_height(&cam) - 9.0).abs() < 1e-5, "{mode:?}");
+            controller.handle_window_event(&scroll(-1.0), &mut cam);
+            assert!((ortho_height(&cam) - 10.0).abs() < 1e-5, "{mode:?}");
+        }
+        // Fly speed only changes for perspective cameras.
+        assert_eq!(controller.settings.base_speed, 1.0);
+        let mut cam = OrbitCamera::new(Vec3::ZERO, 0.0, 0.0);
+        controller.handle_window_event(&scroll(1.0), &mut cam);
+        assert!((controller.settings.base_speed - 1.1).abs() < 1e-5);
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn approach_is_instant_at_zero_and_infinite_rates() {
+        for dt in [0.0, 0.016, 1.0] {
+            assert_eq!(approach(0.0, dt), 1.0);
+            assert_eq!(approach(f32::INFINITY, dt), 1.0);
+        }
+        assert_eq!(approach(4.0, 0.0), 0.0);
+        assert!(close(approach(1.0, 1.0), 1.0 - (-1.0f32).exp()));
+        // Two half steps close the same gap as one full step, so smoothing is frame-rate independent.
+        let half = approach(3.0, 0.05);
+        assert!(close(1.0 - (1.0 - half) * (1.0 - half), approach(3.0, 0.1)));
+    }
+
+    #[test]
+    fn zero_rotation_smoothing_applies_the_whole_turn() {
+        let mut cam = OrbitCamera::new(Vec3::ZERO, 0.0, 0.0);
+        let mut controller = CameraController::new(1.0);
+        assert_eq!(controller.settings.rotation_smoothing, 0.0);
+        controller.handle_device_event(
+            &DeviceEvent::MouseMotion {
+                delta: (-40.0, 0.0),
+            },
+            &mut cam,
+        );
+        // A zero-length frame still must not produce NaN.
+        controller.update(&mut cam, 0.0);
+        assert!(close(cam.yaw, 0.1));
+        assert_eq!(controller.pending_rotation, Vec2::ZERO);
+    }
+
+    #[test]
+    fn rotation_smoothing_spreads_the_turn_over_frames() {
+        let mut cam = OrbitCamera::new(Vec3::ZERO, 0.0, 0.0);
+        let mut controller = CameraController::new(1.0);
+        controller.settings.rotation_smoothing = 0.1;
+        controller.handle_device_event(
+            &DeviceEvent::MouseMotion {
+                delta: (-40.0, 0.0),
+            },
+            &mut cam,
+        );
+        controller.update(&mut cam, 0.1);
+        let first = 0.1 * (1.0 - (-1.0f32).exp());
+        assert!(close(cam.yaw, first));
+        for _ in 0..20 {
+            controller.update(&mut cam, 0.1);
+        }
+        assert!(close(cam.yaw, 0.1));
+    }
+
+    #[test]
+    fn fly_velocity_follows_acceleration_and_deceleration() {
+        let mut cam = OrbitCamera::new(Vec3::ZERO, 0.0, 0.0);
+        let mut controller = CameraController::new(3.0);
+        controller.move_forward = true;
 
-    let vp = (proj * view).to_cols_array();
-    queue.write_buffer(camera_buf, 0, bytemuck::cast_slice(&[vp]));
+        // Rates of 0 reach full speed at once.
+        controller.update(&mut cam, 0.5);
+        assert!(close(controller.velocity.length(), 3.0));
+        assert!(cam.eye.abs_diff_eq(cam.forward() * 1.5, 1e-5));
+
+        controller.boost_speed = true;
+        controller.update(&mut cam, 0.1);
+        assert!(close(controller.velocity.length(), 15.0));
+        controller.boost_speed = false;
+
+        controller.settings.deceleration = 2.0;
+        controller.move_forward = false;
+        controller.update(&mut cam, 0.5);
+        assert!(close(controller.velocity.length(), 15.0 * (-1.0f32).exp()));
+
+        controller.velocity = Vec3::ZERO;
+        controller.settings.acceleration = 2.0;
+        controller.move_forward = true;
+        controller.update(&mut cam, 0.5);
+        assert!(close(
+            controller.velocity.length(),
+            3.0 * (1.0 - (-1.0f32).exp())
+        ));
+
+        // Other modes drop any velocity left over from flying.
+        cam.set_mode(CameraMode::Orbit);
+        controller.update(&mut cam, 0.1);
+        assert_eq!(controller.velocity, Vec3::ZERO);
+    }
+
+    #[test]
+    fn sticks_move_and_turn_with_a_deadzone() {
+        assert_eq!(apply_deadzone(Vec2::new(0.1, 0.05), 0.15), Vec2::ZERO);
+        assert!(close(apply_deadzone(Vec2::X, 0.15).x, 1.0));
+        assert!(close(apply_deadzone(Vec2::new(0.0, -0.575), 0.15).y, -0.5));
+        assert!(close(apply_deadzone(Vec2::splat(1.0), 0.15).length(), 1.0));
+
+        let mut cam = OrbitCamera::new(Vec3::ZERO, 0.0, 0.0);
+        let mut controller = CameraController::new(4.0);
+        controller.settings.stick_deadzone = 0.0;
+        controller.set_stick_input(Vec2::new(0.0, 0.5), Vec2::ZERO);
+        controller.update(&mut cam, 1.0);
+        assert!(cam.eye.abs_diff_eq(cam.forward() * 2.0, 1e-5));
+
+        // Look right at full deflection for half a second turns by half the look speed.
+        controller.set_stick_input(Vec2::ZERO, Vec2::new(1.0, 0.0));
+        controller.update(&mut cam, 0.5);
+        assert!(close(cam.yaw, -controller.settings.stick_look_speed * 0.5));
+        controller.set_stick_input(Vec2::ZERO, Vec2::new(0.0, 1.0));
+        controller.update(&mut cam, 0.1);
+        assert!(cam.pitch > 0.0);
+
+        // The stick state holds until replaced or released.
+        controller.handle_window_event(&WindowEvent::Focused(false), &mut cam);
+        let (yaw, pitch) = (cam.yaw, cam.pitch);
+        controller.update(&mut cam, 0.5);
+        assert_eq!((cam.yaw, cam.pitch), (yaw, pitch));
+    }
+
+    #[test]
+    fn editor_and_scene_projections_agree() {
+        use minima_3d::CameraProjection;
+
+        let aspect = 1.5;
+        for depth in [DepthMode::Standard, DepthMode::Reverse] {
+            for far in [Some(50.0), None] {
+                let editor = Projection::Perspective {
+                    fov_y: 0.8,
+                    near: 0.1,
+                    far,
+                };
+                let scene = CameraProjection::Perspective {
+                    yfov: 0.8,
+                    aspect_ratio: None,
+                    znear: 0.1,
+                    zfar: far,
+                };
+                assert_eq!(editor.matrix(aspect, depth), scene.matrix(aspect, depth));
+            }
+            // glTF's ymag is a half height, the editor's height is the full one.
+            let editor = Projection::Orthographic {
+                height: 4.0,
+                near: 0.1,
+                far: 50.0,
+            };
+            let scene = CameraProjection::Orthographic {
+                xmag: 3.0,
+                ymag: 2.0,
+                znear: 0.1,
+                zfar: 50.0,
+            };
+            assert_eq!(editor.matrix(aspect, depth), scene.matrix(aspect, depth));
+        }
+    }
 }
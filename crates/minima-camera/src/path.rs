@@ -0,0 +1,426 @@
+use anyhow::{Context, Result};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{PI, TAU};
+use std::path::Path;
+
+use crate::{MAX_PITCH, OrbitCamera, Projection};
+
+const MIN_FOV: f32 = 0.01;
+const MAX_FOV: f32 = PI - 0.01;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplineKind {
+    #[default]
+    CatmullRom,
+    Bezier,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PathKey {
+    pub time: f32,
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov_y: f32,
+    // Bézier control points relative to `position`; Catmull-Rom ignores them.
+    #[serde(default)]
+    pub in_handle: Vec3,
+    #[serde(default)]
+    pub out_handle: Vec3,
+}
+
+impl PathKey {
+    pub fn from_camera(camera: &OrbitCamera, time: f32) -> Self {
+        let fov_y = match camera.projection {
+            Projection::Perspective { fov_y, .. } => fov_y,
+            Projection::Orthographic { .. } => crate::FOV_Y,
+        };
+        Self {
+            time,
+            position: camera.eye,
+            yaw: camera.yaw,
+            pitch: camera.pitch,
+            fov_y,
+            in_handle: Vec3::ZERO,
+            out_handle: Vec3::ZERO,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PathSample {
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov_y: f32,
+}
+
+impl PathSample {
+    pub fn apply(&self, camera: &mut OrbitCamera) {
+        camera.eye = self.position;
+        camera.yaw = self.yaw;
+        camera.pitch = self.pitch;
+        camera.target = camera.eye + camera.forward() * camera.distance;
+        if let Projection::Perspective { fov_y, .. } = &mut camera.projection {
+            *fov_y = self.fov_y;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CameraPath {
+    #[serde(default)]
+    pub kind: SplineKind,
+    #[serde(default)]
+    pub looping: bool,
+    #[serde(default)]
+    pub keys: Vec<PathKey>,
+}
+
+fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, u: f32) -> f32 {
+    let (u2, u3) = (u * u, u * u * u);
+    0.5 * (2.0 * p1
+        + (p2 - p0) * u
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * u2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * u3)
+}
+
+fn bezier(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, u: f32) -> Vec3 {
+    let v = 1.0 - u;
+    p0 * (v * v * v) + p1 * (3.0 * v * v * u) + p2 * (3.0 * v * u * u) + p3 * (u * u * u)
+}
+
+// Shifts `angle` by whole turns so it lies within half a turn of `reference`.
+fn unwrap_angle(angle: f32, reference: f32) -> f32 {
+    angle - ((angle - reference + PI) / TAU).floor() * TAU
+}
+
+impl CameraPath {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading camera path {}", path.display()))?;
+        let mut camera_path: CameraPath = toml::from_str(&text)
+            .with_context(|| format!("parsing camera path {}", path.display()))?;
+        camera_path.sort_keys();
+        Ok(camera_path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("writing camera path {}", path.display()))
+    }
+
+    pub fn sort_keys(&mut self) {
+        self.keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    pub fn start_time(&self) -> f32 {
+        self.keys.first().map_or(0.0, |k| k.time)
+    }
+
+    pub fn end_time(&self) -> f32 {
+        self.keys.last().map_or(0.0, |k| k.time)
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.end_time() - self.start_time()
+    }
+
+    // One pass of the path. A looping path adds a closing segment from the last key back to the
+    // first, as long as the average spacing of the others.
+    pub fn period(&self) -> f32 {
+        let n = self.keys.len();
+        if self.looping && n > 1 {
+            self.duration() * n as f32 / (n - 1) as f32
+        } else {
+            self.duration()
+        }
+    }
+
+    pub fn auto_handles(&mut self) {
+        for i in 0..self.keys.len() {
+            self.auto_handle(i);
+        }
+    }
+
+    // Sets one key's Bézier handles to the tangent Catmull-Rom would use there, as a starting
+    // point for editing; the other keys keep theirs.
+    pub fn auto_handle(&mut self, i: usize) {
+        let n = self.keys.len();
+        if i >= n {
+            return;
+        }
+        let (prev, next) = if self.looping {
+            ((i + n - 1) % n, (i + 1) % n)
+        } else {
+            (i.saturating_sub(1), (i + 1).min(n - 1))
+        };
+        let tangent = (self.keys[next].position - self.keys[prev].position) / 6.0;
+        self.keys[i].out_handle = tangent;
+        self.keys[i].in_handle = -tangent;
+    }
+
+    pub fn sample(&self, time: f32) -> Option<PathSample> {
+        let keys = &self.keys;
+        let n = keys.len();
+        let last = n.checked_sub(1)?;
+        let start = self.start_time();
+        let period = self.period();
+        let looping = self.looping && period > 0.0;
+        let time = if looping {
+            start + (time - start).rem_euclid(period)
+        } else {
+            time.clamp(start, self.end_time())
+        };
+        // Segment `i` runs from key `i` to the next one, wrapping to the first when looping.
+        let segments = if looping { n } else { last.max(1) };
+        let i = (0..segments)
+            .rev()
+            .find(|&i| keys[i.min(last)].time <= time)
+            .unwrap_or(0)
+            .min(last);
+        let index = |k: usize| if looping { k % n } else { k.min(last) };
+        let j = index(i + 1);
+        let end = if looping && i == last {
+            start + period
+        } else {
+            keys[j].time
+        };
+        let span = end - keys[i].time;
+        let u = if span > 0.0 {
+            ((time - keys[i].time) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let (k1, k2) = (&keys[i], &keys[j]);
+        let k0 = &keys[if looping {
+            (i + n - 1) % n
+        } else {
+            i.saturating_sub(1)
+        }];
+        let k3 = &keys[index(i + 2)];
+
+        let position = match self.kind {
+            SplineKind::CatmullRom => {
+                // Reflect the end points so an open curve does not stall at its first and last key.
+                let p0 = if i == 0 && !looping {
+                    2.0 * k1.position - k2.position
+                } else {
+                    k0.position
+                };
+                let p3 = if j == last && !looping {
+                    2.0 * k2.position - k1.position
+                } else {
+                    k3.position
+                };
+                Vec3::from_array(std::array::from_fn(|c| {
+                    catmull_rom(p0[c], k1.position[c], k2.position[c], p3[c], u)
+                }))
+            }
+            SplineKind::Bezier => bezier(
+                k1.position,
+                k1.position + k1.out_handle,
+                k2.position + k2.in_handle,
+                k2.position,
+                u,
+            ),
+        };
+
+        // The spline overshoots between keys, so the result is kept to what the camera accepts.
+        let yaw1 = k1.yaw;
+        let yaw0 = unwrap_angle(k0.yaw, yaw1);
+        let yaw2 = unwrap_angle(k2.yaw, yaw1);
+        let yaw3 = unwrap_angle(k3.yaw, yaw2);
+        let pitch = catmull_rom(k0.pitch, k1.pitch, k2.pitch, k3.pitch, u);
+        let fov_y = catmull_rom(k0.fov_y, k1.fov_y, k2.fov_y, k3.fov_y, u);
+        Some(PathSample {
+            position,
+            yaw: catmull_rom(yaw0, yaw1, yaw2, yaw3, u),
+            pitch: pitch.clamp(-MAX_PITCH, MAX_PITCH),
+            fov_y: fov_y.clamp(MIN_FOV, MAX_FOV),
+        })
+    }
+}
+
+pub struct PathPlayer {
+    pub path: CameraPath,
+    pub time: f32,
+    pub speed: f32,
+}
+
+impl PathPlayer {
+    pub fn new(path: CameraPath) -> Self {
+        Self {
+            time: path.start_time(),
+            path,
+            speed: 1.0,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        !self.path.looping && self.time >= self.path.end_time()
+    }
+
+    pub fn advance(&mut self, camera: &mut OrbitCamera, dt: f32) {
+        self.time += dt * self.speed;
+        if let Some(sample) = self.path.sample(self.time) {
+            sample.apply(camera);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time: f32, position: Vec3) -> PathKey {
+        PathKey {
+            time,
+            position,
+            yaw: 0.0,
+            pitch: 0.0,
+            fov_y: 1.0,
+            in_handle: Vec3::ZERO,
+            out_handle: Vec3::ZERO,
+        }
+    }
+
+    // A unit square, one second per side.
+    fn square(kind: SplineKind, looping: bool) -> CameraPath {
+        let corners = [Vec3::ZERO, Vec3::X, Vec3::new(1.0, 0.0, 1.0), Vec3::Z];
+        let mut path = CameraPath {
+            kind,
+            looping,
+            keys: corners
+                .iter()
+                .enumerate()
+                .map(|(i, &p)| key(i as f32, p))
+                .collect(),
+        };
+        path.auto_handles();
+        path
+    }
+
+    fn position(path: &CameraPath, time: f32) -> Vec3 {
+        path.sample(time).unwrap().position
+    }
+
+    #[test]
+    fn open_paths_pass_through_keys_and_clamp() {
+        for kind in [SplineKind::CatmullRom, SplineKind::Bezier] {
+            let path = square(kind, false);
+            assert_eq!(path.period(), 3.0);
+            for k in &path.keys {
+                assert!(position(&path, k.time).abs_diff_eq(k.position, 1e-5));
+            }
+            assert_eq!(position(&path, -5.0), Vec3::ZERO);
+            assert!(position(&path, 10.0).abs_diff_eq(Vec3::Z, 1e-5));
+        }
+        assert_eq!(CameraPath::default().sample(0.0), None);
+        let single = CameraPath {
+            keys: vec![key(2.0, Vec3::Y)],
+            looping: true,
+            ..Default::default()
+        };
+        assert_eq!(position(&single, 7.0), Vec3::Y);
+    }
+
+    #[test]
+    fn looping_paths_close_back_to_the_first_key() {
+        for kind in [SplineKind::CatmullRom, SplineKind::Bezier] {
+            let path = square(kind, true);
+            assert_eq!(path.period(), 4.0);
+            // The closing segment runs from the last corner back to the first.
+            let closing = position(&path, 3.5);
+            assert!(
+                (closing.x).abs() < 0.2 && (closing.z - 0.5).abs() < 0.05,
+                "{closing}"
+            );
+            // Continuous across the wrap, and periodic.
+            let before = position(&path, 4.0 - 1e-3);
+            assert!(before.distance(Vec3::ZERO) < 0.01, "{kind:?} {before}");
+            assert!(position(&path, 5.5).abs_diff_eq(position(&path, 1.5), 1e-4));
+            assert!(position(&path, -0.5).abs_diff_eq(closing, 1e-4));
+        }
+        // Catmull-Rom wraps its neighbours, so the first key's tangent points along the loop.
+        let path = square(SplineKind::CatmullRom, true);
+        let d = position(&path, 0.01) - position(&path, 3.99);
+        assert!(d.x > 0.0 && d.z < 0.0, "{d}");
+
+        let mut player = PathPlayer::new(path);
+        let mut camera = OrbitCamera::new(Vec3::ZERO, 0.0, 0.0);
+        player.advance(&mut camera, 100.0);
+        assert!(!player.finished());
+    }
+
+    #[test]
+    fn overshoot_keeps_pitch_and_fov_in_range() {
+        let mut path = CameraPath::default();
+        for (i, (pitch, fov)) in [(-1.5, 1.0), (1.5, 0.02), (1.5, 0.02), (-1.5, 1.0)]
+            .into_iter()
+            .enumerate()
+        {
+            path.keys.push(PathKey {
+                pitch,
+                fov_y: fov,
+                ..key(i as f32, Vec3::ZERO)
+            });
+        }
+        let sample = path.sample(1.5).unwrap();
+        assert_eq!(sample.pitch, MAX_PITCH);
+        assert_eq!(sample.fov_y, MIN_FOV);
+
+        let mut camera = OrbitCamera::new(Vec3::ZERO, 0.0, 0.0);
+        sample.apply(&mut camera);
+        assert!(camera.forward().is_finite() && camera.forward().y < 1.0);
+    }
+
+    #[test]
+    fn yaw_takes_the_short_way_round() {
+        let mut path = CameraPath::default();
+        for (i, yaw) in [3.0, -3.0].into_iter().enumerate() {
+            path.keys.push(PathKey {
+                yaw,
+                ..key(i as f32, Vec3::ZERO)
+            });
+        }
+        let yaw = path.sample(0.5).unwrap().yaw;
+        assert!((yaw.rem_euclid(TAU) - PI).abs() < 0.05, "{yaw}");
+    }
+
+    #[test]
+    fn auto_handle_leaves_other_keys_alone() {
+        let mut path = square(SplineKind::Bezier, false);
+        path.keys[0].out_handle = Vec3::splat(9.0);
+        path.keys.push(key(4.0, Vec3::new(-1.0, 0.0, 1.0)));
+        path.auto_handle(4);
+        assert_eq!(path.keys[0].out_handle, Vec3::splat(9.0));
+        assert!(
+            path.keys[4]
+                .in_handle
+                .abs_diff_eq(Vec3::new(1.0, 0.0, 0.0) / 6.0, 1e-6)
+        );
+    }
+
+    #[test]
+    fn paths_round_trip_through_toml() {
+        let dir = std::env::temp_dir().join(format!("minima-path-{}", std::process::id()));
+        let file = dir.join("intro.toml");
+        let mut path = square(SplineKind::Bezier, true);
+        path.save(&file).unwrap();
+        assert_eq!(CameraPath::load(&file).unwrap(), path);
+
+        // Keys come back sorted whatever order the file lists them in.
+        path.keys.reverse();
+        path.save(&file).unwrap();
+        let loaded = CameraPath::load(&file).unwrap();
+        assert!(loaded.keys.windows(2).all(|w| w[0].time <= w[1].time));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+use glam::{Mat4, Vec2, Vec3, Vec4Swizzles};
+use minima_3d::DepthMode;
+
+use crate::OrbitCamera;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+}
+
+// Where the rendered image sits on screen, e.g. the letterboxed rect in the editor viewport.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScreenRect {
+    pub min: Vec2,
+    pub size: Vec2,
+}
+
+impl ScreenRect {
+    pub fn new(min: Vec2, size: Vec2) -> Self {
+        Self { min, size }
+    }
+
+    pub fn full(width: u32, height: u32) -> Self {
+        Self::new(Vec2::ZERO, Vec2::new(width as f32, height as f32))
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        let local = point - self.min;
+        local.cmpge(Vec2::ZERO).all() && local.cmple(self.size).all()
+    }
+
+    fn screen_to_ndc(self, point: Vec2) -> Vec2 {
+        let uv = (point - self.min) / self.size.max(Vec2::ONE);
+        Vec2::new(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0)
+    }
+
+    fn ndc_to_screen(self, ndc: Vec2) -> Vec2 {
+        self.min + Vec2::new(ndc.x + 1.0, 1.0 - ndc.y) * 0.5 * self.size
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct CameraMatrices {
+    pub view: Mat4,
+    pub proj: Mat4,
+    pub view_proj: Mat4,
+    pub inv_view: Mat4,
+    pub inv_proj: Mat4,
+    pub inv_view_proj: Mat4,
+    pub depth: DepthMode,
+}
+
+impl CameraMatrices {
+    // `depth` must be the renderer's mode, or depth tests and picking disagree with the image.
+    pub fn new(camera: &OrbitCamera, depth: DepthMode, width: u32, height: u32) -> Self {
+        let view = Mat4::look_at_rh(camera.eye, camera.eye + camera.forward(), Vec3::Y);
+        let aspect = (width.max(1) as f32) / (height.max(1) as f32);
+        let proj = camera.projection.matrix(aspect, depth);
+        let view_proj = proj * view;
+        Self {
+            view,
+            proj,
+            view_proj,
+            inv_view: view.inverse(),
+            inv_proj: proj.inverse(),
+            inv_view_proj: view_proj.inverse(),
+            depth,
+        }
+    }
+
+    pub fn pixel_to_ray(&self, rect: ScreenRect, pixel: Vec2) -> Option<Ray> {
+        if !rect.contains(pixel) {
+            return None;
+        }
+        let ndc = rect.screen_to_ndc(pixel);
+        // Depth 0.5 stays finite for infinite far planes in both depth modes.
+        let near_depth = 1.0 - self.depth.clear_value();
+        let near = self.inv_view_proj.project_point3(ndc.extend(near_depth));
+        let mid = self.inv_view_proj.project_point3(ndc.extend(0.5));
+        Some(Ray {
+            origin: near,
+            direction: (mid - near).try_normalize()?,
+        })
+    }
+
+    pub fn world_to_screen(&self, rect: ScreenRect, point: Vec3) -> Option<Vec2> {
+        let clip = self.view_proj * point.extend(1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        Some(rect.ndc_to_screen(clip.xy() / clip.w))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FAR, FOV_Y, NEAR, Projection};
+
+    const PROJECTIONS: [Projection; 3] = [
+        Projection::Perspective {
+            fov_y: FOV_Y,
+            near: NEAR,
+            far: Some(FAR),
+        },
+        Projection::Perspective {
+            fov_y: FOV_Y,
+            near: NEAR,
+            far: None,
+        },
+        Projection::Orthographic {
+            height: 8.0,
+            near: NEAR,
+            far: FAR,
+        },
+    ];
+
+    fn cameras() -> impl Iterator<Item = (CameraMatrices, ScreenRect)> {
+        // A letterboxed rect offset from the window corner, as in the editor viewport.
+        let rect = ScreenRect::new(Vec2::new(40.0, 25.0), Vec2::new(320.0, 180.0));
+        PROJECTIONS.into_iter().flat_map(move |projection| {
+            [DepthMode::Standard, DepthMode::Reverse].map(|depth| {
+                let mut camera = OrbitCamera::new(Vec3::new(1.0, 2.0, 3.0), 0.7, -0.3);
+                camera.projection = projection;
+                (CameraMatrices::new(&camera, depth, 320, 180), rect)
+            })
+        })
+    }
+
+    #[test]
+    fn world_points_project_onto_their_pick_ray() {
+        for (matrices, rect) in cameras() {
+            let eye = matrices.inv_view.transform_point3(Vec3::ZERO);
+            for local in [
+                Vec3::new(0.0, 0.0, -1.0),
+                Vec3::new(1.5, -0.5, -4.0),
+                Vec3::new(-2.0, 1.0, -25.0),
+                Vec3::new(0.3, 0.2, -90.0),
+            ] {
+                let point = matrices.inv_view.transform_point3(local);
+                let pixel = matrices.world_to_screen(rect, point).unwrap();
+                let ray = matrices.pixel_to_ray(rect, pixel).unwrap();
+                let along = (point - ray.origin).dot(ray.direction);
+                assert!(along > 0.0, "{:?} {local}", matrices.depth);
+                let miss = ray.at(along).distance(point);
+                assert!(
+                    miss < 1e-3 * along.max(1.0),
+                    "{:?} {local}: {miss}",
+                    matrices.depth
+                );
+                // Rays start on the near plane, in front of the eye.
+                assert!((ray.origin - eye).dot(ray.direction) >= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn pick_rays_project_back_to_their_pixel() {
+        for (matrices, rect) in cameras() {
+            for pixel in [
+                Vec2::new(200.0, 115.0),
+                Vec2::new(41.0, 26.0),
+                Vec2::new(359.0, 204.0),
+                Vec2::new(300.0, 60.0),
+            ] {
+                let ray = matrices.pixel_to_ray(rect, pixel).unwrap();
+                for t in [0.5, 10.0, 80.0] {
+                    let back = matrices.world_to_screen(rect, ray.at(t)).unwrap();
+                    assert!(
+                        back.distance(pixel) < 0.05,
+                        "{:?} {pixel} at {t}: {back}",
+                        matrices.depth
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pixels_outside_the_rect_and_points_behind_miss() {
+        for (matrices, rect) in cameras() {
+            assert!(matrices.pixel_to_ray(rect, Vec2::new(10.0, 10.0)).is_none());
+            assert!(
+                matrices
+                    .pixel_to_ray(rect, Vec2::new(361.0, 100.0))
+                    .is_none()
+            );
+        }
+        let camera = OrbitCamera::new(Vec3::ZERO, 0.0, 0.0);
+        let matrices = CameraMatrices::new(&camera, DepthMode::Reverse, 16, 9);
+        let behind = -camera.forward() * 5.0;
+        assert!(
+            matrices
+                .world_to_screen(ScreenRect::full(16, 9), behind)
+                .is_none()
+        );
+    }
+}
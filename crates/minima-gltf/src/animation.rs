@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use gltf::animation::util::ReadOutputs;
+use minima_3d::{AnimationClip, Channel, ChannelValues, Interpolation};
+use std::sync::Arc;
+
+pub fn clips_from_gltf(
+    doc: &gltf::Document,
+    buffers: &[gltf::buffer::Data],
+) -> Result<Vec<Arc<AnimationClip>>> {
+    doc.animations()
+        .map(|anim| {
+            let label = anim
+                .name()
+                .map_or_else(|| anim.index().to_string(), str::to_owned);
+            let channels = anim
+                .channels()
+                .map(|channel| {
+                    read_channel(&channel, buffers)
+                        .with_context(|| format!("animation {label} channel {}", channel.index()))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let duration = channels
+                .iter()
+                .filter_map(|c| c.times.last().copied())
+                .fold(0.0, f32::max);
+            Ok(Arc::new(AnimationClip {
+                name: anim.name().map(str::to_owned),
+                duration,
+                channels,
+            }))
+        })
+        .collect()
+}
+
+fn read_channel(
+    channel: &gltf::animation::Channel,
+    buffers: &[gltf::buffer::Data],
+) -> Result<Channel> {
+    let reader = channel.reader(|buf| buffers.get(buf.index()).map(|b| &b.0[..]));
+    let times: Vec<f32> = reader
+        .read_inputs()
+        .context("sampler input is unreadable")?
+        .collect();
+    let interpolation = match channel.sampler().interpolation() {
+        gltf::animation::Interpolation::Step => Interpolation::Step,
+        gltf::animation::Interpolation::Linear => Interpolation::Linear,
+        gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+    };
+    let per_key = if interpolation == Interpolation::CubicSpline {
+        3
+    } else {
+        1
+    };
+    let values = match reader
+        .read_outputs()
+        .context("sampler output is unreadable")?
+    {
+        ReadOutputs::Translations(it) => ChannelValues::Translation(it.map(Into::into).collect()),
+        ReadOutputs::Rotations(it) => {
+            ChannelValues::Rotation(it.into_f32().map(glam::Quat::from_array).collect())
+        }
+        ReadOutputs::Scales(it) => ChannelValues::Scale(it.map(Into::into).collect()),
+        ReadOutputs::MorphTargetWeights(it) => {
+            let flat: Vec<f32> = it.into_f32().collect();
+            let targets = flat.len() / (times.len() * per_key).max(1);
+            ChannelValues::Weights(split_weights(&flat, targets))
+        }
+    };
+    let value_count = match &values {
+        ChannelValues::Translation(v) | ChannelValues::Scale(v) => v.len(),
+        ChannelValues::Rotation(v) => v.len(),
+        ChannelValues::Weights(tracks) => tracks.first().map_or(times.len() * per_key, Vec::len),
+    };
+    if times.is_empty() || value_count != times.len() * per_key {
+        anyhow::bail!("{} keyframes but {value_count} output values", times.len());
+    }
+    Ok(Channel {
+        node: channel.target().node().index(),
+        interpolation,
+        times,
+        values,
+    })
+}
+
+fn split_weights(flat: &[f32], targets: usize) -> Vec<Vec<f32>> {
+    if targets == 0 {
+        return Vec::new();
+    }
+    (0..targets)
+        .map(|target| {
+            flat.chunks_exact(targets)
+                .map(|group| group[target])
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,725 @@
+// LDR ASTC block decoder. HDR blocks and illegal encodings decode to the
+// spec's error colour.
+
+use std::sync::OnceLock;
+
+const ERROR: [u8; 4] = [255, 0, 255, 255];
+
+// (bits, trits, quints) for each integer sequence range, in ASTC range order.
+pub(crate) const RANGES: [(u32, u32, u32); 21] = [
+    (1, 0, 0),
+    (0, 1, 0),
+    (2, 0, 0),
+    (0, 0, 1),
+    (1, 1, 0),
+    (3, 0, 0),
+    (1, 0, 1),
+    (2, 1, 0),
+    (4, 0, 0),
+    (2, 0, 1),
+    (3, 1, 0),
+    (5, 0, 0),
+    (3, 0, 1),
+    (4, 1, 0),
+    (6, 0, 0),
+    (4, 0, 1),
+    (5, 1, 0),
+    (7, 0, 0),
+    (5, 0, 1),
+    (6, 1, 0),
+    (8, 0, 0),
+];
+
+// Bit layout of the B term and the C multiplier for trit and quint ranges,
+// indexed by range; letters name bits of the value's binary part.
+const ENDPOINT_UNQUANT: [(&[u8; 9], i32); 21] = [
+    (b"000000000", 0),
+    (b"000000000", 0),
+    (b"000000000", 0),
+    (b"000000000", 0),
+    (b"000000000", 204),
+    (b"000000000", 0),
+    (b"000000000", 113),
+    (b"b000b0bb0", 93),
+    (b"000000000", 0),
+    (b"b0000bb00", 54),
+    (b"cb000cbcb", 44),
+    (b"000000000", 0),
+    (b"cb0000cbc", 26),
+    (b"dcb000dcb", 22),
+    (b"000000000", 0),
+    (b"dcb0000dc", 13),
+    (b"edcb000ed", 11),
+    (b"000000000", 0),
+    (b"edcb0000e", 6),
+    (b"fedcb000f", 5),
+    (b"000000000", 0),
+];
+
+const WEIGHT_UNQUANT: [(&[u8; 7], i32); 12] = [
+    (b"0000000", 0),
+    (b"0000000", 0),
+    (b"0000000", 0),
+    (b"0000000", 0),
+    (b"0000000", 50),
+    (b"0000000", 0),
+    (b"0000000", 28),
+    (b"b000b0b", 23),
+    (b"0000000", 0),
+    (b"b0000b0", 13),
+    (b"cb000cb", 11),
+    (b"0000000", 0),
+];
+
+fn field(data: u128, start: u32, count: u32) -> u32 {
+    ((data >> start) & ((1 << count) - 1)) as u32
+}
+
+// Reads stop at `end`; bits past it read as zero, as the spec requires for
+// the tail of a truncated integer sequence.
+pub(crate) struct Bits {
+    pub data: u128,
+    pub pos: u32,
+    pub end: u32,
+}
+
+impl Bits {
+    pub fn read(&mut self, count: u32) -> u32 {
+        let available = self.end.saturating_sub(self.pos).min(count);
+        let value = if available == 0 {
+            0
+        } else {
+            field(self.data, self.pos, available)
+        };
+        self.pos += count;
+        value
+    }
+}
+
+pub(crate) fn ise_bit_count(count: u32, range: usize) -> u32 {
+    let (bits, trits, quints) = RANGES[range];
+    count * bits + (count * 8 * trits).div_ceil(5) + (count * 7 * quints).div_ceil(3)
+}
+
+fn bit(value: u32, index: u32) -> u32 {
+    (value >> index) & 1
+}
+
+fn decode_trits(t: u32) -> [u32; 5] {
+    let (c, t4, t3) = if t >> 2 & 7 == 7 {
+        ((t >> 5 & 7) << 2 | (t & 3), 2, 2)
+    } else if t >> 5 & 3 == 3 {
+        (t & 31, 2, bit(t, 7))
+    } else {
+        (t & 31, bit(t, 7), t >> 5 & 3)
+    };
+    let (t2, t1, t0) = if c & 3 == 3 {
+        (2, bit(c, 4), bit(c, 3) << 1 | (bit(c, 2) & !bit(c, 3) & 1))
+    } else if c >> 2 & 3 == 3 {
+        (2, 2, c & 3)
+    } else {
+        (
+            bit(c, 4),
+            c >> 2 & 3,
+            bit(c, 1) << 1 | (bit(c, 0) & !bit(c, 1) & 1),
+        )
+    };
+    [t0, t1, t2, t3, t4]
+}
+
+fn decode_quints(q: u32) -> [u32; 3] {
+    if q >> 1 & 3 == 3 && q >> 5 & 3 == 0 {
+        let q2 = bit(q, 0) << 2 | (bit(q, 4) & !bit(q, 0) & 1) << 1 | (bit(q, 3) & !bit(q, 0) & 1);
+        return [4, 4, q2];
+    }
+    let (q2, c) = if q >> 1 & 3 == 3 {
+        (4, (q >> 3 & 3) << 3 | (!(q >> 5) & 3) << 1 | bit(q, 0))
+    } else {
+        (q >> 5 & 3, q & 31)
+    };
+    if c & 7 == 5 {
+        [c >> 3 & 3, 4, q2]
+    } else {
+        [c & 7, c >> 3 & 3, q2]
+    }
+}
+
+// Values come back as `digit << bits | low bits`, the packed form the
+// unquantisation tables are written against.
+fn decode_ise(data: u128, start: u32, count: usize, range: usize) -> Vec<u32> {
+    let (bits, trits, quints) = RANGES[range];
+    let mut reader = Bits {
+        data,
+        pos: start,
+        end: start + ise_bit_count(count as u32, range),
+    };
+    let mut values = Vec::with_capacity(count + 4);
+    while values.len() < count {
+        if trits == 1 {
+            let mut low = [0; 5];
+            let mut packed = 0;
+            let mut shift = 0;
+            for (i, packed_bits) in [2, 2, 1, 2, 1].into_iter().enumerate() {
+                low[i] = reader.read(bits);
+                packed |= reader.read(packed_bits) << shift;
+                shift += packed_bits;
+            }
+            for (digit, low) in decode_trits(packed).into_iter().zip(low) {
+                values.push(digit << bits | low);
+            }
+        } else if quints == 1 {
+            let mut low = [0; 3];
+            let mut packed = 0;
+            let mut shift = 0;
+            for (i, packed_bits) in [3, 2, 2].into_iter().enumerate() {
+                low[i] = reader.read(bits);
+                packed |= reader.read(packed_bits) << shift;
+                shift += packed_bits;
+            }
+            for (digit, low) in decode_quints(packed).into_iter().zip(low) {
+                values.push(digit << bits | low);
+            }
+        } else {
+            values.push(reader.read(bits));
+        }
+    }
+    values.truncate(count);
+    values
+}
+
+// Inverse of decode_trits, indexed by the digits read as a base-3 number.
+fn trit_encodings() -> &'static [u8; 243] {
+    static TABLE: OnceLock<[u8; 243]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0; 243];
+        for packed in (0..=255).rev() {
+            let index = decode_trits(packed).iter().rev().fold(0, |i, &t| i * 3 + t);
+            table[index as usize] = packed as u8;
+        }
+        table
+    })
+}
+
+fn quint_encodings() -> &'static [u8; 125] {
+    static TABLE: OnceLock<[u8; 125]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0; 125];
+        for packed in (0..=127).rev() {
+            let index = decode_quints(packed)
+                .iter()
+                .rev()
+                .fold(0, |i, &q| i * 5 + q);
+            table[index as usize] = packed as u8;
+        }
+        table
+    })
+}
+
+// Packs values in the `digit << bits | low bits` form decode_ise returns.
+pub(crate) fn encode_ise(values: &[u32], range: usize) -> u128 {
+    let (bits, trits, quints) = RANGES[range];
+    let mut out = 0u128;
+    let mut pos = 0;
+    let mut put = |value: u32, count: u32| {
+        if pos < 128 {
+            out |= u128::from(value) << pos;
+        }
+        pos += count;
+    };
+    let low = |v: u32| v & ((1 << bits) - 1);
+    if trits == 1 || quints == 1 {
+        let (group, base, layout): (usize, u32, &[u32]) = if trits == 1 {
+            (5, 3, &[2, 2, 1, 2, 1])
+        } else {
+            (3, 5, &[3, 2, 2])
+        };
+        for chunk in values.chunks(group) {
+            let index = chunk.iter().rev().fold(0, |i, &v| i * base + (v >> bits));
+            let mut packed = if trits == 1 {
+                trit_encodings()[index as usize]
+            } else {
+                quint_encodings()[index as usize]
+            } as u32;
+            for (i, &packed_bits) in layout.iter().enumerate() {
+                put(chunk.get(i).map_or(0, |&v| low(v)), bits);
+                put(packed & ((1 << packed_bits) - 1), packed_bits);
+                packed >>= packed_bits;
+            }
+        }
+    } else {
+        for &value in values {
+            put(value, bits);
+        }
+    }
+    let used = ise_bit_count(values.len() as u32, range);
+    out & ((1 << used) - 1)
+}
+
+fn replicate(value: u32, bits: u32, to: u32) -> u32 {
+    let mut out = 0;
+    let mut filled = 0;
+    while filled < to {
+        out = out << bits | value;
+        filled += bits;
+    }
+    out >> (filled - to)
+}
+
+fn layout_term(layout: &[u8], low: u32) -> i32 {
+    let top = layout.len() as u32 - 1;
+    layout.iter().enumerate().fold(0, |b, (i, c)| match c {
+        b'0' => b,
+        letter => b | (bit(low, (letter - b'a') as u32) << (top - i as u32)) as i32,
+    })
+}
+
+pub(crate) fn unquantize_endpoint(value: u32, range: usize) -> i32 {
+    let (bits, trits, quints) = RANGES[range];
+    if trits == 0 && quints == 0 {
+        return replicate(value, bits, 8) as i32;
+    }
+    let (layout, c) = ENDPOINT_UNQUANT[range];
+    let low = value & ((1 << bits) - 1);
+    let a = if low & 1 == 1 { 0x1FF } else { 0 };
+    let t = ((value >> bits) as i32 * c + layout_term(layout, low)) ^ a;
+    (a & 0x80) | (t >> 2)
+}
+
+fn unquantize_weight(value: u32, range: usize) -> u32 {
+    let (bits, trits, quints) = RANGES[range];
+    let unquantized = match (bits, trits, quints) {
+        (_, 0, 0) => replicate(value, bits, 6),
+        (0, 1, _) => return value * 32,
+        (0, _, 1) => return value * 16,
+        _ => {
+            let (layout, c) = WEIGHT_UNQUANT[range];
+            let low = value & ((1 << bits) - 1);
+            let a = if low & 1 == 1 { 0x7F } else { 0 };
+            let t = ((value >> bits) as i32 * c + layout_term(layout, low)) ^ a;
+            ((a & 0x20) | (t >> 2)) as u32
+        }
+    };
+    unquantized + u32::from(unquantized > 32)
+}
+
+struct BlockMode {
+    grid_width: u32,
+    grid_height: u32,
+    dual_plane: bool,
+    weight_range: usize,
+}
+
+fn decode_block_mode(mode: u32) -> Option<BlockMode> {
+    let a = mode >> 5 & 3;
+    let mut high_precision = bit(mode, 9);
+    let mut dual_plane = bit(mode, 10);
+    let mut range = bit(mode, 4);
+    let (grid_width, grid_height) = if mode & 3 != 0 {
+        range |= (mode & 3) << 1;
+        let b = mode >> 7 & 3;
+        match mode >> 2 & 3 {
+            0 => (b + 4, a + 2),
+            1 => (b + 8, a + 2),
+            2 => (a + 2, b + 8),
+            _ if mode & 0x100 != 0 => ((b & 1) + 2, a + 2),
+            _ => (a + 2, (b & 1) + 6),
+        }
+    } else {
+        range |= (mode >> 2 & 3) << 1;
+        if mode >> 2 & 3 == 0 {
+            return None;
+        }
+        let b = mode >> 9 & 3;
+        match mode >> 7 & 3 {
+            0 => (12, a + 2),
+            1 => (a + 2, 12),
+            2 => {
+                high_precision = 0;
+                dual_plane = 0;
+                (a + 6, b + 6)
+            }
+            _ => match a {
+                0 => (6, 10),
+                1 => (10, 6),
+                _ => return None,
+            },
+        }
+    };
+    Some(BlockMode {
+        grid_width,
+        grid_height,
+        dual_plane: dual_plane == 1,
+        weight_range: (range - 2 + 6 * high_precision) as usize,
+    })
+}
+
+fn hash52(mut p: u32) -> u32 {
+    p ^= p >> 15;
+    p = p.wrapping_sub(p << 17);
+    p = p.wrapping_add(p << 7);
+    p = p.wrapping_add(p << 4);
+    p ^= p >> 5;
+    p = p.wrapping_add(p << 16);
+    p ^= p >> 7;
+    p ^= p >> 3;
+    p ^= p << 6;
+    p ^= p >> 17;
+    p
+}
+
+pub(crate) fn partition(seed: u32, count: u32, x: u32, y: u32, small_block: bool) -> usize {
+    let (x, y) = if small_block {
+        (x << 1, y << 1)
+    } else {
+        (x, y)
+    };
+    let seed = seed + (count - 1) * 1024;
+    let rnum = hash52(seed);
+    let mut seeds: [u32; 8] = std::array::from_fn(|i| {
+        let s = rnum >> (i * 4) & 0xF;
+        s * s
+    });
+    let (sh1, sh2) = if seed & 1 == 1 {
+        (
+            if seed & 2 != 0 { 4 } else { 5 },
+            if count == 3 { 6 } else { 5 },
+        )
+    } else {
+        (
+            if count == 3 { 6 } else { 5 },
+            if seed & 2 != 0 { 4 } else { 5 },
+        )
+    };
+    for (i, s) in seeds.iter_mut().enumerate() {
+        *s >>= if i % 2 == 0 { sh1 } else { sh2 };
+    }
+    let mut scores = [
+        seeds[0] * x + seeds[1] * y + (rnum >> 14),
+        seeds[2] * x + seeds[3] * y + (rnum >> 10),
+        seeds[4] * x + seeds[5] * y + (rnum >> 6),
+        seeds[6] * x + seeds[7] * y + (rnum >> 2),
+    ]
+    .map(|s| s & 0x3F);
+    for score in scores.iter_mut().skip(count as usize) {
+        *score = 0;
+    }
+    let [a, b, c, d] = scores;
+    if a >= b && a >= c && a >= d {
+        0
+    } else if b >= c && b >= d {
+        1
+    } else if c >= d {
+        2
+    } else {
+        3
+    }
+}
+
+fn bit_transfer_signed(a: i32, b: i32) -> (i32, i32) {
+    let b = (b >> 1) | (a & 0x80);
+    let a = (a >> 1) & 0x3F;
+    (if a & 0x20 != 0 { a - 0x40 } else { a }, b)
+}
+
+fn blue_contract(r: i32, g: i32, b: i32, a: i32) -> [i32; 4] {
+    [(r + b) >> 1, (g + b) >> 1, b, a]
+}
+
+// Returns `None` for the HDR endpoint modes.
+fn endpoints(cem: u32, v: &[i32]) -> Option<[[i32; 4]; 2]> {
+    let pair = match cem {
+        0 => [[v[0], v[0], v[0], 255], [v[1], v[1], v[1], 255]],
+        1 => {
+            let l0 = (v[0] >> 2) | (v[1] & 0xC0);
+            let l1 = (l0 + (v[1] & 0x3F)).min(255);
+            [[l0, l0, l0, 255], [l1, l1, l1, 255]]
+        }
+        4 => [[v[0], v[0], v[0], v[2]], [v[1], v[1], v[1], v[3]]],
+        5 => {
+            let (d0, l0) = bit_transfer_signed(v[1], v[0]);
+            let (d1, a0) = bit_transfer_signed(v[3], v[2]);
+            let l1 = l0 + d0;
+            [[l0, l0, l0, a0], [l1, l1, l1, a0 + d1]]
+        }
+        6 | 10 => {
+            let (a0, a1) = if cem == 10 { (v[4], v[5]) } else { (255, 255) };
+            let scaled = |c: i32| (c * v[3]) >> 8;
+            [
+                [scaled(v[0]), scaled(v[1]), scaled(v[2]), a0],
+                [v[0], v[1], v[2], a1],
+            ]
+        }
+        8 | 12 => {
+            let (a0, a1) = if cem == 12 { (v[6], v[7]) } else { (255, 255) };
+            if v[1] + v[3] + v[5] >= v[0] + v[2] + v[4] {
+                [[v[0], v[2], v[4], a0], [v[1], v[3], v[5], a1]]
+            } else {
+                [
+                    blue_contract(v[1], v[3], v[5], a1),
+                    blue_contract(v[0], v[2], v[4], a0),
+                ]
+            }
+        }
+        9 | 13 => {
+            let (d0, r) = bit_transfer_signed(v[1], v[0]);
+            let (d1, g) = bit_transfer_signed(v[3], v[2]);
+            let (d2, b) = bit_transfer_signed(v[5], v[4]);
+            let (d3, a) = if cem == 13 {
+                bit_transfer_signed(v[7], v[6])
+            } else {
+                (0, 255)
+            };
+            if d0 + d1 + d2 >= 0 {
+                [[r, g, b, a], [r + d0, g + d1, b + d2, a + d3]]
+            } else {
+                [
+                    blue_contract(r + d0, g + d1, b + d2, a + d3),
+                    blue_contract(r, g, b, a),
+                ]
+            }
+        }
+        _ => return None,
+    };
+    Some(pair.map(|e| e.map(|c| c.clamp(0, 255))))
+}
+
+// Bilinear infill from the weight grid to the block's texels.
+fn infill(grid: &[u32], grid_width: u32, grid_height: u32, width: u32, height: u32) -> Vec<u32> {
+    let ds = (1024 + width / 2) / (width - 1).max(1);
+    let dt = (1024 + height / 2) / (height - 1).max(1);
+    let at = |i: u32| grid.get(i as usize).copied().unwrap_or(0);
+    let mut weights = Vec::with_capacity((width * height) as usize);
+    for t in 0..height {
+        for s in 0..width {
+            let gs = (ds * s * (grid_width - 1) + 32) >> 6;
+            let gt = (dt * t * (grid_height - 1) + 32) >> 6;
+            let (js, fs) = (gs >> 4, gs & 0xF);
+            let (jt, ft) = (gt >> 4, gt & 0xF);
+            let w11 = (fs * ft + 8) >> 4;
+            let w10 = ft - w11;
+            let w01 = fs - w11;
+            let w00 = 16 + w11 - fs - ft;
+            let v0 = js + jt * grid_width;
+            let sum = at(v0) * w00
+                + at(v0 + 1) * w01
+                + at(v0 + grid_width) * w10
+                + at(v0 + grid_width + 1) * w11;
+            weights.push((sum + 8) >> 4);
+        }
+    }
+    weights
+}
+
+fn interpolate(e0: i32, e1: i32, weight: u32, srgb: bool) -> u8 {
+    let expand = |e: i32| if srgb { (e << 8) | 0x80 } else { e * 257 };
+    let w = weight as i32;
+    ((((expand(e0) * (64 - w) + expand(e1) * w + 32) >> 6) >> 8) & 0xFF) as u8
+}
+
+// Decodes one 16-byte block of `width` x `height` texels, row-major.
+pub fn decode_astc(block: &[u8], width: u32, height: u32, srgb: bool) -> Vec<[u8; 4]> {
+    decode_block(block, width, height, srgb)
+        .unwrap_or_else(|| vec![ERROR; (width * height) as usize])
+}
+
+fn decode_block(block: &[u8], width: u32, height: u32, srgb: bool) -> Option<Vec<[u8; 4]>> {
+    let data = u128::from_le_bytes(block.get(..16)?.try_into().ok()?);
+    let texels = (width * height) as usize;
+    let mode = field(data, 0, 11);
+
+    if mode & 0x1FF == 0x1FC {
+        if bit(mode, 9) == 1 {
+            return None;
+        }
+        let color: [u8; 4] =
+            std::array::from_fn(|c| (field(data, 64 + c as u32 * 16, 16) >> 8) as u8);
+        return Some(vec![color; texels]);
+    }
+
+    let mode = decode_block_mode(mode)?;
+    let partitions = field(data, 11, 2) + 1;
+    if mode.grid_width > width || mode.grid_height > height || (mode.dual_plane && partitions == 4)
+    {
+        return None;
+    }
+    let planes = if mode.dual_plane { 2 } else { 1 };
+    let weight_count = mode.grid_width * mode.grid_height * planes;
+    let weight_bits = ise_bit_count(weight_count, mode.weight_range);
+    if weight_count > 64 || !(24..=96).contains(&weight_bits) {
+        return None;
+    }
+
+    let mut below_weights = 128 - weight_bits;
+    let mut cems = [0; 4];
+    let mut extra_cem_bits = 0;
+    if partitions == 1 {
+        cems[0] = field(data, 13, 4);
+    } else {
+        let encoded = field(data, 23, 6);
+        if encoded & 3 == 0 {
+            cems = [encoded >> 2; 4];
+        } else {
+            extra_cem_bits = 3 * partitions - 4;
+            below_weights -= extra_cem_bits;
+            let encoded = encoded | field(data, below_weights, extra_cem_bits) << 6;
+            let base = (encoded & 3) - 1;
+            for (i, cem) in cems.iter_mut().take(partitions as usize).enumerate() {
+                let class = base + bit(encoded, 2 + i as u32);
+                let low = encoded >> (2 + partitions + 2 * i as u32) & 3;
+                *cem = class << 2 | low;
+            }
+        }
+    }
+    let cems = &cems[..partitions as usize];
+
+    let value_count: u32 = cems.iter().map(|cem| ((cem >> 2) + 1) * 2).sum();
+    if value_count > 18 {
+        return None;
+    }
+    let config_start = if partitions == 1 { 17 } else { 29 };
+    let ccs_bits = if mode.dual_plane { 2 } else { 0 };
+    let color_bits = 128u32.saturating_sub(weight_bits + extra_cem_bits + config_start + ccs_bits);
+    let color_range = (0..RANGES.len())
+        .rev()
+        .find(|&range| ise_bit_count(value_count, range) <= color_bits)
+        .filter(|&range| range >= 4)?;
+    let values: Vec<i32> = decode_ise(data, config_start, value_count as usize, color_range)
+        .into_iter()
+        .map(|v| unquantize_endpoint(v, color_range))
+        .collect();
+
+    let mut pairs = Vec::with_capacity(cems.len());
+    let mut next = 0;
+    for &cem in cems {
+        let count = (((cem >> 2) + 1) * 2) as usize;
+        pairs.push(endpoints(cem, &values[next..next + count])?);
+        next += count;
+    }
+    let ccs = mode
+        .dual_plane
+        .then(|| field(data, below_weights - 2, 2) as usize);
+
+    let grid: Vec<u32> = decode_ise(
+        data.reverse_bits(),
+        0,
+        weight_count as usize,
+        mode.weight_range,
+    )
+    .into_iter()
+    .map(|w| unquantize_weight(w, mode.weight_range))
+    .collect();
+    let plane_weights: Vec<Vec<u32>> = (0..planes as usize)
+        .map(|plane| {
+            let plane_grid: Vec<u32> = grid
+                .iter()
+                .skip(plane)
+                .step_by(planes as usize)
+                .copied()
+                .collect();
+            infill(
+                &plane_grid,
+                mode.grid_width,
+                mode.grid_height,
+                width,
+                height,
+            )
+        })
+        .collect();
+
+    let seed = field(data, 13, 10);
+    let small_block = texels < 31;
+    let texels = (0..texels).map(|t| {
+        let (x, y) = (t as u32 % width, t as u32 / width);
+        let subset = if partitions == 1 {
+            0
+        } else {
+            partition(seed, partitions, x, y, small_block)
+        };
+        let [e0, e1] = pairs[subset];
+        std::array::from_fn(|c| {
+            let plane = usize::from(ccs == Some(c));
+            interpolate(e0[c], e1[c], plane_weights[plane][t], srgb)
+        })
+    });
+    Some(texels.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::{AstcBlock, AstcChannel, Features, TextureFormat};
+
+    use super::*;
+    use crate::compressed::fit_to_device;
+    use crate::texture::DecodedImage;
+
+    fn void_extent(hdr: bool, color: [u16; 4]) -> [u8; 16] {
+        let mut data = 0x1FC | u128::from(hdr) << 9 | ((1 << 52) - 1) << 12;
+        for (c, value) in color.into_iter().enumerate() {
+            data |= u128::from(value) << (64 + c * 16);
+        }
+        data.to_le_bytes()
+    }
+
+    #[test]
+    fn void_extent_fills_the_block_with_one_color() {
+        let texels = decode_astc(
+            &void_extent(false, [0xFFFF, 0x8000, 0x00FF, 0x4000]),
+            5,
+            4,
+            false,
+        );
+        assert_eq!(texels.len(), 20);
+        assert!(texels.iter().all(|&t| t == [255, 128, 0, 64]));
+    }
+
+    #[test]
+    fn hdr_and_reserved_blocks_decode_to_the_error_color() {
+        let hdr = decode_astc(&void_extent(true, [0; 4]), 4, 4, false);
+        assert!(hdr.iter().all(|&t| t == ERROR));
+        // Block mode with all of bits 0..3 clear is reserved.
+        let reserved = decode_astc(&[0; 16], 4, 4, false);
+        assert!(reserved.iter().all(|&t| t == ERROR));
+    }
+
+    #[test]
+    fn trits_quints_and_unquantisation() {
+        assert_eq!(decode_trits(0), [0; 5]);
+        assert_eq!(decode_trits(0b0111_1110), [2; 5]);
+        assert_eq!(decode_trits(0b0000_0011), [0, 0, 2, 0, 0]);
+        assert_eq!(decode_quints(0), [0; 3]);
+        assert_eq!(decode_quints(0b001_1111), [4; 3]);
+        assert_eq!(decode_quints(0b000_0101), [0, 4, 0]);
+        assert_eq!(unquantize_endpoint(5, 20), 5);
+        assert_eq!(unquantize_endpoint(0b1, 0), 255);
+        assert_eq!(
+            [0, 1, 2, 3].map(|v| unquantize_weight(v, 2)),
+            [0, 21, 43, 64]
+        );
+    }
+
+    // footprint_6x6 comes from google/astc-codec (Apache-2.0), with its reference decode.
+    #[test]
+    fn six_by_six_image_matches_the_reference_decode() {
+        let astc = include_bytes!("../testdata/footprint_6x6.astc");
+        let expected = image::load_from_memory(include_bytes!("../testdata/footprint_6x6.png"))
+            .unwrap()
+            .to_rgba8();
+        let image = DecodedImage {
+            width: 32,
+            height: 32,
+            format: TextureFormat::Astc {
+                block: AstcBlock::B6x6,
+                channel: AstcChannel::Unorm,
+            },
+            mip_level_count: 1,
+            bytes: astc[16..].to_vec(),
+        };
+
+        // A BC-only adapter gets the image decoded to RGBA8 on the CPU.
+        let decoded = fit_to_device(image, Features::TEXTURE_COMPRESSION_BC).unwrap();
+        assert_eq!(decoded.format, TextureFormat::Rgba8Unorm);
+        for (got, want) in decoded.bytes.iter().zip(expected.as_raw()) {
+            assert!(got.abs_diff(*want) <= 1, "{got} vs {want}");
+        }
+    }
+}
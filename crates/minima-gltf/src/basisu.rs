@@ -0,0 +1,974 @@
+// Basis Universal payloads from KTX2 files. ETC1S is repacked as ETC2 and
+// UASTC as ASTC 4x4 when the device samples those formats; otherwise the
+// blocks are decoded and re-encoded as BC7 where BC is available, or left as
+// RGBA8.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result, bail};
+use ktx2::{ColorModel, DfdBlockBasic, TransferFunction};
+use wgpu::{AstcBlock, AstcChannel, Features, TextureFormat};
+
+use crate::astc::{self, Bits};
+use crate::bcn::Texels;
+use crate::compressed::compress_bc7;
+use crate::etc2;
+use crate::texture::DecodedImage;
+
+// KHR_DF_CHANNEL_ETC1S_AAA: the sample describing an ETC1S alpha slice.
+const ETC1S_ALPHA_CHANNEL: u8 = 15;
+
+pub(crate) fn transcode(
+    width: u32,
+    height: u32,
+    dfd: &DfdBlockBasic,
+    global_data: &[u8],
+    levels: &[Vec<u8>],
+    features: Features,
+) -> Result<DecodedImage> {
+    let srgb = dfd.header.transfer_function == Some(TransferFunction::SRGB);
+    let target = Target {
+        width,
+        height,
+        srgb,
+        features,
+    };
+    match dfd.header.color_model {
+        Some(ColorModel::ETC1S) => {
+            let alpha = dfd
+                .sample_information()
+                .any(|sample| sample.channel_type == ETC1S_ALPHA_CHANNEL);
+            transcode_etc1s(&target, global_data, levels, alpha)
+        }
+        Some(ColorModel::UASTC) => transcode_uastc(&target, levels),
+        _ => bail!("KTX2 image has no format"),
+    }
+}
+
+struct Target {
+    width: u32,
+    height: u32,
+    srgb: bool,
+    features: Features,
+}
+
+impl Target {
+    fn level_size(&self, level: usize) -> (usize, usize) {
+        (
+            (self.width >> level).max(1) as usize,
+            (self.height >> level).max(1) as usize,
+        )
+    }
+
+    fn image(&self, format: TextureFormat, levels: Vec<Vec<u8>>) -> DecodedImage {
+        DecodedImage {
+            width: self.width,
+            height: self.height,
+            format,
+            mip_level_count: levels.len() as u32,
+            bytes: levels.concat(),
+        }
+    }
+
+    fn rgba(&self, levels: Vec<Vec<u8>>) -> DecodedImage {
+        let format = if self.srgb {
+            TextureFormat::Rgba8UnormSrgb
+        } else {
+            TextureFormat::Rgba8Unorm
+        };
+        let image = self.image(format, levels);
+        if !self.features.contains(Features::TEXTURE_COMPRESSION_BC) {
+            return image;
+        }
+        match compress_bc7(&image) {
+            Some(bc7) => bc7,
+            None => image,
+        }
+    }
+}
+
+// Copies decoded 4x4 blocks, in raster order, into a tightly packed RGBA8 level.
+fn paint<B: AsRef<[[u8; 4]]>>(
+    width: usize,
+    height: usize,
+    blocks: impl Iterator<Item = B>,
+) -> Vec<u8> {
+    let blocks_x = width.div_ceil(4);
+    let mut pixels = vec![0; width * height * 4];
+    for (i, texels) in blocks.enumerate() {
+        let (bx, by) = (i % blocks_x * 4, i / blocks_x * 4);
+        for (t, texel) in texels.as_ref().iter().enumerate() {
+            let (x, y) = (bx + t % 4, by + t / 4);
+            if x < width && y < height {
+                pixels[(y * width + x) * 4..][..4].copy_from_slice(texel);
+            }
+        }
+    }
+    pixels
+}
+
+// LSB-first bit stream; reads past the end return zeros, as in the reference
+// transcoder.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bits(&mut self, count: u32) -> u32 {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self.data.get(self.pos / 8).copied().unwrap_or(0);
+            value |= u32::from(byte >> (self.pos % 8) & 1) << i;
+            self.pos += 1;
+        }
+        value
+    }
+
+    // Chunks of `chunk_bits`, each followed by a continuation bit.
+    fn vlc(&mut self, chunk_bits: u32) -> u32 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let chunk = self.bits(chunk_bits + 1);
+            value |= (chunk & ((1 << chunk_bits) - 1)) << shift;
+            shift += chunk_bits;
+            if chunk >> chunk_bits == 0 || shift >= 32 {
+                return value;
+            }
+        }
+    }
+
+    fn huffman(&mut self) -> Result<Huffman> {
+        const CODE_LENGTH_ORDER: [usize; 21] = [
+            17, 18, 19, 20, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15, 16,
+        ];
+        let total = self.bits(14) as usize;
+        if total == 0 {
+            return Huffman::new(&[]);
+        }
+        let count = self.bits(5) as usize;
+        if !(1..=CODE_LENGTH_ORDER.len()).contains(&count) {
+            bail!("corrupt Basis Huffman table");
+        }
+        let mut length_sizes = [0u8; 21];
+        for &code in &CODE_LENGTH_ORDER[..count] {
+            length_sizes[code] = self.bits(3) as u8;
+        }
+        let lengths = Huffman::new(&length_sizes)?;
+
+        let mut sizes = Vec::with_capacity(total);
+        while sizes.len() < total {
+            match lengths.decode(self)? {
+                size @ 0..=16 => sizes.push(size as u8),
+                17 => sizes.resize(sizes.len() + self.bits(3) as usize + 3, 0),
+                18 => sizes.resize(sizes.len() + self.bits(7) as usize + 11, 0),
+                code => {
+                    let run = if code == 19 {
+                        self.bits(2) + 3
+                    } else {
+                        self.bits(7) + 7
+                    };
+                    let previous = *sizes
+                        .last()
+                        .filter(|&&size| size != 0)
+                        .context("corrupt Basis Huffman table")?;
+                    sizes.resize(sizes.len() + run as usize, previous);
+                }
+            }
+        }
+        if sizes.len() != total {
+            bail!("corrupt Basis Huffman table");
+        }
+        Huffman::new(&sizes)
+    }
+}
+
+// Canonical Huffman code, read most significant bit first.
+struct Huffman {
+    counts: [u32; 17],
+    symbols: Vec<u32>,
+}
+
+impl Huffman {
+    fn new(sizes: &[u8]) -> Result<Self> {
+        let mut counts = [0; 17];
+        for &size in sizes {
+            *counts
+                .get_mut(size as usize)
+                .context("corrupt Basis Huffman table")? += 1;
+        }
+        counts[0] = 0;
+        let mut symbols: Vec<u32> = (0..sizes.len() as u32)
+            .filter(|&s| sizes[s as usize] != 0)
+            .collect();
+        symbols.sort_by_key(|&s| sizes[s as usize]);
+        Ok(Self { counts, symbols })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u32> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for &count in &self.counts[1..] {
+            code |= reader.bits(1);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        bail!("invalid Basis Huffman code")
+    }
+}
+
+struct Endpoint {
+    color5: [u32; 3],
+    inten: u32,
+}
+
+struct Etc1sTables {
+    endpoint_pred: Huffman,
+    delta_endpoint: Huffman,
+    selector: Huffman,
+    selector_rle: Huffman,
+    history_size: usize,
+}
+
+// The palettes and models shared by every slice of a BasisLZ file.
+struct Etc1sCodebook {
+    endpoints: Vec<Endpoint>,
+    // Linear selector indices, row-major: 0 is the darkest ETC1 modifier.
+    selectors: Vec<[u8; 16]>,
+    tables: Etc1sTables,
+}
+
+impl Etc1sCodebook {
+    fn parse(global_data: &[u8], level_count: usize) -> Result<Self> {
+        let u16_at = |at: usize| -> Result<usize> {
+            let bytes = global_data
+                .get(at..at + 2)
+                .context("ETC1S global data is truncated")?;
+            Ok(u16::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        };
+        let u32_at = |at: usize| -> Result<usize> {
+            let bytes = global_data
+                .get(at..at + 4)
+                .context("ETC1S global data is truncated")?;
+            Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        };
+        let (endpoint_count, selector_count) = (u16_at(0)?, u16_at(2)?);
+        let lengths = [u32_at(4)?, u32_at(8)?, u32_at(12)?];
+
+        // A 20-byte header, then one 20-byte image descriptor per level.
+        let mut start = 20 + 20 * level_count;
+        let mut sections = Vec::new();
+        for len in lengths {
+            let section = global_data
+                .get(start..start + len)
+                .context("ETC1S global data is truncated")?;
+            sections.push(section);
+            start += len;
+        }
+        Ok(Self {
+            endpoints: decode_endpoints(sections[0], endpoint_count)?,
+            selectors: decode_selectors(sections[1], selector_count)?,
+            tables: decode_tables(sections[2])?,
+        })
+    }
+
+    fn image_slices<'a>(
+        &self,
+        global_data: &[u8],
+        level: usize,
+        data: &'a [u8],
+    ) -> Result<[&'a [u8]; 2]> {
+        let desc = 20 + 20 * level;
+        let field = |i: usize| -> Result<usize> {
+            let bytes = global_data
+                .get(desc + 4 * i..desc + 4 * i + 4)
+                .context("ETC1S global data is truncated")?;
+            Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        };
+        let slice = |offset: usize, len: usize| {
+            data.get(offset..offset + len)
+                .context("ETC1S slice lies outside its level")
+        };
+        Ok([slice(field(1)?, field(2)?)?, slice(field(3)?, field(4)?)?])
+    }
+
+    fn etc1_block(&self, (endpoint, selector): (usize, usize)) -> [u8; 8] {
+        // Linear selector order to ETC1 selector codes.
+        const ETC1_CODES: [u64; 4] = [3, 2, 0, 1];
+        let Endpoint {
+            color5: [r, g, b],
+            inten,
+        } = self.endpoints[endpoint];
+        let (r, g, b, inten) = (r as u64, g as u64, b as u64, inten as u64);
+        // Differential mode with a zero delta and both halves on one table.
+        let mut word = r << 59 | g << 51 | b << 43 | inten << 37 | inten << 34 | 1 << 33;
+        for (texel, &s) in self.selectors[selector].iter().enumerate() {
+            let code = ETC1_CODES[s as usize];
+            let column_major = (texel % 4) * 4 + texel / 4;
+            word |= (code >> 1) << (16 + column_major) | (code & 1) << column_major;
+        }
+        word.to_be_bytes()
+    }
+
+    fn texels(&self, block: (usize, usize)) -> Texels {
+        etc2::decode_etc2_rgb(&self.etc1_block(block))
+    }
+}
+
+fn decode_endpoints(data: &[u8], count: usize) -> Result<Vec<Endpoint>> {
+    let mut reader = BitReader::new(data);
+    let color_models = [reader.huffman()?, reader.huffman()?, reader.huffman()?];
+    let inten_model = reader.huffman()?;
+    if color_models.iter().any(Huffman::is_empty) || inten_model.is_empty() {
+        bail!("corrupt ETC1S endpoint palette");
+    }
+    let grayscale = reader.bits(1) == 1;
+
+    let mut color5 = [16u32; 3];
+    let mut inten = 0;
+    let mut endpoints = Vec::with_capacity(count);
+    for _ in 0..count {
+        inten = (inten_model.decode(&mut reader)? + inten) & 7;
+        for c in &mut color5[..if grayscale { 1 } else { 3 }] {
+            // The previous value picks which delta model codes the next.
+            let model = match *c {
+                0..=9 => &color_models[0],
+                10..=21 => &color_models[1],
+                _ => &color_models[2],
+            };
+            *c = (*c + model.decode(&mut reader)?) & 31;
+        }
+        let color5 = if grayscale { [color5[0]; 3] } else { color5 };
+        endpoints.push(Endpoint { color5, inten });
+    }
+    Ok(endpoints)
+}
+
+fn decode_selectors(data: &[u8], count: usize) -> Result<Vec<[u8; 16]>> {
+    let mut reader = BitReader::new(data);
+    if reader.bits(1) == 1 || reader.bits(1) == 1 {
+        bail!("ETC1S global and hybrid selector codebooks are not supported");
+    }
+    let unpack = |rows: [u32; 4]| -> [u8; 16] {
+        std::array::from_fn(|t| (rows[t / 4] >> (t % 4 * 2) & 3) as u8)
+    };
+    if reader.bits(1) == 1 {
+        return Ok((0..count)
+            .map(|_| unpack(std::array::from_fn(|_| reader.bits(8))))
+            .collect());
+    }
+
+    // The first selector is raw; the rest XOR a coded delta onto the previous one.
+    let delta_model = reader.huffman()?;
+    if count > 1 && delta_model.is_empty() {
+        bail!("corrupt ETC1S selector palette");
+    }
+    let mut rows = [0u32; 4];
+    let mut selectors = Vec::with_capacity(count);
+    for i in 0..count {
+        for row in &mut rows {
+            *row = if i == 0 {
+                reader.bits(8)
+            } else {
+                delta_model.decode(&mut reader)? ^ *row
+            };
+        }
+        selectors.push(unpack(rows));
+    }
+    Ok(selectors)
+}
+
+fn decode_tables(data: &[u8]) -> Result<Etc1sTables> {
+    let mut reader = BitReader::new(data);
+    let tables = Etc1sTables {
+        endpoint_pred: reader.huffman()?,
+        delta_endpoint: reader.huffman()?,
+        selector: reader.huffman()?,
+        selector_rle: reader.huffman()?,
+        history_size: reader.bits(13) as usize,
+    };
+    let models = [
+        &tables.endpoint_pred,
+        &tables.delta_endpoint,
+        &tables.selector,
+        &tables.selector_rle,
+    ];
+    if models.iter().any(|model| model.is_empty()) || tables.history_size == 0 {
+        bail!("corrupt ETC1S slice tables");
+    }
+    Ok(tables)
+}
+
+// Recently used selectors, nudged towards the front as they are reused.
+struct SelectorHistory {
+    values: Vec<usize>,
+    rover: usize,
+}
+
+impl SelectorHistory {
+    fn add(&mut self, selector: usize) {
+        self.values[self.rover] = selector;
+        self.rover += 1;
+        if self.rover == self.values.len() {
+            self.rover = self.values.len() / 2;
+        }
+    }
+
+    fn take(&mut self, index: usize) -> Option<usize> {
+        let selector = *self.values.get(index)?;
+        self.values.swap(index / 2, index);
+        Some(selector)
+    }
+}
+
+// Returns each block's (endpoint, selector) palette indices in raster order.
+fn decode_slice(
+    codebook: &Etc1sCodebook,
+    data: &[u8],
+    blocks_x: usize,
+    blocks_y: usize,
+) -> Result<Vec<(usize, usize)>> {
+    const REPEAT_PREDICTION: u32 = 256;
+    const LONG_SELECTOR_RUN: usize = 63;
+    let tables = &codebook.tables;
+    let endpoint_count = codebook.endpoints.len();
+    let selector_count = codebook.selectors.len();
+    let selector_run = selector_count + tables.history_size;
+    let corrupt = || anyhow::anyhow!("corrupt ETC1S slice");
+
+    let mut reader = BitReader::new(data);
+    let mut history = SelectorHistory {
+        values: vec![0; tables.history_size],
+        rover: tables.history_size / 2,
+    };
+    // Per column: (endpoint index, predictor bits for the odd row) for the
+    // previous and current rows.
+    let mut rows = [vec![(0usize, 0u32); blocks_x], vec![(0, 0); blocks_x]];
+    let (mut pred_bits, mut prev_pred, mut pred_repeat) = (0, 0, 0);
+    let mut prev_endpoint = 0;
+    let mut selector_repeat = 0;
+
+    let mut blocks = Vec::with_capacity(blocks_x * blocks_y);
+    for y in 0..blocks_y {
+        let row = y & 1;
+        for x in 0..blocks_x {
+            // One symbol predicts a 2x2 group of blocks, two bits each.
+            if x & 1 == 0 {
+                if y & 1 == 0 {
+                    if pred_repeat > 0 {
+                        pred_repeat -= 1;
+                        pred_bits = prev_pred;
+                    } else {
+                        pred_bits = tables.endpoint_pred.decode(&mut reader)?;
+                        if pred_bits == REPEAT_PREDICTION {
+                            pred_repeat = reader.vlc(4) + 2;
+                            pred_bits = prev_pred;
+                        } else {
+                            prev_pred = pred_bits;
+                        }
+                    }
+                    rows[row ^ 1][x].1 = pred_bits >> 4;
+                } else {
+                    pred_bits = rows[row][x].1;
+                }
+            }
+
+            let endpoint = match pred_bits & 3 {
+                0 if x > 0 => prev_endpoint,
+                1 if y > 0 => rows[row ^ 1][x].0,
+                2 if x > 0 && y > 0 => rows[row ^ 1][x - 1].0,
+                3 => {
+                    let e = tables.delta_endpoint.decode(&mut reader)? as usize + prev_endpoint;
+                    if e >= endpoint_count {
+                        e - endpoint_count
+                    } else {
+                        e
+                    }
+                }
+                _ => return Err(corrupt()),
+            };
+            pred_bits >>= 2;
+            rows[row][x].0 = endpoint;
+            prev_endpoint = endpoint;
+
+            let mut symbol = if selector_repeat > 0 {
+                selector_repeat -= 1;
+                selector_count
+            } else {
+                tables.selector.decode(&mut reader)? as usize
+            };
+            if symbol == selector_run {
+                let run = tables.selector_rle.decode(&mut reader)? as usize;
+                selector_repeat = if run == LONG_SELECTOR_RUN {
+                    reader.vlc(7) as usize + 3
+                } else {
+                    run + 3
+                };
+                if selector_repeat > blocks_x * blocks_y {
+                    return Err(corrupt());
+                }
+                selector_repeat -= 1;
+                symbol = selector_count;
+            }
+            let selector = if symbol >= selector_count {
+                history.take(symbol - selector_count).ok_or_else(corrupt)?
+            } else {
+                history.add(symbol);
+                symbol
+            };
+
+            if endpoint >= endpoint_count || selector >= selector_count {
+                return Err(corrupt());
+            }
+            blocks.push((endpoint, selector));
+        }
+    }
+    Ok(blocks)
+}
+
+fn transcode_etc1s(
+    target: &Target,
+    global_data: &[u8],
+    levels: &[Vec<u8>],
+    alpha: bool,
+) -> Result<DecodedImage> {
+    let codebook = Etc1sCodebook::parse(global_data, levels.len())?;
+    let etc2 = target.features.contains(Features::TEXTURE_COMPRESSION_ETC2);
+    // Alpha blocks repeat heavily, so their EAC fits are shared.
+    let mut eac_blocks: HashMap<(usize, usize), [u8; 8]> = HashMap::new();
+
+    let mut out = Vec::with_capacity(levels.len());
+    for (level, data) in levels.iter().enumerate() {
+        let (width, height) = target.level_size(level);
+        let (blocks_x, blocks_y) = (width.div_ceil(4), height.div_ceil(4));
+        let [rgb_slice, alpha_slice] = codebook.image_slices(global_data, level, data)?;
+        let rgb = decode_slice(&codebook, rgb_slice, blocks_x, blocks_y)?;
+        let alpha = if alpha {
+            Some(decode_slice(&codebook, alpha_slice, blocks_x, blocks_y)?)
+        } else {
+            None
+        };
+
+        out.push(match (&alpha, etc2) {
+            (None, true) => rgb.iter().flat_map(|&b| codebook.etc1_block(b)).collect(),
+            (Some(alpha), true) => rgb
+                .iter()
+                .zip(alpha)
+                .flat_map(|(&color, &a)| {
+                    let eac = *eac_blocks.entry(a).or_insert_with(|| {
+                        etc2::encode_eac_alpha(&codebook.texels(a).map(|texel| texel[1]))
+                    });
+                    [eac, codebook.etc1_block(color)].concat()
+                })
+                .collect(),
+            (alpha, false) => {
+                let blocks = rgb.iter().enumerate().map(|(i, &color)| {
+                    let mut texels = codebook.texels(color);
+                    if let Some(alpha) = alpha {
+                        // The alpha slice stores its value in green.
+                        for (texel, a) in texels.iter_mut().zip(codebook.texels(alpha[i])) {
+                            texel[3] = a[1];
+                        }
+                    }
+                    texels
+                });
+                paint(width, height, blocks)
+            }
+        });
+    }
+
+    if !etc2 {
+        return Ok(target.rgba(out));
+    }
+    let format = match (alpha, target.srgb) {
+        (false, false) => TextureFormat::Etc2Rgb8Unorm,
+        (false, true) => TextureFormat::Etc2Rgb8UnormSrgb,
+        (true, false) => TextureFormat::Etc2Rgba8Unorm,
+        (true, true) => TextureFormat::Etc2Rgba8UnormSrgb,
+    };
+    Ok(target.image(format, out))
+}
+
+struct UastcMode {
+    code_bits: u32,
+    hint_bits: u32,
+    weight_bits: u32,
+    endpoint_range: usize,
+    subsets: usize,
+    planes: usize,
+    components: usize,
+    cem: u32,
+    // The 11-bit ASTC block mode with the same weight grid and range.
+    astc_mode: u32,
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn mode(
+    code_bits: u32,
+    hint_bits: u32,
+    weight_bits: u32,
+    endpoint_range: usize,
+    subsets: usize,
+    planes: usize,
+    components: usize,
+    cem: u32,
+    astc_mode: u32,
+) -> UastcMode {
+    UastcMode {
+        code_bits,
+        hint_bits,
+        weight_bits,
+        endpoint_range,
+        subsets,
+        planes,
+        components,
+        cem,
+        astc_mode,
+    }
+}
+
+const SOLID_MODE: usize = 8;
+
+const UASTC_MODES: [UastcMode; 19] = [
+    mode(4, 15, 4, 19, 1, 1, 3, 8, 0x242),
+    mode(6, 15, 2, 20, 1, 1, 3, 8, 0x42),
+    mode(5, 15, 3, 8, 2, 1, 3, 8, 0x53),
+    mode(5, 15, 2, 7, 3, 1, 3, 8, 0x42),
+    mode(5, 15, 2, 12, 2, 1, 3, 8, 0x42),
+    mode(5, 15, 3, 20, 1, 1, 3, 8, 0x53),
+    mode(5, 15, 2, 18, 1, 2, 3, 8, 0x442),
+    mode(5, 15, 2, 12, 2, 1, 3, 8, 0x42),
+    mode(5, 0, 0, 0, 0, 0, 4, 0, 0),
+    mode(5, 23, 2, 8, 2, 1, 4, 12, 0x42),
+    mode(3, 17, 4, 13, 1, 1, 4, 12, 0x242),
+    mode(2, 17, 2, 13, 1, 2, 4, 12, 0x442),
+    mode(3, 17, 3, 19, 1, 1, 4, 12, 0x53),
+    mode(5, 23, 1, 20, 1, 2, 4, 12, 0x441),
+    mode(5, 23, 2, 20, 1, 1, 4, 12, 0x42),
+    mode(7, 23, 4, 20, 1, 1, 2, 4, 0x242),
+    mode(6, 23, 2, 20, 2, 1, 2, 4, 0x42),
+    mode(6, 23, 2, 20, 1, 2, 2, 4, 0x442),
+    mode(4, 15, 5, 11, 1, 1, 3, 8, 0x253),
+];
+
+// The mode for each value of a block's low seven bits; 19 marks an invalid code.
+const UASTC_MODE_CODES: [u8; 128] = [
+    11, 0, 10, 3, 11, 15, 12, 7, 11, 18, 10, 5, 11, 14, 12, 9, 11, 0, 10, 4, 11, 16, 12, 8, 11, 18,
+    10, 6, 11, 2, 12, 13, 11, 0, 10, 3, 11, 17, 12, 7, 11, 18, 10, 5, 11, 14, 12, 9, 11, 0, 10, 4,
+    11, 1, 12, 8, 11, 18, 10, 6, 11, 2, 12, 13, 11, 0, 10, 3, 11, 19, 12, 7, 11, 18, 10, 5, 11, 14,
+    12, 9, 11, 0, 10, 4, 11, 16, 12, 8, 11, 18, 10, 6, 11, 2, 12, 13, 11, 0, 10, 3, 11, 17, 12, 7,
+    11, 18, 10, 5, 11, 14, 12, 9, 11, 0, 10, 4, 11, 1, 12, 8, 11, 18, 10, 6, 11, 2, 12, 13,
+];
+
+// ASTC partition seeds for UASTC's common two- and three-subset patterns.
+const PATTERNS_2: [u32; 30] = [
+    28, 20, 16, 29, 91, 9, 107, 72, 149, 204, 50, 114, 496, 17, 78, 39, 252, 828, 43, 156, 116,
+    210, 476, 273, 684, 359, 246, 195, 694, 524,
+];
+const PATTERNS_3: [u32; 11] = [260, 74, 32, 156, 183, 15, 745, 0, 335, 902, 254];
+const PATTERNS_7: [u32; 19] = [
+    36, 48, 61, 137, 161, 183, 226, 281, 302, 307, 479, 495, 593, 594, 605, 799, 812, 988, 993,
+];
+
+// Repacks a UASTC block as the equivalent ASTC 4x4 block.
+fn uastc_to_astc(block: &[u8]) -> Option<[u8; 16]> {
+    let data = u128::from_le_bytes(block.try_into().ok()?);
+    let index = UASTC_MODE_CODES[(block[0] & 127) as usize] as usize;
+    let mode = UASTC_MODES.get(index)?;
+    let mut bits = Bits {
+        data,
+        pos: mode.code_bits,
+        end: 128,
+    };
+
+    if index == SOLID_MODE {
+        // A void-extent block covering the whole texture.
+        let mut out = 0xFFFF_FFFF_FFFF_FDFCu128;
+        for c in 0..4 {
+            out |= u128::from(bits.read(8) * 0x101) << (64 + 16 * c);
+        }
+        return Some(out.to_le_bytes());
+    }
+    bits.pos += mode.hint_bits;
+
+    let seed = match index {
+        2 | 4 | 9 | 16 => *PATTERNS_2.get(bits.read(5) as usize)?,
+        3 => *PATTERNS_3.get(bits.read(4) as usize)?,
+        7 => *PATTERNS_7.get(bits.read(5) as usize)?,
+        _ => 0,
+    };
+    let ccs = match index {
+        6 | 11 | 13 => bits.read(2),
+        _ => 3,
+    };
+
+    // Trit or quint groups come first, then each value's low bits.
+    let (low_bits, trits, quints) = astc::RANGES[mode.endpoint_range];
+    let value_count = mode.components * 2 * mode.subsets;
+    let (group, base): (usize, u32) = match (trits, quints) {
+        (1, _) => (5, 3),
+        (_, 1) => (3, 5),
+        _ => (0, 0),
+    };
+    let mut packed = Vec::new();
+    if group > 0 {
+        let groups = value_count.div_ceil(group);
+        for g in 0..groups {
+            let remaining = value_count - g * group;
+            let width = match (group, remaining) {
+                (5, 1) => 2,
+                (5, 2) => 4,
+                (5, 3) => 5,
+                (5, 4) => 7,
+                (5, _) => 8,
+                (_, 1) => 3,
+                (_, 2) => 5,
+                _ => 7,
+            };
+            packed.push(bits.read(width));
+        }
+    }
+    let mut endpoints: Vec<u32> = (0..value_count)
+        .map(|i| {
+            let low = bits.read(low_bits);
+            if group == 0 {
+                return low;
+            }
+            let digit = packed[i / group] / base.pow((i % group) as u32) % base;
+            digit << low_bits | low
+        })
+        .collect();
+
+    // The first texel of each subset drops its weight's top bit.
+    let subset_of = |texel: usize| {
+        if mode.subsets == 1 {
+            0
+        } else {
+            astc::partition(
+                seed,
+                mode.subsets as u32,
+                texel as u32 % 4,
+                texel as u32 / 4,
+                true,
+            )
+        }
+    };
+    let anchors: Vec<usize> = (0..mode.subsets)
+        .map(|s| (0..16).find(|&t| subset_of(t) == s).unwrap_or(0))
+        .collect();
+    let weight_count = 16 * mode.planes;
+    let mut weights: Vec<u32> = (0..weight_count)
+        .map(|i| {
+            let anchor = if mode.planes == 2 {
+                i < 2
+            } else {
+                anchors.contains(&i)
+            };
+            bits.read(mode.weight_bits - u32::from(anchor))
+        })
+        .collect();
+
+    // ASTC would blue-contract endpoint pairs whose second colour is darker,
+    // so those pairs are swapped and their weights inverted.
+    if mode.components >= 3 {
+        let unquantized = |v: u32| astc::unquantize_endpoint(v, mode.endpoint_range);
+        let max_weight = (1 << mode.weight_bits) - 1;
+        for subset in 0..mode.subsets {
+            let pair = &mut endpoints[subset * mode.components * 2..][..mode.components * 2];
+            let sum = |first: usize| {
+                (0..3)
+                    .map(|c| unquantized(pair[c * 2 + first]))
+                    .sum::<i32>()
+            };
+            if sum(1) < sum(0) {
+                for c in 0..mode.components {
+                    pair.swap(c * 2, c * 2 + 1);
+                }
+                for texel in (0..16).filter(|&t| subset_of(t) == subset) {
+                    for plane in 0..mode.planes {
+                        let w = &mut weights[texel * mode.planes + plane];
+                        *w = max_weight - *w;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = u128::from(mode.astc_mode) | ((mode.subsets - 1) as u128) << 11;
+    let endpoints_start = if mode.subsets == 1 {
+        out |= u128::from(mode.cem) << 13;
+        17
+    } else {
+        out |= u128::from(seed) << 13 | u128::from((mode.cem << 2) & 63) << 23;
+        29
+    };
+    let weight_bits = weight_count as u32 * mode.weight_bits;
+    if mode.planes == 2 {
+        out |= u128::from(ccs) << (128 - weight_bits - 2);
+    }
+    out |= astc::encode_ise(&endpoints, mode.endpoint_range) << endpoints_start;
+    // Weights run downwards from the top of the block.
+    let weight_stream = weights.iter().enumerate().fold(0u128, |stream, (i, &w)| {
+        stream | u128::from(w) << (i as u32 * mode.weight_bits)
+    });
+    out |= weight_stream.reverse_bits();
+    Some(out.to_le_bytes())
+}
+
+fn transcode_uastc(target: &Target, levels: &[Vec<u8>]) -> Result<DecodedImage> {
+    let astc = target.features.contains(Features::TEXTURE_COMPRESSION_ASTC);
+    let mut out = Vec::with_capacity(levels.len());
+    for (level, data) in levels.iter().enumerate() {
+        let (width, height) = target.level_size(level);
+        let blocks = width.div_ceil(4) * height.div_ceil(4);
+        if data.len() != blocks * 16 {
+            bail!(
+                "UASTC level {level} has {} bytes, expected {}",
+                data.len(),
+                blocks * 16
+            );
+        }
+        let astc_blocks = data
+            .chunks_exact(16)
+            .map(|block| uastc_to_astc(block).context("corrupt UASTC block"))
+            .collect::<Result<Vec<_>>>()?;
+        out.push(if astc {
+            astc_blocks.concat()
+        } else {
+            // UASTC decodes as plain (not sRGB-expanded) ASTC.
+            let texels = astc_blocks
+                .iter()
+                .map(|block| astc::decode_astc(block, 4, 4, false));
+            paint(width, height, texels)
+        });
+    }
+
+    if !astc {
+        return Ok(target.rgba(out));
+    }
+    let channel = if target.srgb {
+        AstcChannel::UnormSrgb
+    } else {
+        AstcChannel::Unorm
+    };
+    let format = TextureFormat::Astc {
+        block: AstcBlock::B4x4,
+        channel,
+    };
+    Ok(target.image(format, out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compressed::decompress;
+    use crate::ktx::load_ktx2;
+
+    // 16x16 images with alpha and five mips, encoded by the basisu tool. The
+    // PNGs are the tool's own RGBA32 decode of the top level.
+    const ETC1S: &[u8] = include_bytes!("../testdata/etc1s.ktx2");
+    const UASTC: &[u8] = include_bytes!("../testdata/uastc.ktx2");
+
+    fn reference(png: &[u8]) -> Vec<u8> {
+        image::load_from_memory(png).unwrap().to_rgba8().into_raw()
+    }
+
+    fn assert_close(got: &[u8], want: &[u8], tolerance: u8) {
+        assert_eq!(got.len(), want.len());
+        for (got, want) in got.iter().zip(want) {
+            assert!(got.abs_diff(*want) <= tolerance, "{got} vs {want}");
+        }
+    }
+
+    #[test]
+    fn etc1s_without_compression_decodes_to_rgba8() {
+        let image = load_ktx2(ETC1S, Features::empty()).unwrap();
+        assert_eq!(image.format, TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(image.mip_level_count, 5);
+        assert_eq!(image.bytes.len(), 4 * (256 + 64 + 16 + 4 + 1));
+        let expected = reference(include_bytes!("../testdata/etc1s.png"));
+        assert_eq!(&image.bytes[..expected.len()], expected);
+    }
+
+    #[test]
+    fn etc1s_repacks_as_etc2_when_supported() {
+        let image = load_ktx2(ETC1S, Features::TEXTURE_COMPRESSION_ETC2).unwrap();
+        assert_eq!(image.format, TextureFormat::Etc2Rgba8UnormSrgb);
+        assert_eq!(image.mip_level_count, 5);
+        // The alpha blocks are refit as EAC, which may round differently.
+        let decoded = decompress(&image).unwrap();
+        let expected = reference(include_bytes!("../testdata/etc1s.png"));
+        assert_close(&decoded.bytes[..expected.len()], &expected, 1);
+    }
+
+    #[test]
+    fn etc1s_becomes_bc7_on_bc_adapters() {
+        let image = load_ktx2(ETC1S, Features::TEXTURE_COMPRESSION_BC).unwrap();
+        assert_eq!(image.format, TextureFormat::Bc7RgbaUnormSrgb);
+        assert_eq!(image.mip_level_count, 5);
+    }
+
+    #[test]
+    fn uastc_repacks_as_astc_when_supported() {
+        let image = load_ktx2(UASTC, Features::TEXTURE_COMPRESSION_ASTC).unwrap();
+        assert_eq!(
+            image.format,
+            TextureFormat::Astc {
+                block: AstcBlock::B4x4,
+                channel: AstcChannel::UnormSrgb,
+            }
+        );
+        assert_eq!(image.bytes.len(), 16 * (16 + 4 + 1 + 1 + 1));
+        let level = DecodedImage {
+            width: 16,
+            height: 16,
+            format: TextureFormat::Astc {
+                block: AstcBlock::B4x4,
+                channel: AstcChannel::Unorm,
+            },
+            mip_level_count: 1,
+            bytes: image.bytes[..256].to_vec(),
+        };
+        let decoded = decompress(&level).unwrap();
+        let expected = reference(include_bytes!("../testdata/uastc.png"));
+        assert_eq!(decoded.bytes, expected);
+    }
+
+    #[test]
+    fn uastc_without_compression_decodes_to_rgba8() {
+        let image = load_ktx2(UASTC, Features::empty()).unwrap();
+        assert_eq!(image.format, TextureFormat::Rgba8UnormSrgb);
+        let expected = reference(include_bytes!("../testdata/uastc.png"));
+        assert_eq!(&image.bytes[..expected.len()], expected);
+    }
+
+    #[test]
+    fn corrupt_etc1s_codebook_is_an_error() {
+        let reader = ktx2::Reader::new(ETC1S).unwrap();
+        let dfd = reader
+            .dfd_blocks()
+            .find_map(|block| DfdBlockBasic::parse(block.data).ok())
+            .unwrap();
+        let levels: Vec<Vec<u8>> = reader.levels().map(|level| level.data.to_vec()).collect();
+        let global = reader.supercompression_global_data();
+
+        let truncated = &global[..global.len() / 2];
+        assert!(transcode(16, 16, &dfd, truncated, &levels, Features::empty()).is_err());
+        let mut garbled = global.to_vec();
+        let tables = garbled.len() - 8;
+        garbled[tables..].fill(0xFF);
+        assert!(transcode(16, 16, &dfd, &garbled, &levels, Features::empty()).is_err());
+    }
+}
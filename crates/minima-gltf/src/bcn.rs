@@ -0,0 +1,741 @@
+pub type Texels = [[u8; 4]; 16];
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+// Bit i set means texel i belongs to the second subset.
+const PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80, 0xc800, 0xffec, 0xfe80, 0xe800,
+    0xffe8, 0xff00, 0xfff0, 0xf000, 0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c, 0xaaaa, 0xf0f0, 0x5a5a, 0x33cc,
+    0x3c3c, 0x55aa, 0x9696, 0xa55a, 0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c, 0x9336, 0x9cc6, 0x817e, 0xe718,
+    0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+// Two bits per texel, texel 0 in the lowest bits.
+const PARTITIONS_3: [u32; 64] = [
+    0xaa685050, 0x6a5a5040, 0x5a5a4200, 0x5450a0a8, 0xa5a50000, 0xa0a05050, 0x5555a0a0, 0x5a5a5050,
+    0xaa550000, 0xaa555500, 0xaaaa5500, 0x90909090, 0x94949494, 0xa4a4a4a4, 0xa9a59450, 0x2a0a4250,
+    0xa5945040, 0x0a425054, 0xa5a5a500, 0x55a0a0a0, 0xa8a85454, 0x6a6a4040, 0xa4a45000, 0x1a1a0500,
+    0x0050a4a4, 0xaaa59090, 0x14696914, 0x69691400, 0xa08585a0, 0xaa821414, 0x50a4a450, 0x6a5a0200,
+    0xa9a58000, 0x5090a0a8, 0xa8a09050, 0x24242424, 0x00aa5500, 0x24924924, 0x24499224, 0x50a50a50,
+    0x500aa550, 0xaaaa4444, 0x66660000, 0xa5a0a5a0, 0x50a050a0, 0x69286928, 0x44aaaa44, 0x66666600,
+    0xaa444444, 0x54a854a8, 0x95809580, 0x96969600, 0xa85454a8, 0x80959580, 0xaa141414, 0x96960000,
+    0xaaaa1414, 0xa05050a0, 0xa0a5a5a0, 0x96000000, 0x40804080, 0xa9a8a9a8, 0xaaaaaa44, 0x2a4a5254,
+];
+
+const ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+const ANCHORS_3A: [u8; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5,
+    15, 15, 8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8, 5, 10, 5,
+    10, 8, 13, 15, 12, 3, 3,
+];
+
+const ANCHORS_3B: [u8; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6,
+    10, 15, 15, 10, 8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15, 15, 15,
+    15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+];
+
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    selector_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    index2_bits: u32,
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn mode(
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    selector_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    index2_bits: u32,
+) -> Bc7Mode {
+    Bc7Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        selector_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_pbits,
+        shared_pbits,
+        index_bits,
+        index2_bits,
+    }
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    mode(3, 4, 0, 0, 4, 0, true, false, 3, 0),
+    mode(2, 6, 0, 0, 6, 0, false, true, 3, 0),
+    mode(3, 6, 0, 0, 5, 0, false, false, 2, 0),
+    mode(2, 6, 0, 0, 7, 0, true, false, 2, 0),
+    mode(1, 0, 2, 1, 5, 6, false, false, 2, 3),
+    mode(1, 0, 2, 0, 7, 8, false, false, 2, 2),
+    mode(1, 0, 0, 0, 7, 7, true, false, 4, 0),
+    mode(2, 6, 0, 0, 5, 5, true, false, 2, 0),
+];
+
+struct BitReader {
+    bits: u128,
+    pos: u32,
+}
+
+impl BitReader {
+    fn new(block: &[u8]) -> Self {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&block[..16]);
+        Self {
+            bits: u128::from_le_bytes(bytes),
+            pos: 0,
+        }
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.bits >> self.pos) as u32 & ((1u64 << count) - 1) as u32;
+        self.pos += count;
+        value
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bits: u128,
+    pos: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= (value as u128 & ((1u128 << count) - 1)) << self.pos;
+        self.pos += count;
+    }
+}
+
+fn expand_565(c: u16) -> [u32; 3] {
+    let (r, g, b) = ((c >> 11) as u32 & 31, (c >> 5) as u32 & 63, c as u32 & 31);
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+fn decode_color(block: &[u8], texels: &mut Texels, four_color: bool) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (e0, e1) = (expand_565(c0), expand_565(c1));
+    let mut palette = [[0u8; 4]; 4];
+    for c in 0..3 {
+        palette[0][c] = e0[c] as u8;
+        palette[1][c] = e1[c] as u8;
+        if four_color || c0 > c1 {
+            palette[2][c] = ((2 * e0[c] + e1[c] + 1) / 3) as u8;
+            palette[3][c] = ((e0[c] + 2 * e1[c] + 1) / 3) as u8;
+        } else {
+            palette[2][c] = ((e0[c] + e1[c]) / 2) as u8;
+        }
+    }
+    palette[0][3] = 255;
+    palette[1][3] = 255;
+    palette[2][3] = 255;
+    palette[3][3] = if four_color || c0 > c1 { 255 } else { 0 };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (i, texel) in texels.iter_mut().enumerate() {
+        *texel = palette[(indices >> (i * 2)) as usize & 3];
+    }
+}
+
+fn decode_alpha(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let mut palette = [0u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u32) * a0 + i as u32 * a1 + 3) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u32) * a0 + i as u32 * a1 + 2) / 5) as u8;
+        }
+        palette[7] = 255;
+    }
+    let mut bits = [0u8; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    std::array::from_fn(|i| palette[(indices >> (i * 3)) as usize & 7])
+}
+
+pub fn decode_bc1(block: &[u8]) -> Texels {
+    let mut texels = [[0; 4]; 16];
+    decode_color(block, &mut texels, false);
+    texels
+}
+
+pub fn decode_bc2(block: &[u8]) -> Texels {
+    let mut texels = [[0; 4]; 16];
+    decode_color(&block[8..], &mut texels, true);
+    let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+    for (i, texel) in texels.iter_mut().enumerate() {
+        texel[3] = ((alpha >> (i * 4)) & 15) as u8 * 17;
+    }
+    texels
+}
+
+pub fn decode_bc3(block: &[u8]) -> Texels {
+    let mut texels = [[0; 4]; 16];
+    decode_color(&block[8..], &mut texels, true);
+    for (texel, a) in texels.iter_mut().zip(decode_alpha(block)) {
+        texel[3] = a;
+    }
+    texels
+}
+
+pub fn decode_bc4(block: &[u8]) -> Texels {
+    decode_alpha(block).map(|r| [r, 0, 0, 255])
+}
+
+pub fn decode_bc5(block: &[u8]) -> Texels {
+    let (r, g) = (decode_alpha(block), decode_alpha(&block[8..]));
+    std::array::from_fn(|i| [r[i], g[i], 0, 255])
+}
+
+fn unquantize(value: u32, bits: u32) -> u32 {
+    let value = value << (8 - bits);
+    value | (value >> bits)
+}
+
+fn interpolate(e0: u32, e1: u32, weight: u32) -> u8 {
+    (((64 - weight) * e0 + weight * e1 + 32) >> 6) as u8
+}
+
+fn weights(bits: u32) -> &'static [u32] {
+    match bits {
+        2 => &WEIGHTS_2,
+        3 => &WEIGHTS_3,
+        _ => &WEIGHTS_4,
+    }
+}
+
+pub fn decode_bc7(block: &[u8]) -> Texels {
+    let mut r = BitReader::new(block);
+    let Some(mode_index) = (0..8).find(|_| r.read(1) == 1) else {
+        return [[0; 4]; 16];
+    };
+    let mode = &BC7_MODES[mode_index];
+    let partition = r.read(mode.partition_bits) as usize;
+    let rotation = r.read(mode.rotation_bits);
+    let selector = r.read(mode.selector_bits);
+
+    let mut endpoints = [[0u32; 4]; 6];
+    let count = mode.subsets * 2;
+    for c in 0..3 {
+        for e in &mut endpoints[..count] {
+            e[c] = r.read(mode.color_bits);
+        }
+    }
+    for e in &mut endpoints[..count] {
+        e[3] = if mode.alpha_bits > 0 {
+            r.read(mode.alpha_bits)
+        } else {
+            255
+        };
+    }
+
+    let (color_bits, alpha_bits) = if mode.endpoint_pbits || mode.shared_pbits {
+        let pbits: Vec<u32> = if mode.endpoint_pbits {
+            (0..count).map(|_| r.read(1)).collect()
+        } else {
+            (0..mode.subsets)
+                .flat_map(|_| {
+                    let p = r.read(1);
+                    [p, p]
+                })
+                .collect()
+        };
+        for (e, p) in endpoints[..count].iter_mut().zip(pbits) {
+            let channels = if mode.alpha_bits > 0 { 4 } else { 3 };
+            for v in &mut e[..channels] {
+                *v = (*v << 1) | p;
+            }
+        }
+        (mode.color_bits + 1, mode.alpha_bits + 1)
+    } else {
+        (mode.color_bits, mode.alpha_bits)
+    };
+    for e in &mut endpoints[..count] {
+        for v in &mut e[..3] {
+            *v = unquantize(*v, color_bits);
+        }
+        if mode.alpha_bits > 0 {
+            e[3] = unquantize(e[3], alpha_bits);
+        }
+    }
+
+    let subset_of = |texel: usize| match mode.subsets {
+        2 => (PARTITIONS_2[partition] >> texel) as usize & 1,
+        3 => (PARTITIONS_3[partition] >> (texel * 2)) as usize & 3,
+        _ => 0,
+    };
+    let is_anchor = |texel: usize| {
+        texel == 0
+            || match mode.subsets {
+                2 => texel == ANCHORS_2[partition] as usize,
+                3 => {
+                    texel == ANCHORS_3A[partition] as usize
+                        || texel == ANCHORS_3B[partition] as usize
+                }
+                _ => false,
+            }
+    };
+    let mut indices = [0u32; 16];
+    for (texel, index) in indices.iter_mut().enumerate() {
+        *index = r.read(mode.index_bits - is_anchor(texel) as u32);
+    }
+    let mut indices2 = [0u32; 16];
+    if mode.index2_bits > 0 {
+        for (texel, index) in indices2.iter_mut().enumerate() {
+            *index = r.read(mode.index2_bits - (texel == 0) as u32);
+        }
+    }
+
+    std::array::from_fn(|texel| {
+        let subset = subset_of(texel);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        let (color_weight, alpha_weight) = if mode.index2_bits == 0 {
+            let w = weights(mode.index_bits)[indices[texel] as usize];
+            (w, w)
+        } else {
+            let primary = weights(mode.index_bits)[indices[texel] as usize];
+            let secondary = weights(mode.index2_bits)[indices2[texel] as usize];
+            if selector == 0 {
+                (primary, secondary)
+            } else {
+                (secondary, primary)
+            }
+        };
+        let mut out = [0u8; 4];
+        for c in 0..3 {
+            out[c] = interpolate(e0[c], e1[c], color_weight);
+        }
+        out[3] = interpolate(e0[3], e1[3], alpha_weight);
+        if rotation > 0 {
+            out.swap(3, rotation as usize - 1);
+        }
+        out
+    })
+}
+
+// Single-subset mode 6 encoder: principal axis endpoints, then a least-squares refit.
+pub fn encode_bc7(texels: &Texels) -> [u8; 16] {
+    let pixels = texels.map(|t| t.map(|c| c as f32));
+    let mut mean = [0.0f32; 4];
+    for p in &pixels {
+        for c in 0..4 {
+            mean[c] += p[c] / 16.0;
+        }
+    }
+    let mut cov = [[0.0f32; 4]; 4];
+    for p in &pixels {
+        let d: [f32; 4] = std::array::from_fn(|c| p[c] - mean[c]);
+        for i in 0..4 {
+            for j in 0..4 {
+                cov[i][j] += d[i] * d[j];
+            }
+        }
+    }
+    // Start from the channel that varies most: a fixed start such as grey misses alpha-only or
+    // anticorrelated variation, because the covariance maps it to zero.
+    let widest = (0..4)
+        .max_by(|&a, &b| cov[a][a].total_cmp(&cov[b][b]))
+        .unwrap();
+    let mut axis: [f32; 4] = std::array::from_fn(|c| (c == widest) as u32 as f32);
+    for _ in 0..8 {
+        let next: [f32; 4] = std::array::from_fn(|i| (0..4).map(|j| cov[i][j] * axis[j]).sum());
+        let len = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if len < 1e-6 {
+            break;
+        }
+        axis = next.map(|v| v / len);
+    }
+    let project = |p: &[f32; 4]| (0..4).map(|c| (p[c] - mean[c]) * axis[c]).sum::<f32>();
+    let (mut lo, mut hi) = (f32::MAX, f32::MIN);
+    for p in &pixels {
+        let t = project(p);
+        lo = lo.min(t);
+        hi = hi.max(t);
+    }
+    let e0: [f32; 4] = std::array::from_fn(|c| mean[c] + axis[c] * lo);
+    let e1: [f32; 4] = std::array::from_fn(|c| mean[c] + axis[c] * hi);
+
+    let mut best = fit_mode6(&pixels, e0, e1);
+    let weights: [f32; 16] = best.indices.map(|i| WEIGHTS_4[i as usize] as f32 / 64.0);
+    if let Some((r0, r1)) = least_squares(&pixels, &weights) {
+        let refit = fit_mode6(&pixels, r0, r1);
+        if refit.error < best.error {
+            best = refit;
+        }
+    }
+    best.pack()
+}
+
+struct Mode6 {
+    endpoints: [[u32; 4]; 2],
+    pbits: [u32; 2],
+    indices: [u32; 16],
+    error: f32,
+}
+
+impl Mode6 {
+    fn pack(mut self) -> [u8; 16] {
+        if self.indices[0] >= 8 {
+            self.endpoints.swap(0, 1);
+            self.pbits.swap(0, 1);
+            for i in &mut self.indices {
+                *i = 15 - *i;
+            }
+        }
+        let mut w = BitWriter::default();
+        w.write(1 << 6, 7);
+        for c in 0..4 {
+            w.write(self.endpoints[0][c], 7);
+            w.write(self.endpoints[1][c], 7);
+        }
+        w.write(self.pbits[0], 1);
+        w.write(self.pbits[1], 1);
+        for (texel, &index) in self.indices.iter().enumerate() {
+            w.write(index, if texel == 0 { 3 } else { 4 });
+        }
+        w.bits.to_le_bytes()
+    }
+}
+
+fn fit_mode6(pixels: &[[f32; 4]; 16], e0: [f32; 4], e1: [f32; 4]) -> Mode6 {
+    let mut best: Option<Mode6> = None;
+    for (p0, p1) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+        let quantize = |e: [f32; 4], p: u32| {
+            e.map(|v| {
+                ((v.clamp(0.0, 255.0) - p as f32) / 2.0)
+                    .round()
+                    .clamp(0.0, 127.0) as u32
+            })
+        };
+        let endpoints = [quantize(e0, p0), quantize(e1, p1)];
+        let full = |e: [u32; 4], p: u32| e.map(|v| (v << 1) | p);
+        let (f0, f1) = (full(endpoints[0], p0), full(endpoints[1], p1));
+        let palette: [[f32; 4]; 16] = std::array::from_fn(|i| {
+            std::array::from_fn(|c| interpolate(f0[c], f1[c], WEIGHTS_4[i]) as f32)
+        });
+        let axis: [f32; 4] = std::array::from_fn(|c| palette[15][c] - palette[0][c]);
+        let length = axis.iter().map(|v| v * v).sum::<f32>();
+        let mut indices = [0u32; 16];
+        let mut error = 0.0;
+        for (texel, p) in pixels.iter().enumerate() {
+            // The palette is nearly uniform along the axis, so only the projected guess and its
+            // neighbours need checking.
+            let t = if length > 0.0 {
+                (0..4)
+                    .map(|c| (p[c] - palette[0][c]) * axis[c])
+                    .sum::<f32>()
+                    / length
+            } else {
+                0.0
+            };
+            let guess = (t * 15.0).round().clamp(0.0, 15.0) as usize;
+            let (index, e) = (guess.saturating_sub(1)..=(guess + 1).min(15))
+                .map(|i| {
+                    (
+                        i,
+                        (0..4).map(|c| (palette[i][c] - p[c]).powi(2)).sum::<f32>(),
+                    )
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            indices[texel] = index as u32;
+            error += e;
+        }
+        if best.as_ref().is_none_or(|b| error < b.error) {
+            best = Some(Mode6 {
+                endpoints,
+                pbits: [p0, p1],
+                indices,
+                error,
+            });
+        }
+    }
+    best.unwrap()
+}
+
+fn least_squares(pixels: &[[f32; 4]; 16], weights: &[f32; 16]) -> Option<([f32; 4], [f32; 4])> {
+    let (mut aa, mut ab, mut bb) = (0.0, 0.0, 0.0);
+    let (mut ax, mut bx) = ([0.0f32; 4], [0.0f32; 4]);
+    for (p, &w) in pixels.iter().zip(weights) {
+        let (a, b) = (1.0 - w, w);
+        aa += a * a;
+        ab += a * b;
+        bb += b * b;
+        for c in 0..4 {
+            ax[c] += a * p[c];
+            bx[c] += b * p[c];
+        }
+    }
+    let det = aa * bb - ab * ab;
+    if det.abs() < 1e-6 {
+        return None;
+    }
+    let e0 = std::array::from_fn(|c| (ax[c] * bb - bx[c] * ab) / det);
+    let e1 = std::array::from_fn(|c| (bx[c] * aa - ax[c] * ab) / det);
+    Some((e0, e1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Packs (value, bit count) fields LSB-first, the way BC7 blocks are laid out.
+    fn bc7_block(fields: &[(u32, u32)]) -> [u8; 16] {
+        let mut w = BitWriter::default();
+        for &(value, count) in fields {
+            w.write(value, count);
+        }
+        assert_eq!(w.pos, 128);
+        w.bits.to_le_bytes()
+    }
+
+    fn bc1_block(c0: u16, c1: u16, indices: [u32; 16]) -> [u8; 8] {
+        let bits = indices
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &ix)| acc | ix << (i * 2));
+        let mut block = [0u8; 8];
+        block[..2].copy_from_slice(&c0.to_le_bytes());
+        block[2..4].copy_from_slice(&c1.to_le_bytes());
+        block[4..].copy_from_slice(&bits.to_le_bytes());
+        block
+    }
+
+    fn alpha_block(a0: u8, a1: u8, indices: [u64; 16]) -> [u8; 8] {
+        let bits = indices
+            .iter()
+            .enumerate()
+            .fold(0u64, |acc, (i, &ix)| acc | ix << (i * 3));
+        let mut block = [a0, a1, 0, 0, 0, 0, 0, 0];
+        block[2..].copy_from_slice(&bits.to_le_bytes()[..6]);
+        block
+    }
+
+    const CYCLE4: [u32; 16] = [0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3];
+    const CYCLE8: [u64; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 0, 1, 2, 3, 4, 5, 6, 7];
+
+    #[test]
+    fn bc1_four_and_three_color_palettes() {
+        // Red to blue: thirds of 255 are exact, so no rounding convention is involved.
+        let texels = decode_bc1(&bc1_block(0xf800, 0x001f, CYCLE4));
+        assert_eq!(
+            texels[..4],
+            [
+                [255, 0, 0, 255],
+                [0, 0, 255, 255],
+                [170, 0, 85, 255],
+                [85, 0, 170, 255]
+            ]
+        );
+        // c0 <= c1 selects the midpoint and transparent black; red 16 expands to 132.
+        let texels = decode_bc1(&bc1_block(0x0000, 0x8000, CYCLE4));
+        assert_eq!(
+            texels[..4],
+            [
+                [0, 0, 0, 255],
+                [132, 0, 0, 255],
+                [66, 0, 0, 255],
+                [0, 0, 0, 0]
+            ]
+        );
+    }
+
+    #[test]
+    fn bc2_uses_explicit_alpha_and_four_colors() {
+        let mut block = [0u8; 16];
+        let alpha: u64 = (0..16).fold(0, |acc, i| acc | (i as u64) << (i * 4));
+        block[..8].copy_from_slice(&alpha.to_le_bytes());
+        // c0 <= c1 would mean three colors in BC1; BC2 always uses four.
+        block[8..].copy_from_slice(&bc1_block(0x001f, 0xf800, CYCLE4));
+        let texels = decode_bc2(&block);
+        assert_eq!(texels[0], [0, 0, 255, 0]);
+        assert_eq!(texels[3], [170, 0, 85, 51]);
+        assert_eq!(texels[15], [170, 0, 85, 255]);
+    }
+
+    #[test]
+    fn bc4_eight_and_six_value_ramps() {
+        let r: Vec<u8> = decode_bc4(&alpha_block(224, 0, CYCLE8))[..8]
+            .iter()
+            .map(|t| t[0])
+            .collect();
+        assert_eq!(r, [224, 0, 192, 160, 128, 96, 64, 32]);
+        let r: Vec<u8> = decode_bc4(&alpha_block(0, 250, CYCLE8))[..8]
+            .iter()
+            .map(|t| t[0])
+            .collect();
+        assert_eq!(r, [0, 250, 50, 100, 150, 200, 0, 255]);
+        assert_eq!(
+            decode_bc4(&alpha_block(224, 0, CYCLE8))[2],
+            [192, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn bc3_and_bc5_combine_their_halves() {
+        let mut block = [0u8; 16];
+        block[..8].copy_from_slice(&alpha_block(224, 0, CYCLE8));
+        block[8..].copy_from_slice(&bc1_block(0xf800, 0x001f, CYCLE4));
+        let texels = decode_bc3(&block);
+        assert_eq!(texels[2], [170, 0, 85, 192]);
+        assert_eq!(texels[7], [85, 0, 170, 32]);
+
+        block[8..].copy_from_slice(&alpha_block(0, 250, CYCLE8));
+        let texels = decode_bc5(&block);
+        assert_eq!(texels[3], [160, 100, 0, 255]);
+        assert_eq!(texels[6], [64, 0, 0, 255]);
+    }
+
+    #[test]
+    fn bc7_mode6_interpolates_with_spec_weights() {
+        let mut fields = vec![(1 << 6, 7)];
+        // R 0..127, G 64/64, B 0/0, A 127/127, then p-bits 0 and 1.
+        fields.extend([
+            (0, 7),
+            (127, 7),
+            (64, 7),
+            (64, 7),
+            (0, 7),
+            (0, 7),
+            (127, 7),
+            (127, 7),
+        ]);
+        fields.extend([(0, 1), (1, 1)]);
+        fields.push((0, 3));
+        fields.extend((1..16).map(|i| (i, 4)));
+        let texels = decode_bc7(&bc7_block(&fields));
+        let red: Vec<u8> = texels.iter().map(|t| t[0]).collect();
+        assert_eq!(
+            red,
+            [
+                0, 16, 36, 52, 68, 84, 104, 120, 135, 151, 171, 187, 203, 219, 239, 255
+            ]
+        );
+        assert_eq!(texels[0], [0, 128, 0, 254]);
+        assert_eq!(texels[15], [255, 129, 1, 255]);
+    }
+
+    #[test]
+    fn bc7_mode5_rotates_the_separate_alpha_ramp() {
+        // Mode 5, rotation 1 (alpha swaps with red), red 127/127, alpha 0..255.
+        let mut fields = vec![(1 << 5, 6), (1, 2)];
+        fields.extend([
+            (127, 7),
+            (127, 7),
+            (0, 7),
+            (0, 7),
+            (0, 7),
+            (0, 7),
+            (0, 8),
+            (255, 8),
+        ]);
+        fields.push((0, 1));
+        fields.extend((1..16).map(|_| (0, 2)));
+        fields.push((0, 1));
+        fields.extend((1..16).map(|i| (i % 4, 2)));
+        let texels = decode_bc7(&bc7_block(&fields));
+        assert_eq!(
+            texels[..4],
+            [
+                [0, 0, 0, 255],
+                [84, 0, 0, 255],
+                [171, 0, 0, 255],
+                [255, 0, 0, 255]
+            ]
+        );
+    }
+
+    #[test]
+    fn bc7_mode1_splits_texels_by_partition() {
+        // Partition 13 puts the bottom two rows in subset 1, whose anchor is texel 15.
+        let mut fields = vec![(0b10, 2), (13, 6)];
+        fields.extend([(63, 6), (63, 6), (0, 6), (0, 6)]);
+        fields.extend([(0, 6); 4]);
+        fields.extend([(0, 6), (0, 6), (63, 6), (63, 6)]);
+        fields.extend([(1, 1), (1, 1)]);
+        fields.extend((0..16).map(|t| (0, if t == 0 || t == 15 { 2 } else { 3 })));
+        let texels = decode_bc7(&bc7_block(&fields));
+        assert!(texels[..8].iter().all(|&t| t == [255, 2, 2, 255]));
+        assert!(texels[8..].iter().all(|&t| t == [2, 2, 255, 255]));
+    }
+
+    #[test]
+    fn bc7_reserved_mode_decodes_to_transparent_black() {
+        assert_eq!(decode_bc7(&[0; 16]), [[0; 4]; 16]);
+    }
+
+    fn max_error(texels: &Texels) -> u8 {
+        let decoded = decode_bc7(&encode_bc7(texels));
+        let block = decoded.iter().zip(texels);
+        block
+            .flat_map(|(a, b)| (0..4).map(move |c| a[c].abs_diff(b[c])))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn bc7_encoder_stays_within_error_bounds() {
+        // Mode 6 shares one p-bit across an endpoint's channels, so odd and even channels can
+        // be one step off.
+        for color in [
+            [0, 0, 0, 255],
+            [255, 255, 255, 255],
+            [13, 200, 77, 128],
+            [1, 2, 3, 4],
+        ] {
+            assert!(max_error(&[color; 16]) <= 1, "{color:?}");
+        }
+        let two_colors: Texels = std::array::from_fn(|i| {
+            if i % 3 == 0 {
+                [200, 30, 90, 255]
+            } else {
+                [20, 180, 60, 40]
+            }
+        });
+        assert!(max_error(&two_colors) <= 2);
+        let ramp: Texels = std::array::from_fn(|i| {
+            let t = i as u8 * 17;
+            [t, 255 - t, t / 2, 255]
+        });
+        assert!(max_error(&ramp) <= 6);
+        let alpha_ramp: Texels = std::array::from_fn(|i| [90, 90, 90, i as u8 * 16]);
+        assert!(max_error(&alpha_ramp) <= 6);
+        let opposed: Texels = std::array::from_fn(|i| {
+            let t = i as u8 * 17;
+            [t, 255 - t, 0, 255]
+        });
+        assert!(max_error(&opposed) <= 6);
+    }
+}
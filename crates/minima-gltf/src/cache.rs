@@ -0,0 +1,109 @@
+use minima_3d::{GpuTexture, Material};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wgpu::Queue;
+use xxhash_rust::xxh3::xxh3_128;
+
+use crate::texture::{DecodedImage, upload_image};
+
+// glTF images are keyed by their encoded bytes, so the same image shared between documents is
+// uploaded once and unrelated in-memory documents never collide on a name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImageKey {
+    File(PathBuf),
+    Content(u128),
+}
+
+impl ImageKey {
+    pub fn for_bytes(bytes: &[u8]) -> Self {
+        ImageKey::Content(xxh3_128(bytes))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextureKey {
+    pub source: ImageKey,
+    pub srgb: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MaterialKey {
+    pub base_color: Option<TextureKey>,
+    pub emissive: Option<TextureKey>,
+    pub params: Vec<u32>,
+}
+
+#[derive(Default)]
+pub struct TextureCache {
+    textures: HashMap<TextureKey, Arc<GpuTexture>>,
+    materials: HashMap<MaterialKey, Arc<Material>>,
+}
+
+impl TextureCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &Queue,
+        key: TextureKey,
+        decode: impl FnOnce() -> DecodedImage,
+    ) -> Arc<GpuTexture> {
+        self.textures
+            .entry(key)
+            .or_insert_with(|| {
+                let image = decode();
+                Arc::new(GpuTexture::new(upload_image(
+                    device,
+                    queue,
+                    "gltf_texture",
+                    &image,
+                )))
+            })
+            .clone()
+    }
+
+    // Like `texture`, for decodes that can fail; nothing is cached on error.
+    pub fn try_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &Queue,
+        key: TextureKey,
+        decode: impl FnOnce() -> anyhow::Result<DecodedImage>,
+    ) -> anyhow::Result<Arc<GpuTexture>> {
+        if let Some(texture) = self.textures.get(&key) {
+            return Ok(texture.clone());
+        }
+        let image = decode()?;
+        Ok(self.texture(device, queue, key, || image))
+    }
+
+    pub fn material(
+        &mut self,
+        key: MaterialKey,
+        create: impl FnOnce(&mut Self) -> Material,
+    ) -> Arc<Material> {
+        if let Some(material) = self.materials.get(&key) {
+            return material.clone();
+        }
+        let material = Arc::new(create(self));
+        self.materials.insert(key, material.clone());
+        material
+    }
+
+    pub fn texture_count(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn material_count(&self) -> usize {
+        self.materials.len()
+    }
+
+    pub fn purge_unused(&mut self) {
+        self.materials.retain(|_, m| Arc::strong_count(m) > 1);
+        self.textures.retain(|_, t| Arc::strong_count(t) > 1);
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::{Context, Result, bail};
+use wgpu::{AstcChannel, Features, TextureFormat};
+
+use crate::astc;
+use crate::bcn::{self, Texels};
+use crate::etc2;
+use crate::texture::DecodedImage;
+
+pub fn compression_features() -> Features {
+    Features::TEXTURE_COMPRESSION_BC
+        | Features::TEXTURE_COMPRESSION_ETC2
+        | Features::TEXTURE_COMPRESSION_ASTC
+}
+
+pub fn is_format_supported(format: TextureFormat, features: Features) -> bool {
+    features.contains(format.required_features())
+}
+
+pub fn fit_to_device(image: DecodedImage, features: Features) -> Result<DecodedImage> {
+    let (block_width, block_height) = image.format.block_dimensions();
+    let whole_blocks =
+        image.width.is_multiple_of(block_width) && image.height.is_multiple_of(block_height);
+    if whole_blocks && is_format_supported(image.format, features) {
+        Ok(image)
+    } else {
+        decompress(&image)
+    }
+}
+
+type BlockDecoder = Box<dyn Fn(&[u8]) -> Vec<[u8; 4]>>;
+
+pub fn decompress(image: &DecodedImage) -> Result<DecodedImage> {
+    let (block_width, block_height) = image.format.block_dimensions();
+    let decode: BlockDecoder = match image.format {
+        TextureFormat::Astc {
+            channel: AstcChannel::Hdr,
+            ..
+        } => bail!("no CPU decoder for {:?}", image.format),
+        TextureFormat::Astc { .. } => {
+            let srgb = image.format.is_srgb();
+            Box::new(move |block| astc::decode_astc(block, block_width, block_height, srgb))
+        }
+        format => {
+            let decode: fn(&[u8]) -> Texels = match format.remove_srgb_suffix() {
+                TextureFormat::Bc1RgbaUnorm => bcn::decode_bc1,
+                TextureFormat::Bc2RgbaUnorm => bcn::decode_bc2,
+                TextureFormat::Bc3RgbaUnorm => bcn::decode_bc3,
+                TextureFormat::Bc4RUnorm => bcn::decode_bc4,
+                TextureFormat::Bc5RgUnorm => bcn::decode_bc5,
+                TextureFormat::Bc7RgbaUnorm => bcn::decode_bc7,
+                TextureFormat::Etc2Rgb8Unorm => etc2::decode_etc2_rgb,
+                TextureFormat::Etc2Rgb8A1Unorm => etc2::decode_etc2_rgb_a1,
+                TextureFormat::Etc2Rgba8Unorm => etc2::decode_etc2_rgba,
+                TextureFormat::EacR11Unorm => etc2::decode_eac_r11,
+                TextureFormat::EacRg11Unorm => etc2::decode_eac_rg11,
+                format if !format.is_compressed() => return Ok(image.clone()),
+                format => bail!("no CPU decoder for {format:?}"),
+            };
+            Box::new(move |block| decode(block).to_vec())
+        }
+    };
+    let block_size = image.format.block_copy_size(None).unwrap_or(16) as usize;
+    let (block_width, block_height) = (block_width as usize, block_height as usize);
+
+    let mut bytes = Vec::new();
+    let mut offset = 0;
+    for level in 0..image.mip_level_count {
+        let (width, height) = level_size(image, level);
+        let blocks_x = width.div_ceil(block_width);
+        let blocks_y = height.div_ceil(block_height);
+        let len = blocks_x * blocks_y * block_size;
+        let data = image
+            .bytes
+            .get(offset..offset + len)
+            .context("compressed texture data is truncated")?;
+        offset += len;
+
+        let mut pixels = vec![0u8; width * height * 4];
+        for (i, block) in data.chunks_exact(block_size).enumerate() {
+            let (bx, by) = (i % blocks_x * block_width, i / blocks_x * block_height);
+            for (t, texel) in decode(block).iter().enumerate() {
+                let (x, y) = (bx + t % block_width, by + t / block_width);
+                if x < width && y < height {
+                    pixels[(y * width + x) * 4..][..4].copy_from_slice(texel);
+                }
+            }
+        }
+        bytes.extend_from_slice(&pixels);
+    }
+    Ok(DecodedImage {
+        width: image.width,
+        height: image.height,
+        format: if image.format.is_srgb() {
+            TextureFormat::Rgba8UnormSrgb
+        } else {
+            TextureFormat::Rgba8Unorm
+        },
+        mip_level_count: image.mip_level_count,
+        bytes,
+    })
+}
+
+// Only RGBA8 images whose base level is a whole number of blocks can be compressed.
+pub fn compress_bc7(image: &DecodedImage) -> Option<DecodedImage> {
+    let format = match image.format {
+        TextureFormat::Rgba8Unorm => TextureFormat::Bc7RgbaUnorm,
+        TextureFormat::Rgba8UnormSrgb => TextureFormat::Bc7RgbaUnormSrgb,
+        _ => return None,
+    };
+    if !image.width.is_multiple_of(4) || !image.height.is_multiple_of(4) {
+        return None;
+    }
+
+    let mut bytes = Vec::new();
+    let mut offset = 0;
+    for level in 0..image.mip_level_count {
+        let (width, height) = level_size(image, level);
+        let pixels = image.bytes.get(offset..offset + width * height * 4)?;
+        offset += width * height * 4;
+        for by in (0..height).step_by(4) {
+            for bx in (0..width).step_by(4) {
+                let texels: Texels = std::array::from_fn(|t| {
+                    let x = (bx + t % 4).min(width - 1);
+                    let y = (by + t / 4).min(height - 1);
+                    pixels[(y * width + x) * 4..][..4].try_into().unwrap()
+                });
+                bytes.extend_from_slice(&bcn::encode_bc7(&texels));
+            }
+        }
+    }
+    Some(DecodedImage {
+        width: image.width,
+        height: image.height,
+        format,
+        mip_level_count: image.mip_level_count,
+        bytes,
+    })
+}
+
+fn level_size(image: &DecodedImage, level: u32) -> (usize, usize) {
+    (
+        (image.width >> level).max(1) as usize,
+        (image.height >> level).max(1) as usize,
+    )
+}
@@ -0,0 +1,466 @@
+use crate::bcn::Texels;
+
+const MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+const DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+fn bits(word: u64, high: u32, count: u32) -> i32 {
+    ((word >> (high + 1 - count)) & ((1 << count) - 1)) as i32
+}
+
+fn extend4(v: i32) -> i32 {
+    (v << 4) | v
+}
+
+fn extend5(v: i32) -> i32 {
+    (v << 3) | (v >> 2)
+}
+
+fn signed3(v: i32) -> i32 {
+    (v << 29) >> 29
+}
+
+fn clamp(v: i32) -> u8 {
+    v.clamp(0, 255) as u8
+}
+
+fn offset(color: [i32; 3], d: i32) -> [u8; 4] {
+    [
+        clamp(color[0] + d),
+        clamp(color[1] + d),
+        clamp(color[2] + d),
+        255,
+    ]
+}
+
+// ETC texels are stored column by column; the result is row-major like the BCn decoders.
+fn selector(word: u64, texel: usize) -> usize {
+    let column_major = (texel % 4) * 4 + texel / 4;
+    let msb = (word >> (16 + column_major)) & 1;
+    let lsb = (word >> column_major) & 1;
+    (msb * 2 + lsb) as usize
+}
+
+fn decode_rgb(block: &[u8], punchthrough: bool) -> Texels {
+    let word = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let flag = (word >> 33) & 1 == 1;
+    let opaque = !punchthrough || flag;
+    let differential = punchthrough || flag;
+
+    if differential {
+        let r = bits(word, 63, 5) + signed3(bits(word, 58, 3));
+        let g = bits(word, 55, 5) + signed3(bits(word, 50, 3));
+        let b = bits(word, 47, 5) + signed3(bits(word, 42, 3));
+        if !(0..32).contains(&r) {
+            return decode_t(word, opaque);
+        }
+        if !(0..32).contains(&g) {
+            return decode_h(word, opaque);
+        }
+        if !(0..32).contains(&b) {
+            return decode_planar(word);
+        }
+    }
+
+    let (base0, base1) = if differential {
+        let c0 = [bits(word, 63, 5), bits(word, 55, 5), bits(word, 47, 5)];
+        let d = [
+            signed3(bits(word, 58, 3)),
+            signed3(bits(word, 50, 3)),
+            signed3(bits(word, 42, 3)),
+        ];
+        (
+            c0.map(extend5),
+            std::array::from_fn(|c| extend5(c0[c] + d[c])),
+        )
+    } else {
+        (
+            [bits(word, 63, 4), bits(word, 55, 4), bits(word, 47, 4)].map(extend4),
+            [bits(word, 59, 4), bits(word, 51, 4), bits(word, 43, 4)].map(extend4),
+        )
+    };
+    let tables = [bits(word, 39, 3) as usize, bits(word, 36, 3) as usize];
+    let flip = (word >> 32) & 1 == 1;
+    std::array::from_fn(|texel| {
+        let (x, y) = (texel % 4, texel / 4);
+        let second = if flip { y >= 2 } else { x >= 2 };
+        let s = selector(word, texel);
+        if !opaque && s == 2 {
+            return [0; 4];
+        }
+        let [small, large] = MODIFIERS[tables[second as usize]];
+        let modifier = match s {
+            0 if !opaque => 0,
+            0 => small,
+            1 => large,
+            2 => -small,
+            _ => -large,
+        };
+        offset(if second { base1 } else { base0 }, modifier)
+    })
+}
+
+fn paint(word: u64, opaque: bool, colors: [[u8; 4]; 4]) -> Texels {
+    std::array::from_fn(|texel| {
+        let s = selector(word, texel);
+        if !opaque && s == 2 { [0; 4] } else { colors[s] }
+    })
+}
+
+fn decode_t(word: u64, opaque: bool) -> Texels {
+    let c0 = [
+        (bits(word, 60, 2) << 2) | bits(word, 57, 2),
+        bits(word, 55, 4),
+        bits(word, 51, 4),
+    ]
+    .map(extend4);
+    let c1 = [bits(word, 47, 4), bits(word, 43, 4), bits(word, 39, 4)].map(extend4);
+    let d = DISTANCES[((bits(word, 35, 2) << 1) | bits(word, 32, 1)) as usize];
+    paint(
+        word,
+        opaque,
+        [offset(c0, 0), offset(c1, d), offset(c1, 0), offset(c1, -d)],
+    )
+}
+
+fn decode_h(word: u64, opaque: bool) -> Texels {
+    let c0 = [
+        bits(word, 62, 4),
+        (bits(word, 58, 3) << 1) | bits(word, 52, 1),
+        (bits(word, 51, 1) << 3) | bits(word, 49, 3),
+    ];
+    let c1 = [bits(word, 46, 4), bits(word, 42, 4), bits(word, 38, 4)];
+    let order = (c0[0] << 8 | c0[1] << 4 | c0[2]) >= (c1[0] << 8 | c1[1] << 4 | c1[2]);
+    let index = (bits(word, 34, 1) << 2) | (bits(word, 32, 1) << 1) | order as i32;
+    let d = DISTANCES[index as usize];
+    let (c0, c1) = (c0.map(extend4), c1.map(extend4));
+    paint(
+        word,
+        opaque,
+        [offset(c0, d), offset(c0, -d), offset(c1, d), offset(c1, -d)],
+    )
+}
+
+fn decode_planar(word: u64) -> Texels {
+    let extend6 = |v: i32| (v << 2) | (v >> 4);
+    let extend7 = |v: i32| (v << 1) | (v >> 6);
+    let o = [
+        extend6(bits(word, 62, 6)),
+        extend7((bits(word, 56, 1) << 6) | bits(word, 54, 6)),
+        extend6((bits(word, 48, 1) << 5) | (bits(word, 44, 2) << 3) | bits(word, 41, 3)),
+    ];
+    let h = [
+        extend6((bits(word, 38, 5) << 1) | bits(word, 32, 1)),
+        extend7(bits(word, 31, 7)),
+        extend6(bits(word, 24, 6)),
+    ];
+    let v = [
+        extend6(bits(word, 18, 6)),
+        extend7(bits(word, 12, 7)),
+        extend6(bits(word, 5, 6)),
+    ];
+    std::array::from_fn(|texel| {
+        let (x, y) = ((texel % 4) as i32, (texel / 4) as i32);
+        let c = |i: usize| clamp((x * (h[i] - o[i]) + y * (v[i] - o[i]) + 4 * o[i] + 2) >> 2);
+        [c(0), c(1), c(2), 255]
+    })
+}
+
+fn decode_eac(block: &[u8], eleven_bit: bool) -> [u8; 16] {
+    let word = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let base = bits(word, 63, 8);
+    let multiplier = bits(word, 55, 4);
+    let table = EAC_MODIFIERS[bits(word, 51, 4) as usize];
+    std::array::from_fn(|texel| {
+        let column_major = (texel % 4) * 4 + texel / 4;
+        let modifier = table[((word >> (45 - 3 * column_major)) & 7) as usize];
+        if eleven_bit {
+            let scale = if multiplier == 0 { 1 } else { multiplier * 8 };
+            let v = (base * 8 + 4 + modifier * scale).clamp(0, 2047);
+            ((v * 255 + 1023) / 2047) as u8
+        } else {
+            clamp(base + modifier * multiplier)
+        }
+    })
+}
+
+pub fn decode_etc2_rgb(block: &[u8]) -> Texels {
+    decode_rgb(block, false)
+}
+
+pub fn decode_etc2_rgb_a1(block: &[u8]) -> Texels {
+    decode_rgb(block, true)
+}
+
+pub fn decode_etc2_rgba(block: &[u8]) -> Texels {
+    let mut texels = decode_rgb(&block[8..], false);
+    for (texel, a) in texels.iter_mut().zip(decode_eac(block, false)) {
+        texel[3] = a;
+    }
+    texels
+}
+
+pub fn decode_eac_r11(block: &[u8]) -> Texels {
+    decode_eac(block, true).map(|r| [r, 0, 0, 255])
+}
+
+pub fn decode_eac_rg11(block: &[u8]) -> Texels {
+    let (r, g) = (decode_eac(block, true), decode_eac(&block[8..], true));
+    std::array::from_fn(|i| [r[i], g[i], 0, 255])
+}
+
+// Searches every table and multiplier, with bases around the one that centres
+// the table on the block's alpha range, and keeps the lowest squared error.
+pub fn encode_eac_alpha(alpha: &[u8; 16]) -> [u8; 8] {
+    let mut values: Vec<(i32, i32)> = Vec::new();
+    for &a in alpha {
+        match values.iter_mut().find(|(v, _)| *v == a as i32) {
+            Some((_, count)) => *count += 1,
+            None => values.push((a as i32, 1)),
+        }
+    }
+    let lo = values.iter().map(|v| v.0).min().unwrap_or(0);
+    let hi = values.iter().map(|v| v.0).max().unwrap_or(0);
+    let nearest = |base: i32, multiplier: i32, table: &[i32; 8], a: i32| {
+        (0..8)
+            .map(|k| {
+                let d = clamp(base + table[k] * multiplier) as i32 - a;
+                (d * d, k as u64)
+            })
+            .min()
+            .unwrap()
+    };
+
+    let mut best = (i32::MAX, 0, 0, 0);
+    'search: for (t, table) in EAC_MODIFIERS.iter().enumerate() {
+        for multiplier in 1..16 {
+            let centre = (lo + hi - (table[3] + table[7]) * multiplier) / 2;
+            for base in (centre - 3..=centre + 3).map(|b| b.clamp(0, 255)) {
+                let error: i32 = values
+                    .iter()
+                    .map(|&(a, count)| nearest(base, multiplier, table, a).0 * count)
+                    .sum();
+                if error < best.0 {
+                    best = (error, base, multiplier, t);
+                    if error == 0 {
+                        break 'search;
+                    }
+                }
+            }
+        }
+    }
+
+    let (_, base, multiplier, t) = best;
+    let mut word = (base as u64) << 56 | (multiplier as u64) << 52 | (t as u64) << 48;
+    for (texel, &a) in alpha.iter().enumerate() {
+        let column_major = (texel % 4) * 4 + texel / 4;
+        let index = nearest(base, multiplier, &EAC_MODIFIERS[t], a as i32).1;
+        word |= index << (45 - 3 * column_major);
+    }
+    word.to_be_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds a big-endian ETC2 word from (value, highest bit, bit count) fields.
+    fn block(fields: &[(u64, u32, u32)]) -> [u8; 8] {
+        fields
+            .iter()
+            .fold(0u64, |word, &(value, high, count)| {
+                word | (value & ((1 << count) - 1)) << (high + 1 - count)
+            })
+            .to_be_bytes()
+    }
+
+    // Selector bits for the texel at (x, y), which ETC stores column by column.
+    fn msb(x: u32, y: u32) -> (u64, u32, u32) {
+        (1, 16 + x * 4 + y, 1)
+    }
+
+    fn lsb(x: u32, y: u32) -> (u64, u32, u32) {
+        (1, x * 4 + y, 1)
+    }
+
+    fn rgb(texel: [u8; 4]) -> [u8; 3] {
+        [texel[0], texel[1], texel[2]]
+    }
+
+    #[test]
+    fn individual_mode_subblocks_and_selectors() {
+        // R 8 | 4, G 8 | 8, B 8 | 8, table 0 (2, 8) for both halves.
+        let base = [
+            (8, 63, 4),
+            (4, 59, 4),
+            (8, 55, 4),
+            (8, 51, 4),
+            (8, 47, 4),
+            (8, 43, 4),
+        ];
+        let mut fields = base.to_vec();
+        fields.extend([lsb(1, 0), msb(0, 1), lsb(1, 1), msb(1, 1)]);
+        let texels = decode_etc2_rgb(&block(&fields));
+        assert_eq!(rgb(texels[0]), [138, 138, 138]);
+        assert_eq!(rgb(texels[1]), [144, 144, 144]);
+        assert_eq!(rgb(texels[4]), [134, 134, 134]);
+        assert_eq!(rgb(texels[5]), [128, 128, 128]);
+        assert_eq!(rgb(texels[2]), [70, 138, 138]);
+        assert_eq!(texels[15][3], 255);
+
+        // With the flip bit the second subblock is the bottom half instead.
+        let mut fields = base.to_vec();
+        fields.push((1, 32, 1));
+        let texels = decode_etc2_rgb(&block(&fields));
+        assert_eq!(rgb(texels[2]), [138, 138, 138]);
+        assert_eq!(rgb(texels[8]), [70, 138, 138]);
+    }
+
+    #[test]
+    fn differential_mode_applies_the_signed_delta() {
+        // R 16 with delta -1, G and B 16, table 1 (5, 17).
+        let fields = [
+            (16, 63, 5),
+            (0b111, 58, 3),
+            (16, 55, 5),
+            (16, 47, 5),
+            (1, 39, 3),
+            (1, 36, 3),
+            (1, 33, 1),
+        ];
+        let texels = decode_etc2_rgb(&block(&fields));
+        assert_eq!(rgb(texels[0]), [137, 137, 137]);
+        assert_eq!(rgb(texels[3]), [128, 137, 137]);
+    }
+
+    #[test]
+    fn t_mode_paints_from_two_base_colors() {
+        // Red overflows (0 + -2), selecting T mode; colors (2, 4, 0) and (8, 8, 8), distance 3.
+        let mut fields = vec![
+            (1, 58, 1),
+            (2, 57, 2),
+            (4, 55, 4),
+            (8, 47, 4),
+            (8, 43, 4),
+            (8, 39, 4),
+            (1, 33, 1),
+        ];
+        fields.extend([lsb(1, 0), msb(0, 1), lsb(1, 1), msb(1, 1)]);
+        let texels = decode_etc2_rgb(&block(&fields));
+        assert_eq!(rgb(texels[0]), [34, 68, 0]);
+        assert_eq!(rgb(texels[1]), [139, 139, 139]);
+        assert_eq!(rgb(texels[4]), [136, 136, 136]);
+        assert_eq!(rgb(texels[5]), [133, 133, 133]);
+    }
+
+    #[test]
+    fn planar_mode_interpolates_across_the_block() {
+        // Blue overflows (0 + -4), selecting planar mode. O = H = V = (32, 64, 32), except that
+        // H red is 63 in the second block.
+        let planar = |rh: u64| {
+            block(&[
+                (32, 62, 6),
+                (1, 56, 1),
+                (0, 54, 6),
+                (1, 48, 1),
+                (1, 42, 1),
+                (1, 33, 1),
+                (rh >> 1, 38, 5),
+                (rh & 1, 32, 1),
+                (64, 31, 7),
+                (32, 24, 6),
+                (32, 18, 6),
+                (64, 12, 7),
+                (32, 5, 6),
+            ])
+        };
+        let texels = decode_etc2_rgb(&planar(32));
+        assert!(texels.iter().all(|&t| t == [130, 129, 130, 255]));
+
+        let red: Vec<u8> = decode_etc2_rgb(&planar(63))[..4]
+            .iter()
+            .map(|t| t[0])
+            .collect();
+        assert_eq!(red, [130, 161, 193, 224]);
+    }
+
+    #[test]
+    fn punchthrough_selector_two_is_transparent() {
+        // Differential layout with the opaque bit cleared.
+        let fields = [(16, 63, 5), (16, 55, 5), (16, 47, 5), msb(0, 0)];
+        let texels = decode_etc2_rgb_a1(&block(&fields));
+        assert_eq!(texels[0], [0, 0, 0, 0]);
+        // Selector 0 carries no modifier in punchthrough mode.
+        assert_eq!(texels[1], [132, 132, 132, 255]);
+    }
+
+    fn eac(base: u64, multiplier: u64, table: u64, odd: u64) -> [u8; 8] {
+        let mut word = base << 56 | multiplier << 52 | table << 48;
+        for k in 0..16 {
+            let index = if k == 1 { odd } else { 4 };
+            word |= index << (45 - 3 * k);
+        }
+        word.to_be_bytes()
+    }
+
+    #[test]
+    fn eac_alpha_and_r11_use_the_modifier_tables() {
+        let mut block = [0u8; 16];
+        block[..8].copy_from_slice(&eac(128, 1, 0, 0));
+        let texels = decode_etc2_rgba(&block);
+        // Column-major: the second stored index is texel (0, 1).
+        assert_eq!(texels[0][3], 130);
+        assert_eq!(texels[4][3], 125);
+        assert_eq!(texels[1][3], 130);
+
+        let r = decode_eac_r11(&eac(128, 1, 0, 0));
+        assert_eq!([r[0][0], r[4][0]], [130, 125]);
+        // Table 13 index 3 is -10, scaled by 8 * 3 in 11-bit space: 1028 - 240 = 788. The green
+        // block has multiplier 0, which scales by one: 4 + 14 = 18, and 4 + 2 = 6 elsewhere.
+        let rg = decode_eac_rg11(&[eac(128, 3, 13, 3), eac(0, 0, 0, 7)].concat());
+        assert_eq!(rg[4], [98, 2, 0, 255]);
+        assert_eq!(rg[0], [128, 1, 0, 255]);
+    }
+
+    #[test]
+    fn eac_alpha_encoder_round_trips_through_the_decoder() {
+        let flat = [77u8; 16];
+        assert_eq!(decode_eac(&encode_eac_alpha(&flat), false), flat);
+
+        // Four levels, as an ETC1S alpha block produces, land within a step of the input.
+        let levels = [20u8, 90, 150, 240];
+        let alpha: [u8; 16] = std::array::from_fn(|i| levels[(i * 7) % 4]);
+        let decoded = decode_eac(&encode_eac_alpha(&alpha), false);
+        for (a, d) in alpha.iter().zip(decoded) {
+            assert!(a.abs_diff(d) <= 8, "{a} vs {d}");
+        }
+    }
+}
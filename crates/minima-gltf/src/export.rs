@@ -0,0 +1,845 @@
+use anyhow::{Context, Result, bail};
+use glam::{Mat4, Vec3};
+use half::f16;
+use minima_3d::camera::CameraProjection;
+use minima_3d::light::LightKind;
+use minima_3d::material::{MaterialRegistry, TextureTransform};
+use minima_3d::mesh::CpuMesh;
+use minima_3d::model::{Material, Model};
+use minima_3d::texture::GpuTexture;
+use minima_scene::Scene;
+use serde_json::{Map, Value, json};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use wgpu::{Device, Queue, TextureFormat};
+
+use crate::compressed::decompress;
+use crate::texture::{DecodedImage, linear_to_srgb, read_texture};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+pub fn export_scene(
+    device: &Device,
+    queue: &Queue,
+    registry: &MaterialRegistry,
+    scene: &Scene,
+    path: &Path,
+) -> Result<()> {
+    let mut exporter = Exporter::new(device, queue, registry);
+    let mut roots = Vec::new();
+    for (ix, instance) in scene.models.iter().enumerate() {
+        roots.push(exporter.add_model(&instance.model, instance.transform, ix)?);
+    }
+    for object in &scene.cameras {
+        roots.push(exporter.add_camera(&object.camera, object.transform));
+    }
+    for object in &scene.lights {
+        roots.push(exporter.add_light(&object.light, object.transform));
+    }
+
+    let binary = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("glb"));
+    let bin_name = path.with_extension("bin");
+    let mut buffer = json!({ "byteLength": exporter.bin.len() });
+    if !binary {
+        let uri = bin_name
+            .file_name()
+            .context("export path has no file name")?
+            .to_string_lossy();
+        buffer["uri"] = Value::String(urlencoding::encode(&uri).into_owned());
+    }
+
+    let mut root = json!({
+        "asset": { "version": "2.0", "generator": "minima" },
+        "scene": 0,
+        "scenes": [{ "nodes": roots }],
+        "nodes": exporter.nodes,
+        "meshes": exporter.meshes,
+        "materials": exporter.materials,
+        "textures": exporter.textures,
+        "images": exporter.images,
+        "skins": exporter.skins,
+        "cameras": exporter.cameras,
+        "accessors": exporter.accessors,
+        "bufferViews": exporter.buffer_views,
+        "buffers": [buffer],
+    });
+    if !exporter.textures.is_empty() {
+        root["samplers"] =
+            json!([{ "magFilter": 9729, "minFilter": 9729, "wrapS": 10497, "wrapT": 10497 }]);
+    }
+    if !exporter.lights.is_empty() {
+        root["extensions"] = json!({ "KHR_lights_punctual": { "lights": exporter.lights } });
+    }
+    if !exporter.extensions_used.is_empty() {
+        root["extensionsUsed"] = json!(exporter.extensions_used);
+    }
+    if let Some(object) = root.as_object_mut() {
+        object.retain(|_, v| !v.as_array().is_some_and(Vec::is_empty));
+    }
+
+    let json = serde_json::to_vec(&root)?;
+    if binary {
+        let glb = gltf::binary::Glb {
+            header: gltf::binary::Header {
+                magic: *b"glTF",
+                version: 2,
+                length: 0,
+            },
+            json: Cow::Owned(json),
+            bin: Some(Cow::Owned(exporter.bin)),
+        };
+        let file =
+            std::fs::File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        glb.to_writer(std::io::BufWriter::new(file))?;
+    } else {
+        std::fs::write(path, json).with_context(|| format!("writing {}", path.display()))?;
+        std::fs::write(&bin_name, &exporter.bin)
+            .with_context(|| format!("writing {}", bin_name.display()))?;
+    }
+    Ok(())
+}
+
+struct Exporter<'a> {
+    device: &'a Device,
+    queue: &'a Queue,
+    registry: &'a MaterialRegistry,
+    bin: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    nodes: Vec<Value>,
+    meshes: Vec<Value>,
+    materials: Vec<Value>,
+    textures: Vec<Value>,
+    images: Vec<Value>,
+    skins: Vec<Value>,
+    cameras: Vec<Value>,
+    lights: Vec<Value>,
+    material_ids: HashMap<*const Material, usize>,
+    texture_ids: HashMap<(*const GpuTexture, bool), usize>,
+    extensions_used: BTreeSet<&'static str>,
+}
+
+impl<'a> Exporter<'a> {
+    fn new(device: &'a Device, queue: &'a Queue, registry: &'a MaterialRegistry) -> Self {
+        Self {
+            device,
+            queue,
+            registry,
+            bin: Vec::new(),
+            buffer_views: Vec::new(),
+            accessors: Vec::new(),
+            nodes: Vec::new(),
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            textures: Vec::new(),
+            images: Vec::new(),
+            skins: Vec::new(),
+            cameras: Vec::new(),
+            lights: Vec::new(),
+            material_ids: HashMap::new(),
+            texture_ids: HashMap::new(),
+            extensions_used: BTreeSet::new(),
+        }
+    }
+
+    fn add_model(&mut self, model: &Model, transform: Mat4, ix: usize) -> Result<usize> {
+        let root = self.nodes.len();
+        self.nodes.push(json!({
+            "name": format!("model_{ix}"),
+            "matrix": transform.to_cols_array(),
+        }));
+        let base = self.nodes.len();
+
+        let mut primitives: Vec<Vec<Value>> = vec![Vec::new(); model.nodes.len() + 1];
+        let mut weights: Vec<Vec<f32>> = vec![Vec::new(); model.nodes.len() + 1];
+        for mesh in &model.meshes {
+            let cpu = mesh
+                .cpu
+                .as_ref()
+                .context("mesh has no retained CPU data; load with LoadOptions::retain_cpu_data")?;
+            let slot = mesh.node.unwrap_or(model.nodes.len());
+            let material = self.add_material(&model.materials[mesh.material_id])?;
+            primitives[slot].push(self.add_primitive(cpu, material));
+            if let Some(morph) = &mesh.morph {
+                weights[slot].clone_from(&morph.default_weights);
+            }
+        }
+
+        for (node_ix, node) in model.nodes.iter().enumerate() {
+            let mut value = json!({
+                "translation": node.transform.translation.to_array(),
+                "rotation": node.transform.rotation.to_array(),
+                "scale": node.transform.scale.to_array(),
+            });
+            if let Some(name) = &node.name {
+                value["name"] = json!(name);
+            }
+            if !node.children.is_empty() {
+                value["children"] =
+                    json!(node.children.iter().map(|c| c + base).collect::<Vec<_>>());
+            }
+            if let Some(skin) = node.skin {
+                value["skin"] = json!(self.skins.len() + skin);
+            }
+            if !node.weights.is_empty() {
+                value["weights"] = json!(node.weights);
+            }
+            if let Some(mesh) = self.add_mesh(
+                std::mem::take(&mut primitives[node_ix]),
+                std::mem::take(&mut weights[node_ix]),
+            ) {
+                value["mesh"] = json!(mesh);
+            }
+            self.nodes.push(value);
+        }
+
+        let mut children: Vec<usize> = model.roots.iter().map(|r| r + base).collect();
+        if let Some(mesh) = self.add_mesh(
+            primitives.pop().unwrap_or_default(),
+            weights.pop().unwrap_or_default(),
+        ) {
+            children.push(self.nodes.len());
+            self.nodes.push(json!({ "mesh": mesh }));
+        }
+        if !children.is_empty() {
+            self.nodes[root]["children"] = json!(children);
+        }
+
+        for skin in &model.skins {
+            let mut value = json!({
+                "joints": skin.joints.iter().map(|j| j + base).collect::<Vec<_>>(),
+            });
+            if !skin.inverse_bind_matrices.is_empty() {
+                let matrices: Vec<[f32; 16]> = skin
+                    .inverse_bind_matrices
+                    .iter()
+                    .map(Mat4::to_cols_array)
+                    .collect();
+                value["inverseBindMatrices"] =
+                    json!(self.push_accessor(&matrices, FLOAT, "MAT4", None, None));
+            }
+            if let Some(skeleton) = skin.skeleton {
+                value["skeleton"] = json!(skeleton + base);
+            }
+            if let Some(name) = &skin.name {
+                value["name"] = json!(name);
+            }
+            self.skins.push(value);
+        }
+        Ok(root)
+    }
+
+    fn add_mesh(&mut self, primitives: Vec<Value>, weights: Vec<f32>) -> Option<usize> {
+        if primitives.is_empty() {
+            return None;
+        }
+        let mut mesh = json!({ "primitives": primitives });
+        if !weights.is_empty() {
+            mesh["weights"] = json!(weights);
+        }
+        self.meshes.push(mesh);
+        Some(self.meshes.len() - 1)
+    }
+
+    fn add_primitive(&mut self, cpu: &CpuMesh, material: usize) -> Value {
+        let v = &cpu.vertices;
+        let mut attributes = Map::new();
+        let bounds = (
+            cpu.bounds.min.to_array().to_vec(),
+            cpu.bounds.max.to_array().to_vec(),
+        );
+        attributes.insert(
+            "POSITION".into(),
+            json!(self.push_accessor(
+                &v.positions,
+                FLOAT,
+                "VEC3",
+                Some(bounds),
+                Some(ARRAY_BUFFER)
+            )),
+        );
+        let mut attribute = |name: &str, accessor: Option<usize>| {
+            if let Some(accessor) = accessor {
+                attributes.insert(name.into(), json!(accessor));
+            }
+        };
+        attribute(
+            "NORMAL",
+            v.normals
+                .as_ref()
+                .map(|n| self.push_accessor(n, FLOAT, "VEC3", None, Some(ARRAY_BUFFER))),
+        );
+        attribute(
+            "TANGENT",
+            v.tangents
+                .as_ref()
+                .map(|t| self.push_accessor(t, FLOAT, "VEC4", None, Some(ARRAY_BUFFER))),
+        );
+        attribute(
+            "TEXCOORD_0",
+            v.uv0
+                .as_ref()
+                .map(|t| self.push_accessor(t, FLOAT, "VEC2", None, Some(ARRAY_BUFFER))),
+        );
+        attribute(
+            "TEXCOORD_1",
+            v.uv1
+                .as_ref()
+                .map(|t| self.push_accessor(t, FLOAT, "VEC2", None, Some(ARRAY_BUFFER))),
+        );
+        attribute(
+            "COLOR_0",
+            v.colors
+                .as_ref()
+                .map(|c| self.push_accessor(c, FLOAT, "VEC4", None, Some(ARRAY_BUFFER))),
+        );
+        attribute(
+            "JOINTS_0",
+            v.joints
+                .as_ref()
+                .map(|j| self.push_accessor(j, UNSIGNED_SHORT, "VEC4", None, Some(ARRAY_BUFFER))),
+        );
+        attribute(
+            "WEIGHTS_0",
+            v.weights
+                .as_ref()
+                .map(|w| self.push_accessor(w, FLOAT, "VEC4", None, Some(ARRAY_BUFFER))),
+        );
+
+        let indices = self.push_accessor(
+            &cpu.indices,
+            UNSIGNED_INT,
+            "SCALAR",
+            None,
+            Some(ELEMENT_ARRAY_BUFFER),
+        );
+        let mut primitive = json!({
+            "attributes": attributes,
+            "indices": indices,
+            "material": material,
+        });
+        if !cpu.morph_targets.is_empty() {
+            let targets: Vec<Value> = cpu
+                .morph_targets
+                .iter()
+                .map(|target| {
+                    let bounds = position_bounds(&target.positions);
+                    let mut value = json!({
+                        "POSITION": self.push_accessor(
+                            &target.positions,
+                            FLOAT,
+                            "VEC3",
+                            Some(bounds),
+                            Some(ARRAY_BUFFER),
+                        ),
+                    });
+                    if let Some(normals) = &target.normals {
+                        value["NORMAL"] = json!(self.push_accessor(
+                            normals,
+                            FLOAT,
+                            "VEC3",
+                            None,
+                            Some(ARRAY_BUFFER)
+                        ));
+                    }
+                    value
+                })
+                .collect();
+            primitive["targets"] = json!(targets);
+        }
+        primitive
+    }
+
+    fn add_material(&mut self, material: &Arc<Material>) -> Result<usize> {
+        if let Some(&ix) = self.material_ids.get(&Arc::as_ptr(material)) {
+            return Ok(ix);
+        }
+        let params = &material.params;
+        let mut pbr = json!({
+            "baseColorFactor": params.base_color_factor.to_array(),
+            "metallicFactor": params.metallic_factor,
+            "roughnessFactor": params.roughness_factor,
+        });
+        if let Some(info) =
+            self.texture_info(&material.base_color, true, &params.base_color_transform)?
+        {
+            pbr["baseColorTexture"] = info;
+        }
+        let mut value = json!({ "pbrMetallicRoughness": pbr });
+        let mut extensions = Map::new();
+        if params.emissive_factor != Vec3::ZERO {
+            value["emissiveFactor"] = json!(params.emissive_factor.to_array());
+            if let Some(info) =
+                self.texture_info(&material.emissive, true, &params.emissive_transform)?
+            {
+                value["emissiveTexture"] = info;
+            }
+            if params.emissive_strength != 1.0 {
+                self.extensions_used
+                    .insert("KHR_materials_emissive_strength");
+                extensions.insert(
+                    "KHR_materials_emissive_strength".into(),
+                    json!({ "emissiveStrength": params.emissive_strength }),
+                );
+            }
+        }
+        if params.unlit {
+            self.extensions_used.insert("KHR_materials_unlit");
+            extensions.insert("KHR_materials_unlit".into(), json!({}));
+        }
+        if params.clearcoat_factor > 0.0 {
+            self.extensions_used.insert("KHR_materials_clearcoat");
+            extensions.insert(
+                "KHR_materials_clearcoat".into(),
+                json!({
+                    "clearcoatFactor": params.clearcoat_factor,
+                    "clearcoatRoughnessFactor": params.clearcoat_roughness,
+                }),
+            );
+        }
+        if params.transmission_factor > 0.0 {
+            self.extensions_used.insert("KHR_materials_transmission");
+            extensions.insert(
+                "KHR_materials_transmission".into(),
+                json!({ "transmissionFactor": params.transmission_factor }),
+            );
+        }
+        if !extensions.is_empty() {
+            value["extensions"] = Value::Object(extensions);
+        }
+        self.materials.push(value);
+        let ix = self.materials.len() - 1;
+        self.material_ids.insert(Arc::as_ptr(material), ix);
+        Ok(ix)
+    }
+
+    fn texture_info(
+        &mut self,
+        texture: &Arc<GpuTexture>,
+        srgb: bool,
+        transform: &TextureTransform,
+    ) -> Result<Option<Value>> {
+        let fallbacks = &self.registry.fallbacks;
+        if Arc::ptr_eq(texture, &fallbacks.white) || Arc::ptr_eq(texture, &fallbacks.black) {
+            return Ok(None);
+        }
+        let key = (Arc::as_ptr(texture), srgb);
+        let index = match self.texture_ids.get(&key) {
+            Some(&ix) => ix,
+            None => {
+                let png = read_texture_png(self.device, self.queue, texture, srgb)?;
+                let view = self.push_view(&png, None);
+                self.images
+                    .push(json!({ "bufferView": view, "mimeType": "image/png" }));
+                self.textures
+                    .push(json!({ "source": self.images.len() - 1, "sampler": 0 }));
+                let ix = self.textures.len() - 1;
+                self.texture_ids.insert(key, ix);
+                ix
+            }
+        };
+        let mut info = json!({ "index": index });
+        if transform.tex_coord != 0 {
+            info["texCoord"] = json!(transform.tex_coord);
+        }
+        let identity = TextureTransform {
+            tex_coord: transform.tex_coord,
+            ..TextureTransform::IDENTITY
+        };
+        if *transform != identity {
+            self.extensions_used.insert("KHR_texture_transform");
+            info["extensions"] = json!({
+                "KHR_texture_transform": {
+                    "offset": transform.offset.to_array(),
+                    "rotation": transform.rotation,
+                    "scale": transform.scale.to_array(),
+                }
+            });
+        }
+        Ok(Some(info))
+    }
+
+    fn add_camera(&mut self, camera: &minima_3d::Camera, transform: Mat4) -> usize {
+        let mut value = match camera.projection {
+            CameraProjection::Perspective {
+                yfov,
+                aspect_ratio,
+                znear,
+                zfar,
+            } => {
+                let mut p = json!({ "yfov": yfov, "znear": znear });
+                if let Some(aspect_ratio) = aspect_ratio {
+                    p["aspectRatio"] = json!(aspect_ratio);
+                }
+                if let Some(zfar) = zfar {
+                    p["zfar"] = json!(zfar);
+                }
+                json!({ "type": "perspective", "perspective": p })
+            }
+            CameraProjection::Orthographic {
+                xmag,
+                ymag,
+                znear,
+                zfar,
+            } => json!({
+                "type": "orthographic",
+                "orthographic": { "xmag": xmag, "ymag": ymag, "znear": znear, "zfar": zfar },
+            }),
+        };
+        if let Some(name) = &camera.name {
+            value["name"] = json!(name);
+        }
+        self.cameras.push(value);
+        self.nodes.push(json!({
+            "camera": self.cameras.len() - 1,
+            "matrix": transform.to_cols_array(),
+        }));
+        self.nodes.len() - 1
+    }
+
+    fn add_light(&mut self, light: &minima_3d::Light, transform: Mat4) -> usize {
+        let mut value = json!({
+            "color": light.color.to_array(),
+            "intensity": light.intensity,
+        });
+        match light.kind {
+            LightKind::Directional => value["type"] = json!("directional"),
+            LightKind::Point => value["type"] = json!("point"),
+            LightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => {
+                value["type"] = json!("spot");
+                value["spot"] = json!({
+                    "innerConeAngle": inner_cone_angle,
+                    "outerConeAngle": outer_cone_angle,
+                });
+            }
+        }
+        if let Some(range) = light.range {
+            value["range"] = json!(range);
+        }
+        if let Some(name) = &light.name {
+            value["name"] = json!(name);
+        }
+        self.extensions_used.insert("KHR_lights_punctual");
+        self.lights.push(value);
+        self.nodes.push(json!({
+            "matrix": transform.to_cols_array(),
+            "extensions": { "KHR_lights_punctual": { "light": self.lights.len() - 1 } },
+        }));
+        self.nodes.len() - 1
+    }
+
+    fn push_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        while !self.bin.len().is_multiple_of(4) {
+            self.bin.push(0);
+        }
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.bin.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.bin.extend_from_slice(bytes);
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    fn push_accessor<T: bytemuck::Pod>(
+        &mut self,
+        data: &[T],
+        component_type: u32,
+        ty: &str,
+        bounds: Option<(Vec<f32>, Vec<f32>)>,
+        target: Option<u32>,
+    ) -> usize {
+        let view = self.push_view(bytemuck::cast_slice(data), target);
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": component_type,
+            "count": data.len(),
+            "type": ty,
+        });
+        if let Some((min, max)) = bounds {
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+}
+
+fn position_bounds(positions: &[[f32; 3]]) -> (Vec<f32>, Vec<f32>) {
+    let mut min = Vec3::splat(f32::MAX);
+    let mut max = Vec3::splat(f32::MIN);
+    for p in positions {
+        min = min.min(Vec3::from(*p));
+        max = max.max(Vec3::from(*p));
+    }
+    if positions.is_empty() {
+        (min, max) = (Vec3::ZERO, Vec3::ZERO);
+    }
+    (min.to_array().to_vec(), max.to_array().to_vec())
+}
+
+fn read_texture_png(
+    device: &Device,
+    queue: &Queue,
+    texture: &GpuTexture,
+    srgb: bool,
+) -> Result<Vec<u8>> {
+    encode_png(&read_texture(device, queue, &texture.texture)?, srgb)
+}
+
+// Block-compressed textures decode to RGBA8; either way only the base level is exported.
+fn encode_png(image: &DecodedImage, srgb: bool) -> Result<Vec<u8>> {
+    let image = decompress(image)?;
+    let texels = (image.width * image.height) as usize;
+    let base_level = |bytes_per_texel: usize| {
+        image
+            .bytes
+            .get(..texels * bytes_per_texel)
+            .context("texture readback is truncated")
+    };
+    let pixels = match image.format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => base_level(4)?.to_vec(),
+        TextureFormat::Rgba16Float => base_level(8)?
+            .chunks_exact(2)
+            .enumerate()
+            .map(|(i, c)| {
+                let mut v = f16::from_le_bytes([c[0], c[1]]).to_f32();
+                if srgb && i % 4 != 3 {
+                    v = linear_to_srgb(v);
+                }
+                (v.clamp(0.0, 1.0) * 255.0).round() as u8
+            })
+            .collect(),
+        format => bail!("cannot export texture format {format:?}"),
+    };
+    let image = image::RgbaImage::from_raw(image.width, image.height, pixels)
+        .context("texture readback size mismatch")?;
+    let mut png = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::TextureCache;
+    use crate::compressed::compress_bc7;
+    use crate::loader::{LoadOptions, load_gltf_model, load_gltf_model_from_slice};
+    use crate::test_util;
+    use glam::{Quat, Vec4};
+    use minima_3d::create_bind_group_layouts;
+
+    const FIXTURE: &str = r#"{
+  "asset": {"version": "2.0"},
+  "scene": 0,
+  "scenes": [{"nodes": [0]}],
+  "nodes": [
+    {"name": "root", "translation": [1, 2, 3], "children": [1]},
+    {"name": "child", "rotation": [0, 0.70710677, 0, 0.70710677], "scale": [2, 2, 2], "mesh": 0}
+  ],
+  "meshes": [{"primitives": [{
+    "attributes": {"POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2},
+    "indices": 3,
+    "material": 0
+  }]}],
+  "materials": [{
+    "pbrMetallicRoughness": {
+      "baseColorFactor": [0.5, 0.25, 1, 1],
+      "metallicFactor": 0.25,
+      "roughnessFactor": 0.75,
+      "baseColorTexture": {"index": 0}
+    },
+    "emissiveFactor": [1, 0.5, 0]
+  }],
+  "textures": [{"source": 0}],
+  "images": [{"uri": "albedo.png"}],
+  "buffers": [{"uri": "mesh.bin", "byteLength": 140}],
+  "bufferViews": [
+    {"buffer": 0, "byteOffset": 0, "byteLength": 48},
+    {"buffer": 0, "byteOffset": 48, "byteLength": 48},
+    {"buffer": 0, "byteOffset": 96, "byteLength": 32},
+    {"buffer": 0, "byteOffset": 128, "byteLength": 12}
+  ],
+  "accessors": [
+    {"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+     "min": [0, 0, 0], "max": [1, 1, 0]},
+    {"bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC3"},
+    {"bufferView": 2, "componentType": 5126, "count": 4, "type": "VEC2"},
+    {"bufferView": 3, "componentType": 5123, "count": 6, "type": "SCALAR"}
+  ]
+}"#;
+
+    fn mesh_bin() -> Vec<u8> {
+        let positions = [
+            [0.0f32, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 1.0, 0.0],
+        ];
+        let normals = [[0.0f32, 0.0, 1.0]; 4];
+        let uvs = [[0.0f32, 1.0], [1.0, 1.0], [0.0, 0.0], [1.0, 0.0]];
+        let indices = [0u16, 1, 2, 2, 1, 3];
+        let mut bin = Vec::new();
+        bin.extend_from_slice(bytemuck::cast_slice(&positions));
+        bin.extend_from_slice(bytemuck::cast_slice(&normals));
+        bin.extend_from_slice(bytemuck::cast_slice(&uvs));
+        bin.extend_from_slice(bytemuck::cast_slice(&indices));
+        bin
+    }
+
+    // Colors along one line, which a single BC7 endpoint pair can represent.
+    fn gradient(x: u32, y: u32) -> [u8; 4] {
+        let t = x + y;
+        [(t * 15) as u8, (t * 7) as u8, 128, 255]
+    }
+
+    #[test]
+    fn export_then_reimport_preserves_meshes_materials_and_nodes() {
+        let Some((device, queue)) = test_util::gpu() else {
+            return;
+        };
+        let layouts = create_bind_group_layouts(&device);
+        let registry = MaterialRegistry::new(&device, &queue, &layouts.material_bgl);
+        let albedo = test_util::png(4, 4, gradient);
+        let bin = mesh_bin();
+        let resolver = |uri: &str| {
+            Ok(match uri {
+                "mesh.bin" => bin.clone(),
+                _ => albedo.clone(),
+            })
+        };
+        let source = Arc::new(
+            pollster::block_on(load_gltf_model_from_slice(
+                &device,
+                &queue,
+                &registry,
+                &mut TextureCache::new(),
+                Path::new("fixture.gltf"),
+                FIXTURE.as_bytes(),
+                &resolver,
+                &LoadOptions::default(),
+            ))
+            .unwrap(),
+        );
+
+        let dir = std::env::temp_dir().join(format!("minima-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in ["scene.glb", "scene.gltf"] {
+            let path = dir.join(file);
+            let mut scene = Scene::new();
+            scene.add_model(source.clone(), Mat4::IDENTITY);
+            export_scene(&device, &queue, &registry, &scene, &path).unwrap();
+            let copy = pollster::block_on(load_gltf_model(
+                &device,
+                &queue,
+                &registry,
+                &mut TextureCache::new(),
+                &path,
+                &LoadOptions::default(),
+            ))
+            .unwrap();
+
+            // The export wraps the model in one extra root node.
+            assert_eq!(copy.roots, [0]);
+            assert_eq!(copy.nodes.len(), source.nodes.len() + 1);
+            for (a, b) in source.nodes.iter().zip(&copy.nodes[1..]) {
+                assert_eq!(a.name, b.name);
+                assert_eq!(
+                    a.children.iter().map(|c| c + 1).collect::<Vec<_>>(),
+                    b.children
+                );
+                assert!(
+                    a.transform
+                        .translation
+                        .abs_diff_eq(b.transform.translation, 1e-6)
+                );
+                assert!(a.transform.rotation.abs_diff_eq(b.transform.rotation, 1e-6));
+                assert!(a.transform.scale.abs_diff_eq(b.transform.scale, 1e-6));
+            }
+            assert!(
+                copy.nodes[2]
+                    .transform
+                    .rotation
+                    .abs_diff_eq(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2), 1e-6)
+            );
+
+            assert_eq!(copy.meshes.len(), 1);
+            let (a, b) = (&source.meshes[0], &copy.meshes[0]);
+            assert_eq!(b.node, a.node.map(|n| n + 1));
+            let (a_cpu, b_cpu) = (a.cpu.as_ref().unwrap(), b.cpu.as_ref().unwrap());
+            assert_eq!(a_cpu.indices, b_cpu.indices);
+            assert_eq!(a_cpu.vertices.positions, b_cpu.vertices.positions);
+            assert_eq!(a_cpu.vertices.normals, b_cpu.vertices.normals);
+            assert_eq!(a_cpu.vertices.uv0, b_cpu.vertices.uv0);
+
+            let (a, b) = (&source.materials[0], &copy.materials[0]);
+            assert_eq!(b.params.base_color_factor, Vec4::new(0.5, 0.25, 1.0, 1.0));
+            assert_eq!(b.params.metallic_factor, 0.25);
+            assert_eq!(b.params.roughness_factor, 0.75);
+            assert_eq!(a.params, b.params);
+            let pixels = |t: &GpuTexture| read_texture(&device, &queue, &t.texture).unwrap().bytes;
+            assert_eq!(pixels(&a.base_color), pixels(&b.base_color));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn png_export_decodes_compressed_base_level() {
+        // 8x8 base plus 4x4, 2x2 and 1x1 mips, BC7 compressed like a cooked texture.
+        let mut bytes = Vec::new();
+        for size in [8u32, 4, 2, 1] {
+            for y in 0..size {
+                for x in 0..size {
+                    bytes.extend_from_slice(&gradient(x * 8 / size, y * 8 / size));
+                }
+            }
+        }
+        let image = DecodedImage {
+            width: 8,
+            height: 8,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 4,
+            bytes,
+        };
+        let bc7 = compress_bc7(&image).unwrap();
+        let png = encode_png(&bc7, true).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (8, 8));
+        for (x, y, px) in decoded.enumerate_pixels() {
+            for (got, want) in px.0.iter().zip(gradient(x, y)) {
+                assert!(got.abs_diff(want) <= 8, "({x}, {y}): {:?}", px.0);
+            }
+        }
+    }
+
+    #[test]
+    fn png_export_encodes_float_textures_as_srgb() {
+        let texel = [0.5f32, 0.0, 1.0, 0.5].map(|v| f16::from_f32(v).to_le_bytes());
+        let image = DecodedImage {
+            width: 1,
+            height: 1,
+            format: TextureFormat::Rgba16Float,
+            mip_level_count: 1,
+            bytes: texel.concat(),
+        };
+        let decoded = image::load_from_memory(&encode_png(&image, true).unwrap())
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(decoded.get_pixel(0, 0).0, [188, 0, 255, 128]);
+    }
+}
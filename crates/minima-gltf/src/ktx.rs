@@ -0,0 +1,145 @@
+use anyhow::{Context, Result, bail};
+use ktx2::{DfdBlockBasic, Format, Reader, SupercompressionScheme};
+use std::io::Read;
+use wgpu::{AstcBlock, AstcChannel, Features, TextureDimension, TextureFormat};
+
+use crate::basisu;
+use crate::compressed::fit_to_device;
+use crate::texture::DecodedImage;
+
+const MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+// Every ASTC block size, in the order of KTX2's VkFormat codes.
+pub const ASTC_BLOCKS: [AstcBlock; 14] = [
+    AstcBlock::B4x4,
+    AstcBlock::B5x4,
+    AstcBlock::B5x5,
+    AstcBlock::B6x5,
+    AstcBlock::B6x6,
+    AstcBlock::B8x5,
+    AstcBlock::B8x6,
+    AstcBlock::B8x8,
+    AstcBlock::B10x5,
+    AstcBlock::B10x6,
+    AstcBlock::B10x8,
+    AstcBlock::B10x10,
+    AstcBlock::B12x10,
+    AstcBlock::B12x12,
+];
+
+pub fn is_ktx2(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+pub fn load_ktx2(bytes: &[u8], features: Features) -> Result<DecodedImage> {
+    let reader = Reader::new(bytes).context("parsing KTX2 header")?;
+    let header = reader.header();
+    if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+        bail!("only single 2D KTX2 images are supported");
+    }
+    let mut levels = Vec::new();
+    for bytes in reader.levels() {
+        levels.push(match header.supercompression_scheme {
+            None | Some(SupercompressionScheme::BasisLZ) => bytes.data.to_vec(),
+            Some(SupercompressionScheme::Zstandard) => {
+                let mut out = Vec::with_capacity(bytes.uncompressed_byte_length as usize);
+                ruzstd::decoding::StreamingDecoder::new(bytes.data)
+                    .context("reading zstd level")?
+                    .read_to_end(&mut out)?;
+                out
+            }
+            Some(scheme) => bail!("unsupported KTX2 supercompression {scheme:?}"),
+        });
+    }
+
+    let Some(format) = header.format else {
+        // Basis Universal payloads carry no format; the DFD names the codec.
+        let dfd = reader
+            .dfd_blocks()
+            .find_map(|block| DfdBlockBasic::parse(block.data).ok())
+            .context("KTX2 image has no format")?;
+        let image = basisu::transcode(
+            header.pixel_width,
+            header.pixel_height.max(1),
+            &dfd,
+            reader.supercompression_global_data(),
+            &levels,
+            features,
+        )?;
+        return fit_to_device(image, features);
+    };
+    let format =
+        texture_format(format).with_context(|| format!("unsupported KTX2 format {format:?}"))?;
+
+    let size = wgpu::Extent3d {
+        width: header.pixel_width,
+        height: header.pixel_height.max(1),
+        depth_or_array_layers: 1,
+    };
+    let block_size = format.block_copy_size(None).unwrap_or(16);
+    let (block_width, block_height) = format.block_dimensions();
+    for (level, level_bytes) in levels.iter().enumerate() {
+        let extent = size
+            .mip_level_size(level as u32, TextureDimension::D2)
+            .physical_size(format);
+        let expected =
+            (extent.width / block_width * extent.height / block_height * block_size) as usize;
+        if level_bytes.len() != expected {
+            bail!(
+                "KTX2 level {level} has {} bytes, expected {expected}",
+                level_bytes.len()
+            );
+        }
+    }
+
+    fit_to_device(
+        DecodedImage {
+            width: size.width,
+            height: size.height,
+            format,
+            mip_level_count: levels.len() as u32,
+            bytes: levels.concat(),
+        },
+        features,
+    )
+}
+
+fn texture_format(format: Format) -> Option<TextureFormat> {
+    let astc_range = Format::ASTC_4x4_UNORM_BLOCK.value()..=Format::ASTC_12x12_SRGB_BLOCK.value();
+    if astc_range.contains(&format.value()) {
+        let ix = format.value() - Format::ASTC_4x4_UNORM_BLOCK.value();
+        return Some(TextureFormat::Astc {
+            block: ASTC_BLOCKS[ix as usize / 2],
+            channel: if ix.is_multiple_of(2) {
+                AstcChannel::Unorm
+            } else {
+                AstcChannel::UnormSrgb
+            },
+        });
+    }
+    Some(match format {
+        Format::R8G8B8A8_UNORM => TextureFormat::Rgba8Unorm,
+        Format::R8G8B8A8_SRGB => TextureFormat::Rgba8UnormSrgb,
+        Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGBA_UNORM_BLOCK => TextureFormat::Bc1RgbaUnorm,
+        Format::BC1_RGB_SRGB_BLOCK | Format::BC1_RGBA_SRGB_BLOCK => TextureFormat::Bc1RgbaUnormSrgb,
+        Format::BC2_UNORM_BLOCK => TextureFormat::Bc2RgbaUnorm,
+        Format::BC2_SRGB_BLOCK => TextureFormat::Bc2RgbaUnormSrgb,
+        Format::BC3_UNORM_BLOCK => TextureFormat::Bc3RgbaUnorm,
+        Format::BC3_SRGB_BLOCK => TextureFormat::Bc3RgbaUnormSrgb,
+        Format::BC4_UNORM_BLOCK => TextureFormat::Bc4RUnorm,
+        Format::BC5_UNORM_BLOCK => TextureFormat::Bc5RgUnorm,
+        Format::BC7_UNORM_BLOCK => TextureFormat::Bc7RgbaUnorm,
+        Format::BC7_SRGB_BLOCK => TextureFormat::Bc7RgbaUnormSrgb,
+        Format::ETC2_R8G8B8_UNORM_BLOCK => TextureFormat::Etc2Rgb8Unorm,
+        Format::ETC2_R8G8B8_SRGB_BLOCK => TextureFormat::Etc2Rgb8UnormSrgb,
+        Format::ETC2_R8G8B8A1_UNORM_BLOCK => TextureFormat::Etc2Rgb8A1Unorm,
+        Format::ETC2_R8G8B8A1_SRGB_BLOCK => TextureFormat::Etc2Rgb8A1UnormSrgb,
+        Format::ETC2_R8G8B8A8_UNORM_BLOCK => TextureFormat::Etc2Rgba8Unorm,
+        Format::ETC2_R8G8B8A8_SRGB_BLOCK => TextureFormat::Etc2Rgba8UnormSrgb,
+        Format::EAC_R11_UNORM_BLOCK => TextureFormat::EacR11Unorm,
+        Format::EAC_R11G11_UNORM_BLOCK => TextureFormat::EacRg11Unorm,
+        _ => return None,
+    })
+}
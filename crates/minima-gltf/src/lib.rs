@@ -1,3 +1,29 @@
+mod animation;
+mod astc;
+mod basisu;
+mod bcn;
+pub mod cache;
+pub mod compressed;
+mod etc2;
+mod export;
+mod ktx;
 mod loader;
+#[cfg(test)]
+mod test_util;
+pub mod texture;
 
-pub use loader::load_gltf_model;
+pub use animation::clips_from_gltf;
+pub use cache::{ImageKey, MaterialKey, TextureCache, TextureKey};
+pub use compressed::{
+    compress_bc7, compression_features, decompress, fit_to_device, is_format_supported,
+};
+pub use export::export_scene;
+pub use ktx::{ASTC_BLOCKS, is_ktx2, load_ktx2};
+pub use loader::{
+    LoadOptions, UriResolver, file_resolver, load_gltf_model, load_gltf_model_from_reader,
+    load_gltf_model_from_slice,
+};
+pub use texture::{
+    DecodedImage, SourceImage, TextureSlot, decode_image, decode_source, linear_to_srgb,
+    load_image, load_source_image, read_texture, srgb_to_linear, upload_image,
+};
@@ -1,265 +1,1016 @@
-use anyhow::Result;
-use minima_3d::model::{GpuMesh, Material, Model, Vertex};
+use anyhow::{Context, Result, bail};
+use minima_3d::bounds::Aabb;
+use minima_3d::camera::{Camera, CameraProjection};
+use minima_3d::light::{Light, LightKind};
+use minima_3d::lod::{CpuLod, LodLevel, MeshLod, generate_lods};
+use minima_3d::material::{MaterialParams, MaterialRegistry, TextureTransform};
+use minima_3d::mesh::{CpuMesh, create_index_buffer};
+use minima_3d::model::{GpuMesh, Material, Model, recommended_xform};
+use minima_3d::morph::{GpuMorphTargets, MorphTarget};
+use minima_3d::node::{Node, Transform};
+use minima_3d::optimize::optimize_mesh;
+use minima_3d::skin::Skin;
+use minima_3d::vertex::{VertexFormat, VertexStreams};
+use std::io::Read;
 use std::path::Path;
-use wgpu::{
-    BindGroupEntry, BindGroupLayout, BindingResource, Queue, SamplerDescriptor, TextureDescriptor,
-    TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor,
-    util::{DeviceExt, TextureDataOrder},
-};
+use std::sync::Arc;
+use wgpu::{Queue, util::DeviceExt};
+
+use crate::animation::clips_from_gltf;
+use crate::cache::{ImageKey, MaterialKey, TextureCache, TextureKey};
+use crate::texture::{SourceImage, TextureSlot, decode_source, load_source_image};
+
+const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "KHR_lights_punctual",
+    "KHR_texture_transform",
+    "KHR_materials_emissive_strength",
+    "KHR_materials_unlit",
+    "KHR_materials_clearcoat",
+    "KHR_materials_transmission",
+    "KHR_texture_basisu",
+    "MSFT_lod",
+];
+
+pub type UriResolver<'a> = dyn Fn(&str) -> Result<Vec<u8>> + 'a;
+
+type TextureSource<'a> = Option<(TextureKey, &'a SourceImage)>;
+
+#[derive(Clone, Debug)]
+pub struct LoadOptions {
+    pub retain_cpu_data: bool,
+    pub optimize_meshes: bool,
+    pub lod_levels: Vec<LodLevel>,
+}
+
+// Optimizing and LOD generation are import-time costs, so direct loads skip them; the asset
+// pipeline turns them on when cooking and stores the result.
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            retain_cpu_data: true,
+            optimize_meshes: false,
+            lod_levels: Vec::new(),
+        }
+    }
+}
+
+pub fn file_resolver(base: &Path) -> impl Fn(&str) -> Result<Vec<u8>> + '_ {
+    move |uri| {
+        let path = base.join(&*urlencoding::decode(uri)?);
+        std::fs::read(&path).with_context(|| format!("reading {}", path.display()))
+    }
+}
 
 pub async fn load_gltf_model(
     device: &wgpu::Device,
     queue: &Queue,
-    material_bgl: &BindGroupLayout,
+    registry: &MaterialRegistry,
+    cache: &mut TextureCache,
     path: &Path,
+    options: &LoadOptions,
+) -> Result<Model> {
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    load_gltf_model_from_slice(
+        device,
+        queue,
+        registry,
+        cache,
+        path,
+        &bytes,
+        &file_resolver(base),
+        options,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn load_gltf_model_from_reader(
+    device: &wgpu::Device,
+    queue: &Queue,
+    registry: &MaterialRegistry,
+    cache: &mut TextureCache,
+    name: &Path,
+    mut reader: impl Read,
+    resolver: &UriResolver<'_>,
+    options: &LoadOptions,
 ) -> Result<Model> {
-    let (doc, buffers, images) = gltf::import(path)?;
-    let mut materials = Vec::<Material>::new();
-    if doc.materials().len() == 0 {
-        materials.push(make_white_material(device, material_bgl));
-    } else {
-        for m in doc.materials() {
-            let pbr = m.pbr_metallic_roughness();
-            let img = pbr
-                .base_color_texture()
-                .and_then(|t| images.get(t.texture().source().index()));
-            materials.push(make_texture_material(device, queue, material_bgl, img));
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("reading {}", name.display()))?;
+    load_gltf_model_from_slice(
+        device, queue, registry, cache, name, &bytes, resolver, options,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn load_gltf_model_from_slice(
+    device: &wgpu::Device,
+    queue: &Queue,
+    registry: &MaterialRegistry,
+    cache: &mut TextureCache,
+    name: &Path,
+    bytes: &[u8],
+    resolver: &UriResolver<'_>,
+    options: &LoadOptions,
+) -> Result<Model> {
+    let (doc, buffers, images) = import(name, bytes, resolver, device.features())?;
+    let mut materials = Vec::<Arc<Material>>::new();
+    for m in doc.materials() {
+        let pbr = m.pbr_metallic_roughness();
+        let texture = |info: &gltf::texture::Info, slot: TextureSlot| {
+            let source = texture_image(&doc, &info.texture(), &images)?;
+            let (image_key, image) = images.get(source.index())?.as_ref()?;
+            let key = TextureKey {
+                source: image_key.clone(),
+                srgb: slot.is_srgb(),
+            };
+            Some((key, image))
+        };
+        let base_color_info = pbr.base_color_texture();
+        let emissive_info = m.emissive_texture();
+        let base_color = base_color_info
+            .as_ref()
+            .and_then(|t| texture(t, TextureSlot::BaseColor));
+        let emissive = emissive_info
+            .as_ref()
+            .and_then(|t| texture(t, TextureSlot::Emissive));
+
+        let clearcoat = m.extension_value("KHR_materials_clearcoat");
+        let clearcoat_value = |name: &str| {
+            clearcoat
+                .and_then(|c| c.get(name))
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0) as f32
+        };
+        let params = MaterialParams {
+            base_color_factor: pbr.base_color_factor().into(),
+            base_color_transform: base_color_info
+                .as_ref()
+                .map(texture_transform)
+                .unwrap_or_default(),
+            metallic_factor: pbr.metallic_factor(),
+            roughness_factor: pbr.roughness_factor(),
+            emissive_factor: m.emissive_factor().into(),
+            emissive_strength: m.emissive_strength().unwrap_or(1.0),
+            emissive_transform: emissive_info
+                .as_ref()
+                .map(texture_transform)
+                .unwrap_or_default(),
+            unlit: m.unlit(),
+            clearcoat_factor: clearcoat_value("clearcoatFactor"),
+            clearcoat_roughness: clearcoat_value("clearcoatRoughnessFactor"),
+            transmission_factor: m
+                .transmission()
+                .map(|t| t.transmission_factor())
+                .unwrap_or(0.0),
+        };
+
+        let key = MaterialKey {
+            base_color: base_color.as_ref().map(|(k, _)| k.clone()),
+            emissive: emissive.as_ref().map(|(k, _)| k.clone()),
+            params: params.cache_key(),
+        };
+        materials.push(cache.material(key, |cache| {
+            make_texture_material(device, queue, registry, cache, base_color, emissive, params)
+        }));
+    }
+    let mut nodes: Vec<Node> = doc
+        .nodes()
+        .map(|n| {
+            let (translation, rotation, scale) = n.transform().decomposed();
+            Node {
+                name: n.name().map(str::to_owned),
+                parent: None,
+                children: n.children().map(|c| c.index()).collect(),
+                transform: Transform {
+                    translation: translation.into(),
+                    rotation: glam::Quat::from_array(rotation),
+                    scale: scale.into(),
+                },
+                skin: n.skin().map(|s| s.index()),
+                camera: n.camera().map(|c| c.index()),
+                light: n.light().map(|l| l.index()),
+                weights: n
+                    .weights()
+                    .or_else(|| n.mesh().and_then(|m| m.weights()))
+                    .map(<[f32]>::to_vec)
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+    for ix in 0..nodes.len() {
+        for child in nodes[ix].children.clone() {
+            nodes[child].parent = Some(ix);
         }
     }
-    let mut meshes = Vec::<GpuMesh>::new();
-    let mut min_v = glam::vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY);
-    let mut max_v = glam::vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
-
-    for scene in doc.scenes() {
-        for node in scene.nodes() {
-            if let Some(mesh) = node.mesh() {
-                for prim in mesh.primitives() {
-                    use gltf::mesh::Mode;
-                    assert!(
-                        matches!(prim.mode(), Mode::Triangles),
-                        "Only triangles supported"
-                    );
-
-                    let reader = prim.reader(|buf| Some(&buffers[buf.index()].0));
-
-                    let positions = reader
-                        .read_positions()
-                        .expect("POSITION missing")
-                        .map(|p| [p[0], p[1], p[2]]);
-                    let normals = reader
-                        .read_normals()
-                        .map(|it| it.map(|n| [n[0], n[1], n[2]]));
-                    let uvs = reader
-                        .read_tex_coords(0)
-                        .map(|tc| tc.into_f32().map(|t| [t[0], t[1]]));
-
-                    let mut verts = Vec::<Vertex>::new();
-                    match (normals, uvs) {
-                        (Some(ns), Some(uvs)) => {
-                            for ((p, n), uv) in positions.zip(ns).zip(uvs) {
-                                min_v = min_v.min(glam::Vec3::from(p));
-                                max_v = max_v.max(glam::Vec3::from(p));
-                                verts.push(Vertex { pos: p, nrm: n, uv });
-                            }
-                        }
-                        (Some(ns), None) => {
-                            for (p, n) in positions.zip(ns) {
-                                min_v = min_v.min(glam::Vec3::from(p));
-                                max_v = max_v.max(glam::Vec3::from(p));
-                                verts.push(Vertex {
-                                    pos: p,
-                                    nrm: n,
-                                    uv: [0.0, 0.0],
-                                });
-                            }
-                        }
-                        (None, Some(uvs)) => {
-                            for (p, uv) in positions.zip(uvs) {
-                                min_v = min_v.min(glam::Vec3::from(p));
-                                max_v = max_v.max(glam::Vec3::from(p));
-                                verts.push(Vertex {
-                                    pos: p,
-                                    nrm: [0.0, 1.0, 0.0],
-                                    uv,
-                                });
-                            }
-                        }
-                        (None, None) => {
-                            for p in positions {
-                                min_v = min_v.min(glam::Vec3::from(p));
-                                max_v = max_v.max(glam::Vec3::from(p));
-                                verts.push(Vertex {
-                                    pos: p,
-                                    nrm: [0.0, 1.0, 0.0],
-                                    uv: [0.0, 0.0],
-                                });
-                            }
-                        }
+
+    let skins: Vec<Skin> = doc
+        .skins()
+        .map(|skin| {
+            let reader = skin.reader(|buf| Some(&buffers[buf.index()].0));
+            Skin {
+                name: skin.name().map(str::to_owned),
+                joints: skin.joints().map(|j| j.index()).collect(),
+                inverse_bind_matrices: reader
+                    .read_inverse_bind_matrices()
+                    .map(|it| it.map(|m| glam::Mat4::from_cols_array_2d(&m)).collect())
+                    .unwrap_or_default(),
+                skeleton: skin.skeleton().map(|n| n.index()),
+            }
+        })
+        .collect();
+
+    let cameras: Vec<Camera> = doc
+        .cameras()
+        .map(|camera| Camera {
+            name: camera.name().map(str::to_owned),
+            projection: match camera.projection() {
+                gltf::camera::Projection::Perspective(p) => CameraProjection::Perspective {
+                    yfov: p.yfov(),
+                    aspect_ratio: p.aspect_ratio(),
+                    znear: p.znear(),
+                    zfar: p.zfar(),
+                },
+                gltf::camera::Projection::Orthographic(o) => CameraProjection::Orthographic {
+                    xmag: o.xmag(),
+                    ymag: o.ymag(),
+                    znear: o.znear(),
+                    zfar: o.zfar(),
+                },
+            },
+        })
+        .collect();
+
+    let lights: Vec<Light> = doc
+        .lights()
+        .map(|lights| {
+            lights
+                .map(|light| {
+                    use gltf::khr_lights_punctual::Kind;
+                    Light {
+                        name: light.name().map(str::to_owned),
+                        kind: match light.kind() {
+                            Kind::Directional => LightKind::Directional,
+                            Kind::Point => LightKind::Point,
+                            Kind::Spot {
+                                inner_cone_angle,
+                                outer_cone_angle,
+                            } => LightKind::Spot {
+                                inner_cone_angle,
+                                outer_cone_angle,
+                            },
+                        },
+                        color: light.color().into(),
+                        intensity: light.intensity(),
+                        range: light.range(),
                     }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
 
-                    let indices: Vec<u32> = reader
-                        .read_indices()
-                        .map(|r| r.into_u32().collect())
-                        .unwrap_or_else(|| (0..verts.len() as u32).collect());
-
-                    let vbuf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                        label: Some("mesh_vbuf"),
-                        contents: bytemuck::cast_slice(&verts),
-                        usage: wgpu::BufferUsages::VERTEX,
-                    });
-                    let ibuf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                        label: Some("mesh_ibuf"),
-                        contents: bytemuck::cast_slice(&indices),
-                        usage: wgpu::BufferUsages::INDEX,
-                    });
-
-                    let mat_ix = prim.material().index().unwrap_or(0);
-                    meshes.push(GpuMesh {
-                        vbuf,
-                        ibuf,
-                        index_count: indices.len() as u32,
-                        material_id: mat_ix,
-                    });
+    let roots: Vec<usize> = doc
+        .default_scene()
+        .or_else(|| doc.scenes().next())
+        .map(|scene| scene.nodes().map(|n| n.index()).collect())
+        .unwrap_or_default();
+
+    let mut default_material_ix = None;
+    let mut meshes = Vec::<GpuMesh>::new();
+    let mut bounds = Aabb::EMPTY;
+
+    let gltf_nodes: Vec<gltf::Node> = doc.nodes().collect();
+    let mut stack: Vec<(usize, glam::Mat4)> =
+        roots.iter().map(|&r| (r, glam::Mat4::IDENTITY)).collect();
+    while let Some((node_ix, parent)) = stack.pop() {
+        let node = &gltf_nodes[node_ix];
+        let world = parent * nodes[node_ix].transform.to_matrix();
+        for &child in &nodes[node_ix].children {
+            stack.push((child, world));
+        }
+        let Some(mesh) = node.mesh() else {
+            continue;
+        };
+        let skin_ix = node.skin().map(|s| s.index());
+        let lod_meshes = msft_lods(node, &gltf_nodes);
+        for prim in mesh.primitives() {
+            let reader = prim.reader(|buf| Some(&buffers[buf.index()].0));
+            let (streams, indices) = read_primitive(&prim, &buffers, skin_ix.is_some())
+                .with_context(|| format!("mesh {} primitive {}", mesh.index(), prim.index()))?;
+            let mesh_bounds = Aabb::from_points(&streams.positions);
+
+            let morph_targets: Vec<MorphTarget> = reader
+                .read_morph_targets()
+                .map(|(positions, normals, _)| MorphTarget {
+                    positions: positions.map(|it| it.collect()).unwrap_or_default(),
+                    normals: normals.map(|it| it.collect()),
+                })
+                .collect();
+            let mut mesh_bounds = mesh_bounds;
+            for target in &morph_targets {
+                for (p, d) in streams.positions.iter().zip(&target.positions) {
+                    mesh_bounds.extend(glam::Vec3::from(*p) + glam::Vec3::from(*d));
                 }
             }
+            let mut format = streams.format();
+            if !morph_targets.is_empty() {
+                format |= VertexFormat::MORPH_TARGETS;
+            }
+
+            let mut cpu = CpuMesh::new(streams, indices);
+            cpu.morph_targets = morph_targets;
+            if options.optimize_meshes {
+                optimize_mesh(&mut cpu);
+            }
+            cpu.lods = if lod_meshes.is_empty() {
+                generate_lods(&cpu, &options.lod_levels)
+            } else {
+                lod_meshes
+                    .iter()
+                    .map_while(|(lod_mesh, screen_size)| {
+                        let lod_prim = lod_mesh.primitives().nth(prim.index())?;
+                        let (vertices, indices) =
+                            read_primitive(&lod_prim, &buffers, skin_ix.is_some()).ok()?;
+                        (cpu.morph_targets.is_empty() && vertices.format() == cpu.vertices.format())
+                            .then_some(CpuLod {
+                                screen_size: *screen_size,
+                                vertices: Some(vertices),
+                                indices,
+                            })
+                    })
+                    .collect()
+            };
+            let lods = cpu
+                .lods
+                .iter()
+                .map(|lod| MeshLod::upload(device, lod, cpu.vertices.len()))
+                .collect();
+
+            let morph = (!cpu.morph_targets.is_empty()).then(|| {
+                GpuMorphTargets::new(
+                    device,
+                    &cpu.morph_targets,
+                    cpu.vertices.len(),
+                    nodes[node_ix].weights.clone(),
+                )
+            });
+            let vbuf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("mesh_vbuf"),
+                contents: &cpu.vertices.interleave(),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+            let (ibuf, index_format) =
+                create_index_buffer(device, &cpu.indices, cpu.vertices.len());
+
+            let skinned = format.is_skinned();
+            bounds = bounds.union(&if skinned {
+                mesh_bounds
+            } else {
+                mesh_bounds.transformed(world)
+            });
+
+            let mat_ix = match prim.material().index() {
+                Some(ix) => ix,
+                None => *default_material_ix.get_or_insert_with(|| {
+                    materials.push(registry.default_material());
+                    materials.len() - 1
+                }),
+            };
+            meshes.push(GpuMesh {
+                vbuf,
+                ibuf,
+                index_count: cpu.indices.len() as u32,
+                index_format,
+                material_id: mat_ix,
+                format,
+                bounds: mesh_bounds,
+                cpu: options.retain_cpu_data.then(|| Arc::new(cpu)),
+                node: Some(node_ix),
+                skin: skin_ix.filter(|_| skinned),
+                morph,
+                lods,
+            });
         }
     }
 
-    let center = (min_v + max_v) * 0.5;
-    let extent = max_v - min_v;
-    let max_dim = extent.max_element().max(1e-5);
-    let scale = 1.0 / max_dim;
-    let recommended_xform = glam::Mat4::from_scale(glam::Vec3::splat(scale * 2.0))
-        * glam::Mat4::from_translation(-center);
-
     Ok(Model {
         meshes,
         materials,
-        recommended_xform,
+        nodes,
+        roots,
+        skins,
+        cameras,
+        lights,
+        animations: clips_from_gltf(&doc, &buffers)?,
+        recommended_xform: recommended_xform(&bounds),
+        bounds,
     })
 }
 
-fn make_white_material(device: &wgpu::Device, material_bgl: &BindGroupLayout) -> Material {
-    let rgba = image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255]));
-    make_texture_material_impl(device, None, Some(rgba), material_bgl)
+fn read_primitive(
+    prim: &gltf::Primitive,
+    buffers: &[gltf::buffer::Data],
+    skinned: bool,
+) -> Result<(VertexStreams, Vec<u32>)> {
+    let reader = prim.reader(|buf| Some(&buffers[buf.index()].0));
+    let positions: Vec<[f32; 3]> = reader
+        .read_positions()
+        .context("POSITION is missing")?
+        .collect();
+
+    let mut streams = VertexStreams::new(positions);
+    streams.normals = reader.read_normals().map(|it| it.collect());
+    streams.uv0 = reader.read_tex_coords(0).map(|tc| tc.into_f32().collect());
+    streams.colors = reader.read_colors(0).map(|c| c.into_rgba_f32().collect());
+    streams.uv1 = reader.read_tex_coords(1).map(|tc| tc.into_f32().collect());
+    streams.tangents = reader.read_tangents().map(|it| it.collect());
+    if skinned {
+        streams.joints = reader.read_joints(0).map(|j| j.into_u16().collect());
+        streams.weights = reader.read_weights(0).map(|w| w.into_f32().collect());
+    }
+
+    let indices: Vec<u32> = reader
+        .read_indices()
+        .map(|r| r.into_u32().collect())
+        .unwrap_or_else(|| (0..streams.len() as u32).collect());
+    Ok((streams, triangulate(prim.mode(), indices)?))
 }
 
-fn make_texture_material(
-    device: &wgpu::Device,
-    queue: &Queue,
-    material_bgl: &BindGroupLayout,
-    img: Option<&gltf::image::Data>,
-) -> Material {
-    let rgba_img = if let Some(g) = img {
-        let (w, h) = (g.width, g.height);
-        match g.format {
-            gltf::image::Format::R8G8B8A8 => image::RgbaImage::from_raw(w, h, g.pixels.clone())
-                .unwrap_or_else(|| {
-                    image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255]))
-                }),
-            gltf::image::Format::R8G8B8 => {
-                let mut out = Vec::with_capacity((w * h * 4) as usize);
-                for c in g.pixels.chunks_exact(3) {
-                    out.extend_from_slice(&[c[0], c[1], c[2], 255]);
+// Strips and fans are expanded to lists with the winding the glTF spec defines; points and lines
+// have no surface for the mesh pipelines to shade.
+fn triangulate(mode: gltf::mesh::Mode, indices: Vec<u32>) -> Result<Vec<u32>> {
+    use gltf::mesh::Mode;
+    Ok(match mode {
+        Mode::Triangles => indices,
+        Mode::TriangleStrip => indices
+            .windows(3)
+            .enumerate()
+            .flat_map(|(i, w)| {
+                if i % 2 == 0 {
+                    [w[0], w[1], w[2]]
+                } else {
+                    [w[0], w[2], w[1]]
                 }
-                image::RgbaImage::from_raw(w, h, out).unwrap_or_else(|| {
-                    image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255]))
-                })
+            })
+            .collect(),
+        Mode::TriangleFan => match indices.split_first() {
+            Some((&center, rest)) => rest.windows(2).flat_map(|w| [w[0], w[1], center]).collect(),
+            None => Vec::new(),
+        },
+        mode => bail!("{mode:?} primitives are not supported"),
+    })
+}
+
+// MSFT_screencoverage is a fraction of screen area; LOD selection works on height.
+fn msft_lods<'a>(node: &gltf::Node, nodes: &[gltf::Node<'a>]) -> Vec<(gltf::Mesh<'a>, f32)> {
+    let Some(ids) = node
+        .extension_value("MSFT_lod")
+        .and_then(|ext| ext.get("ids"))
+        .and_then(|ids| ids.as_array())
+    else {
+        return Vec::new();
+    };
+    let coverage: Vec<f32> = node
+        .extras()
+        .as_ref()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw.get()).ok())
+        .and_then(|extras| {
+            let values = extras.get("MSFT_screencoverage")?.as_array()?.clone();
+            Some(
+                values
+                    .iter()
+                    .filter_map(|v| v.as_f64())
+                    .map(|v| v as f32)
+                    .collect(),
+            )
+        })
+        .unwrap_or_default();
+    ids.iter()
+        .enumerate()
+        .map_while(|(i, id)| {
+            let mesh = nodes.get(id.as_u64()? as usize)?.mesh()?;
+            let screen_size = coverage
+                .get(i)
+                .map_or(0.5f32.powi(i as i32 + 1), |c| c.max(0.0).sqrt());
+            Some((mesh, screen_size))
+        })
+        .collect()
+}
+
+type Import = (
+    gltf::Document,
+    Vec<gltf::buffer::Data>,
+    Vec<Option<(ImageKey, SourceImage)>>,
+);
+
+fn import(
+    name: &Path,
+    bytes: &[u8],
+    resolver: &UriResolver,
+    features: wgpu::Features,
+) -> Result<Import> {
+    let gltf::Gltf { document, mut blob } = gltf::Gltf::from_slice_without_validation(bytes)
+        .with_context(|| format!("parsing {}", name.display()))?;
+
+    let unsupported: Vec<&str> = document
+        .extensions_required()
+        .filter(|ext| !SUPPORTED_EXTENSIONS.contains(ext))
+        .collect();
+    if !unsupported.is_empty() {
+        bail!(
+            "{} requires unsupported glTF extensions: {}",
+            name.display(),
+            unsupported.join(", ")
+        );
+    }
+
+    let mut json = document.into_json();
+    json.extensions_required
+        .retain(|ext| gltf::json::extensions::ENABLED_EXTENSIONS.contains(&ext.as_str()));
+    let document = gltf::Document::from_json(json)
+        .with_context(|| format!("validating {}", name.display()))?;
+
+    let mut buffers = Vec::new();
+    for buffer in document.buffers() {
+        let mut data = match buffer.source() {
+            gltf::buffer::Source::Bin => blob.take().context("missing GLB binary chunk")?,
+            gltf::buffer::Source::Uri(uri) if uri.starts_with("data:") => {
+                gltf::buffer::Data::from_source(buffer.source(), None)
+                    .with_context(|| format!("decoding buffer {}", buffer.index()))?
+                    .0
+            }
+            gltf::buffer::Source::Uri(uri) => {
+                resolver(uri).with_context(|| format!("resolving buffer {uri}"))?
             }
-            _ => image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255])),
+        };
+        if data.len() < buffer.length() {
+            bail!(
+                "{}: buffer {} is {} bytes but declares {}",
+                name.display(),
+                buffer.index(),
+                data.len(),
+                buffer.length()
+            );
         }
-    } else {
-        image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255]))
-    };
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+        buffers.push(gltf::buffer::Data(data));
+    }
 
-    let (w, h) = (rgba_img.width(), rgba_img.height());
+    // Only images reached through a texture are decoded. A KTX2 alternative that fails to load is
+    // tolerated when the texture also names a plain source to fall back to.
+    let mut required = vec![false; document.images().len()];
+    let mut optional = vec![false; required.len()];
+    for texture in document.textures() {
+        let source = texture.source().map(|image| image.index());
+        let basisu = basisu_source(&texture).filter(|&ix| ix < required.len());
+        match (basisu, source) {
+            (Some(basisu), Some(source)) => {
+                optional[basisu] = true;
+                required[source] = true;
+            }
+            (Some(ix), None) | (None, Some(ix)) => required[ix] = true,
+            (None, None) => {}
+        }
+    }
 
-    let tex = device.create_texture_with_data(
-        queue,
-        &TextureDescriptor {
-            label: Some("baseColorTex"),
-            size: wgpu::Extent3d {
-                width: w,
-                height: h,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
-            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-            view_formats: &[],
-        },
-        TextureDataOrder::LayerMajor,
-        rgba_img.as_raw(),
-    );
+    let images = document
+        .images()
+        .map(|image| {
+            let ix = image.index();
+            if !required[ix] && !optional[ix] {
+                return Ok(None);
+            }
+            match load_gltf_image(&image, &buffers, resolver, features) {
+                Ok(loaded) => Ok(Some(loaded)),
+                Err(_) if !required[ix] => Ok(None),
+                Err(err) => Err(err.context(format!("{}: loading image {ix}", name.display()))),
+            }
+        })
+        .collect::<Result<_>>()?;
+    Ok((document, buffers, images))
+}
 
-    let view = tex.create_view(&TextureViewDescriptor::default());
-    let sampler = device.create_sampler(&SamplerDescriptor {
-        mag_filter: wgpu::FilterMode::Linear,
-        min_filter: wgpu::FilterMode::Linear,
-        mipmap_filter: wgpu::FilterMode::Nearest,
-        address_mode_u: wgpu::AddressMode::Repeat,
-        address_mode_v: wgpu::AddressMode::Repeat,
-        address_mode_w: wgpu::AddressMode::Repeat,
-        ..Default::default()
-    });
-
-    let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("material_bg"),
-        layout: material_bgl,
-        entries: &[
-            BindGroupEntry {
-                binding: 0,
-                resource: BindingResource::TextureView(&view),
-            },
-            BindGroupEntry {
-                binding: 1,
-                resource: BindingResource::Sampler(&sampler),
-            },
-        ],
-    });
+fn load_gltf_image(
+    image: &gltf::Image,
+    buffers: &[gltf::buffer::Data],
+    resolver: &UriResolver,
+    features: wgpu::Features,
+) -> Result<(ImageKey, SourceImage)> {
+    let bytes = match image.source() {
+        gltf::image::Source::Uri { uri, .. } if uri.starts_with("data:") => {
+            gltf::buffer::Data::from_source(gltf::buffer::Source::Uri(uri), None)?.0
+        }
+        gltf::image::Source::Uri { uri, .. } => {
+            resolver(uri).with_context(|| format!("resolving {uri}"))?
+        }
+        gltf::image::Source::View { view, .. } => buffers
+            .get(view.buffer().index())
+            .and_then(|buffer| buffer.get(view.offset()..view.offset() + view.length()))
+            .context("image buffer view is out of range")?
+            .to_vec(),
+    };
+    let source = load_source_image(&bytes, features)?;
+    Ok((ImageKey::for_bytes(&bytes), source))
+}
+
+fn basisu_source(texture: &gltf::Texture) -> Option<usize> {
+    texture
+        .extension_value("KHR_texture_basisu")
+        .and_then(|ext| ext.get("source")?.as_u64())
+        .map(|ix| ix as usize)
+}
 
-    Material { bind_group: bg }
+// Prefer the KTX2 image from KHR_texture_basisu, falling back to the plain source when it did
+// not load.
+fn texture_image<'a, T>(
+    doc: &'a gltf::Document,
+    texture: &gltf::Texture<'a>,
+    images: &[Option<T>],
+) -> Option<gltf::Image<'a>> {
+    basisu_source(texture)
+        .and_then(|ix| doc.images().nth(ix))
+        .filter(|image| matches!(images.get(image.index()), Some(Some(_))))
+        .or_else(|| texture.source())
 }
 
-fn make_texture_material_impl(
+fn texture_transform(info: &gltf::texture::Info) -> TextureTransform {
+    let tex_coord = info.tex_coord();
+    match info.texture_transform() {
+        Some(t) => TextureTransform {
+            offset: t.offset().into(),
+            rotation: t.rotation(),
+            scale: t.scale().into(),
+            tex_coord: t.tex_coord().unwrap_or(tex_coord),
+        },
+        None => TextureTransform {
+            tex_coord,
+            ..TextureTransform::IDENTITY
+        },
+    }
+}
+
+fn make_texture_material(
     device: &wgpu::Device,
-    _queue: Option<&Queue>,
-    rgba: Option<image::RgbaImage>,
-    material_bgl: &BindGroupLayout,
+    queue: &Queue,
+    registry: &MaterialRegistry,
+    cache: &mut TextureCache,
+    base_color: TextureSource,
+    emissive: TextureSource,
+    params: MaterialParams,
 ) -> Material {
-    let img = rgba
-        .unwrap_or_else(|| image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255])));
-    let tex = device.create_texture(&TextureDescriptor {
-        label: Some("baseColorTex"),
-        size: wgpu::Extent3d {
-            width: img.width(),
-            height: img.height(),
-            depth_or_array_layers: 1,
-        },
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: TextureDimension::D2,
-        format: TextureFormat::Rgba8UnormSrgb,
-        usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-        view_formats: &[],
-    });
-    let view = tex.create_view(&TextureViewDescriptor::default());
-    let sampler = device.create_sampler(&SamplerDescriptor::default());
-    let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("material_bg"),
-        layout: material_bgl,
-        entries: &[
-            BindGroupEntry {
-                binding: 0,
-                resource: BindingResource::TextureView(&view),
-            },
-            BindGroupEntry {
-                binding: 1,
-                resource: BindingResource::Sampler(&sampler),
-            },
-        ],
-    });
-    Material { bind_group: bg }
+    let mut load = |img: TextureSource, slot: TextureSlot, default| match img {
+        Some((key, g)) => cache.texture(device, queue, key, || decode_source(g, slot)),
+        None => default,
+    };
+    let base_color = load(
+        base_color,
+        TextureSlot::BaseColor,
+        registry.fallbacks.white.clone(),
+    );
+    let emissive = load(
+        emissive,
+        TextureSlot::Emissive,
+        registry.fallbacks.white.clone(),
+    );
+    registry.create_material_with_params(device, base_color, emissive, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    // Three positions of a single triangle.
+    const TRIANGLE_BASE64: &str = "AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA";
+    const TRIANGLE_LEN: usize = 36;
+
+    fn triangle_bytes() -> Vec<u8> {
+        [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect()
+    }
+
+    fn png(rgba: [u8; 4]) -> Vec<u8> {
+        test_util::png(1, 1, |_, _| rgba)
+    }
+
+    fn gltf_json(buffer: &str, byte_length: usize, image: Option<&str>) -> String {
+        let (images, textures, materials, material) = match image {
+            Some(uri) => (
+                format!(r#""images": [{{"uri": "{uri}"}}],"#),
+                r#""textures": [{"source": 0}],"#,
+                r#""materials": [{"pbrMetallicRoughness": {"baseColorTexture": {"index": 0}}}],"#,
+                r#", "material": 0"#,
+            ),
+            None => (String::new(), "", "", ""),
+        };
+        format!(
+            r#"{{
+  "asset": {{"version": "2.0"}},
+  "scene": 0,
+  "scenes": [{{"nodes": [0]}}],
+  "nodes": [{{"mesh": 0}}],
+  "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}{material}}}]}}],
+  {images}{textures}{materials}
+  "buffers": [{{{buffer}"byteLength": {byte_length}}}],
+  "bufferViews": [{{"buffer": 0, "byteLength": 36}}],
+  "accessors": [{{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                  "min": [0, 0, 0], "max": [1, 1, 0]}}]
+}}"#
+        )
+    }
+
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut bin = bin.to_vec();
+        while !bin.len().is_multiple_of(4) {
+            bin.push(0);
+        }
+        let total = 12 + 8 + json.len() + 8 + bin.len();
+        let mut out = Vec::with_capacity(total);
+        out.extend_from_slice(b"glTF");
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&(total as u32).to_le_bytes());
+        out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"JSON");
+        out.extend_from_slice(&json);
+        out.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"BIN\0");
+        out.extend_from_slice(&bin);
+        out
+    }
+
+    fn no_files(uri: &str) -> Result<Vec<u8>> {
+        bail!("unexpected request for {uri}")
+    }
+
+    fn positions(doc: &gltf::Document, buffers: &[gltf::buffer::Data]) -> Vec<[f32; 3]> {
+        let prim = doc.meshes().next().unwrap().primitives().next().unwrap();
+        let (streams, indices) = read_primitive(&prim, buffers, false).unwrap();
+        assert_eq!(indices, [0, 1, 2]);
+        streams.positions
+    }
+
+    fn data_uri_buffer() -> String {
+        format!(r#""uri": "data:application/octet-stream;base64,{TRIANGLE_BASE64}", "#)
+    }
+
+    #[test]
+    fn gltf_with_data_uri_buffer_imports_from_a_slice() {
+        let json = gltf_json(&data_uri_buffer(), TRIANGLE_LEN, None);
+        let (doc, buffers, images) = import(
+            Path::new("inline.gltf"),
+            json.as_bytes(),
+            &no_files,
+            wgpu::Features::empty(),
+        )
+        .unwrap();
+        assert!(images.is_empty());
+        assert_eq!(
+            positions(&doc, &buffers),
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn glb_reads_its_binary_chunk() {
+        let json = gltf_json("", TRIANGLE_LEN, None);
+        let bytes = glb(&json, &triangle_bytes());
+        let (doc, buffers, _) = import(
+            Path::new("inline.glb"),
+            &bytes,
+            &no_files,
+            wgpu::Features::empty(),
+        )
+        .unwrap();
+        assert_eq!(positions(&doc, &buffers)[1], [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn custom_resolver_supplies_external_buffers_and_images() {
+        let files: HashMap<&str, Vec<u8>> = HashMap::from([
+            ("mesh data.bin", triangle_bytes()),
+            ("albedo.png", png([255, 0, 0, 255])),
+        ]);
+        let requested = RefCell::new(Vec::new());
+        let resolver = |uri: &str| {
+            requested.borrow_mut().push(uri.to_owned());
+            let path = urlencoding::decode(uri)?;
+            files.get(&*path).cloned().context("not found")
+        };
+        let json = gltf_json(
+            r#""uri": "mesh%20data.bin", "#,
+            TRIANGLE_LEN,
+            Some("albedo.png"),
+        );
+        let (doc, buffers, images) = import(
+            Path::new("memory.gltf"),
+            json.as_bytes(),
+            &resolver,
+            wgpu::Features::empty(),
+        )
+        .unwrap();
+        assert_eq!(*requested.borrow(), ["mesh%20data.bin", "albedo.png"]);
+        assert_eq!(positions(&doc, &buffers).len(), 3);
+        assert!(matches!(images[..], [Some((ImageKey::Content(_), _))]));
+    }
+
+    #[test]
+    fn short_buffer_is_rejected() {
+        let json = gltf_json(&data_uri_buffer(), TRIANGLE_LEN + 4, None);
+        let err = import(
+            Path::new("short.gltf"),
+            json.as_bytes(),
+            &no_files,
+            wgpu::Features::empty(),
+        )
+        .err()
+        .unwrap();
+        assert!(
+            err.to_string().contains("is 36 bytes but declares 40"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn unreadable_image_is_an_error() {
+        let json = gltf_json(&data_uri_buffer(), TRIANGLE_LEN, Some("broken.png"));
+        let resolver = |_: &str| Ok(b"not a png".to_vec());
+        let err = import(
+            Path::new("broken.gltf"),
+            json.as_bytes(),
+            &resolver,
+            wgpu::Features::empty(),
+        )
+        .err()
+        .unwrap();
+        assert!(format!("{err:#}").contains("loading image 0"), "{err:#}");
+
+        let err = import(
+            Path::new("missing.gltf"),
+            json.as_bytes(),
+            &no_files,
+            wgpu::Features::empty(),
+        )
+        .err()
+        .unwrap();
+        assert!(
+            format!("{err:#}").contains("resolving broken.png"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn in_memory_images_are_keyed_by_content() {
+        let key = |name: &str, rgba: [u8; 4]| {
+            let image = png(rgba);
+            let resolver = move |_: &str| Ok(image.clone());
+            let json = gltf_json(&data_uri_buffer(), TRIANGLE_LEN, Some("albedo.png"));
+            let (_, _, images) = import(
+                Path::new(name),
+                json.as_bytes(),
+                &resolver,
+                wgpu::Features::empty(),
+            )
+            .unwrap();
+            images[0].as_ref().unwrap().0.clone()
+        };
+        // Same name, different pixels: must not share a cache entry.
+        assert_ne!(
+            key("scene.gltf", [255, 0, 0, 255]),
+            key("scene.gltf", [0, 0, 255, 255])
+        );
+        // Same pixels from two documents: shared.
+        assert_eq!(key("a.gltf", [9, 9, 9, 255]), key("b.gltf", [9, 9, 9, 255]));
+    }
+
+    #[test]
+    fn basisu_textures_prefer_ktx2_and_fall_back_to_the_source() {
+        let json = gltf_json(&data_uri_buffer(), TRIANGLE_LEN, Some("albedo.png"))
+            .replace(
+                r#""images": [{"uri": "albedo.png"}],"#,
+                r#""images": [{"uri": "albedo.png"}, {"uri": "albedo.ktx2"}],"#,
+            )
+            .replace(
+                r#""textures": [{"source": 0}],"#,
+                r#""textures": [{"source": 0, "extensions": {"KHR_texture_basisu": {"source": 1}}}],
+  "extensionsUsed": ["KHR_texture_basisu"],"#,
+            );
+        let files = |uri: &str| match uri {
+            "albedo.png" => Ok(png([0, 255, 0, 255])),
+            _ => Ok(b"not a ktx2".to_vec()),
+        };
+        let (doc, _, images) = import(
+            Path::new("optional.gltf"),
+            json.as_bytes(),
+            &files,
+            wgpu::Features::empty(),
+        )
+        .unwrap();
+        assert!(images[0].is_some() && images[1].is_none());
+        let texture = doc.textures().next().unwrap();
+        assert_eq!(texture_image(&doc, &texture, &images).unwrap().index(), 0);
+
+        // A file that requires Basis Universal has its KTX2 image transcoded and preferred.
+        let required = json.replace(r#""source": 0, "#, "").replace(
+            r#""extensionsUsed": ["KHR_texture_basisu"],"#,
+            r#""extensionsUsed": ["KHR_texture_basisu"],
+  "extensionsRequired": ["KHR_texture_basisu"],"#,
+        );
+        let files = |uri: &str| match uri {
+            "albedo.png" => Ok(png([0, 255, 0, 255])),
+            _ => Ok(include_bytes!("../testdata/etc1s.ktx2").to_vec()),
+        };
+        let (doc, _, images) = import(
+            Path::new("required.gltf"),
+            required.as_bytes(),
+            &files,
+            wgpu::Features::empty(),
+        )
+        .unwrap();
+        assert!(images[0].is_none() && images[1].is_some());
+        let texture = doc.textures().next().unwrap();
+        assert_eq!(texture_image(&doc, &texture, &images).unwrap().index(), 1);
+
+        // Without a fallback, a KTX2 image that does not decode is an error.
+        let err = import(
+            Path::new("broken.gltf"),
+            required.as_bytes(),
+            &|_: &str| Ok(b"not a ktx2".to_vec()),
+            wgpu::Features::empty(),
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("loading image 1"), "{err}");
+    }
+
+    #[test]
+    fn strips_and_fans_become_triangle_lists() {
+        use gltf::mesh::Mode;
+        let indices = vec![0, 1, 2, 3, 4];
+        assert_eq!(
+            triangulate(Mode::TriangleStrip, indices.clone()).unwrap(),
+            [0, 1, 2, 1, 3, 2, 2, 3, 4]
+        );
+        assert_eq!(
+            triangulate(Mode::TriangleFan, indices.clone()).unwrap(),
+            [1, 2, 0, 2, 3, 0, 3, 4, 0]
+        );
+        assert_eq!(
+            triangulate(Mode::Triangles, vec![2, 1, 0]).unwrap(),
+            [2, 1, 0]
+        );
+        assert!(
+            triangulate(Mode::TriangleFan, Vec::new())
+                .unwrap()
+                .is_empty()
+        );
+        let err = triangulate(Mode::Lines, indices).unwrap_err();
+        assert!(err.to_string().contains("Lines"), "{err}");
+    }
+
+    #[test]
+    fn load_from_slice_builds_a_model() {
+        let Some((device, queue)) = test_util::gpu() else {
+            return;
+        };
+        let layouts = minima_3d::create_bind_group_layouts(&device);
+        let registry = MaterialRegistry::new(&device, &queue, &layouts.material_bgl);
+        let mut cache = TextureCache::new();
+        let image = png([0, 255, 0, 255]);
+        let resolver = |_: &str| Ok(image.clone());
+
+        let json = gltf_json(&data_uri_buffer(), TRIANGLE_LEN, Some("albedo.png"));
+        let mut load = |name: &str| {
+            pollster::block_on(load_gltf_model_from_slice(
+                &device,
+                &queue,
+                &registry,
+                &mut cache,
+                Path::new(name),
+                json.as_bytes(),
+                &resolver,
+                &LoadOptions::default(),
+            ))
+            .unwrap()
+        };
+        let a = load("a.gltf");
+        let b = load("b.gltf");
+        assert_eq!(a.meshes.len(), 1);
+        assert_eq!(a.meshes[0].index_count, 3);
+        assert_eq!(a.roots, [0]);
+        assert!(Arc::ptr_eq(&a.materials[0], &b.materials[0]));
+        assert_eq!(cache.texture_count(), 1);
+    }
 }
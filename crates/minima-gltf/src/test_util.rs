@@ -0,0 +1,20 @@
+// GPU-backed tests return early on machines without an adapter.
+pub fn gpu() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::default();
+    let Ok(adapter) = pollster::block_on(instance.request_adapter(&Default::default())) else {
+        eprintln!("no adapter, skipping");
+        return None;
+    };
+    pollster::block_on(adapter.request_device(&Default::default())).ok()
+}
+
+pub fn png(width: u32, height: u32, pixel: impl Fn(u32, u32) -> [u8; 4]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image::RgbaImage::from_fn(width, height, |x, y| image::Rgba(pixel(x, y)))
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+    bytes
+}
@@ -0,0 +1,301 @@
+use anyhow::{Result, bail};
+use gltf::image::{Data, Format};
+use half::f16;
+use image::DynamicImage;
+use wgpu::{
+    Features, Queue, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    util::{DeviceExt, TextureDataOrder},
+};
+
+use crate::ktx::{is_ktx2, load_ktx2};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TextureSlot {
+    BaseColor,
+    Emissive,
+    Normal,
+    MetallicRoughness,
+    Occlusion,
+}
+
+impl TextureSlot {
+    pub fn is_srgb(self) -> bool {
+        matches!(self, TextureSlot::BaseColor | TextureSlot::Emissive)
+    }
+}
+
+#[derive(Clone)]
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+    pub mip_level_count: u32,
+    pub bytes: Vec<u8>,
+}
+
+pub enum SourceImage {
+    Pixels(Data),
+    Ktx2(DecodedImage),
+}
+
+pub fn decode_image(img: &Data, slot: TextureSlot) -> DecodedImage {
+    let srgb = slot.is_srgb();
+    let (format, bytes) = match img.format {
+        Format::R8 => (rgba8_format(srgb), expand_u8(&img.pixels, 1)),
+        Format::R8G8 => (rgba8_format(srgb), expand_u8(&img.pixels, 2)),
+        Format::R8G8B8 => (rgba8_format(srgb), expand_u8(&img.pixels, 3)),
+        Format::R8G8B8A8 => (rgba8_format(srgb), img.pixels.clone()),
+        Format::R16 => (TextureFormat::Rgba16Float, expand_u16(&img.pixels, 1, srgb)),
+        Format::R16G16 => (TextureFormat::Rgba16Float, expand_u16(&img.pixels, 2, srgb)),
+        Format::R16G16B16 => (TextureFormat::Rgba16Float, expand_u16(&img.pixels, 3, srgb)),
+        Format::R16G16B16A16 => (TextureFormat::Rgba16Float, expand_u16(&img.pixels, 4, srgb)),
+        Format::R32G32B32FLOAT => (TextureFormat::Rgba16Float, expand_f32(&img.pixels, 3)),
+        Format::R32G32B32A32FLOAT => (TextureFormat::Rgba16Float, expand_f32(&img.pixels, 4)),
+    };
+    DecodedImage {
+        width: img.width,
+        height: img.height,
+        format,
+        mip_level_count: 1,
+        bytes,
+    }
+}
+
+pub fn decode_source(image: &SourceImage, slot: TextureSlot) -> DecodedImage {
+    match image {
+        SourceImage::Pixels(data) => decode_image(data, slot),
+        SourceImage::Ktx2(image) => {
+            // KTX2 transfer functions are often left unspecified, so the slot decides like it
+            // does for PNG and JPEG.
+            let format = if slot.is_srgb() {
+                image.format.add_srgb_suffix()
+            } else {
+                image.format.remove_srgb_suffix()
+            };
+            DecodedImage {
+                format,
+                ..image.clone()
+            }
+        }
+    }
+}
+
+pub fn load_source_image(bytes: &[u8], features: Features) -> Result<SourceImage> {
+    if is_ktx2(bytes) {
+        Ok(SourceImage::Ktx2(load_ktx2(bytes, features)?))
+    } else {
+        Ok(SourceImage::Pixels(load_image(bytes)?))
+    }
+}
+
+pub fn load_image(bytes: &[u8]) -> Result<Data> {
+    let image = image::load_from_memory(bytes)?;
+    let format = match image {
+        DynamicImage::ImageLuma8(_) => Format::R8,
+        DynamicImage::ImageLumaA8(_) => Format::R8G8,
+        DynamicImage::ImageRgb8(_) => Format::R8G8B8,
+        DynamicImage::ImageRgba8(_) => Format::R8G8B8A8,
+        DynamicImage::ImageLuma16(_) => Format::R16,
+        DynamicImage::ImageLumaA16(_) => Format::R16G16,
+        DynamicImage::ImageRgb16(_) => Format::R16G16B16,
+        DynamicImage::ImageRgba16(_) => Format::R16G16B16A16,
+        DynamicImage::ImageRgb32F(_) => Format::R32G32B32FLOAT,
+        DynamicImage::ImageRgba32F(_) => Format::R32G32B32A32FLOAT,
+        other => bail!("unsupported image color type {:?}", other.color()),
+    };
+    Ok(Data {
+        width: image.width(),
+        height: image.height(),
+        format,
+        pixels: image.into_bytes(),
+    })
+}
+
+pub fn upload_image(
+    device: &wgpu::Device,
+    queue: &Queue,
+    label: &str,
+    image: &DecodedImage,
+) -> wgpu::Texture {
+    device.create_texture_with_data(
+        queue,
+        &TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: image.width,
+                height: image.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: image.mip_level_count,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: image.format,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        },
+        TextureDataOrder::LayerMajor,
+        &image.bytes,
+    )
+}
+
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &Queue,
+    texture: &wgpu::Texture,
+) -> Result<DecodedImage> {
+    let format = texture.format();
+    let Some(block_size) = format.block_copy_size(None) else {
+        bail!("cannot read back texture format {format:?}");
+    };
+    let (block_width, block_height) = format.block_dimensions();
+    let size = texture.size();
+    let levels = texture.mip_level_count();
+
+    let mut layouts = Vec::with_capacity(levels as usize);
+    let mut offset = 0;
+    for level in 0..levels {
+        let extent = size
+            .mip_level_size(level, TextureDimension::D2)
+            .physical_size(format);
+        let rows = extent.height / block_height;
+        let unpadded = extent.width / block_width * block_size;
+        let padded = unpadded.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        layouts.push((extent, offset, rows, unpadded, padded));
+        offset += (padded * rows) as u64;
+    }
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("texture_readback"),
+        size: offset,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("texture_readback"),
+    });
+    for (level, &(extent, offset, rows, _, padded)) in layouts.iter().enumerate() {
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                mip_level: level as u32,
+                ..texture.as_image_copy()
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset,
+                    bytes_per_row: Some(padded),
+                    rows_per_image: Some(rows),
+                },
+            },
+            extent,
+        );
+    }
+    queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (tx, rx) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = tx.send(result);
+    });
+    device.poll(wgpu::PollType::wait_indefinitely())?;
+    rx.recv()??;
+
+    let mut bytes = Vec::new();
+    {
+        let mapped = slice.get_mapped_range();
+        for &(_, offset, rows, unpadded, padded) in &layouts {
+            let level = &mapped[offset as usize..(offset + (padded * rows) as u64) as usize];
+            for row in level.chunks_exact(padded as usize) {
+                bytes.extend_from_slice(&row[..unpadded as usize]);
+            }
+        }
+    }
+    buffer.unmap();
+    Ok(DecodedImage {
+        width: size.width,
+        height: size.height,
+        format,
+        mip_level_count: levels,
+        bytes,
+    })
+}
+
+fn rgba8_format(srgb: bool) -> TextureFormat {
+    if srgb {
+        TextureFormat::Rgba8UnormSrgb
+    } else {
+        TextureFormat::Rgba8Unorm
+    }
+}
+
+fn expand_u8(pixels: &[u8], channels: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(pixels.len() / channels * 4);
+    for c in pixels.chunks_exact(channels) {
+        out.extend_from_slice(&gray_or_color(c, 255u8));
+    }
+    out
+}
+
+fn expand_u16(pixels: &[u8], channels: usize, srgb: bool) -> Vec<u8> {
+    let values: Vec<f32> = pixels
+        .chunks_exact(2)
+        .map(|b| u16::from_ne_bytes([b[0], b[1]]) as f32 / 65535.0)
+        .collect();
+    let mut out = Vec::with_capacity(values.len() / channels * 8);
+    for c in values.chunks_exact(channels) {
+        let mut px = gray_or_color(c, 1.0);
+        if srgb {
+            for v in &mut px[..3] {
+                *v = srgb_to_linear(*v);
+            }
+        }
+        push_f16(&mut out, px);
+    }
+    out
+}
+
+fn expand_f32(pixels: &[u8], channels: usize) -> Vec<u8> {
+    let values: Vec<f32> = pixels
+        .chunks_exact(4)
+        .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    let mut out = Vec::with_capacity(values.len() / channels * 8);
+    for c in values.chunks_exact(channels) {
+        push_f16(&mut out, gray_or_color(c, 1.0));
+    }
+    out
+}
+
+fn gray_or_color<T: Copy>(c: &[T], one: T) -> [T; 4] {
+    match c.len() {
+        1 => [c[0], c[0], c[0], one],
+        2 => [c[0], c[0], c[0], c[1]],
+        3 => [c[0], c[1], c[2], one],
+        _ => [c[0], c[1], c[2], c[3]],
+    }
+}
+
+fn push_f16(out: &mut Vec<u8>, px: [f32; 4]) {
+    for v in px {
+        out.extend_from_slice(&f16::from_f32(v).to_le_bytes());
+    }
+}
+
+pub fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
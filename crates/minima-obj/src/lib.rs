@@ -0,0 +1,3 @@
+mod loader;
+
+pub use loader::{load_obj_model, texture_path};
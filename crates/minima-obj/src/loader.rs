@@ -0,0 +1,221 @@
+use anyhow::{Context, Result};
+use glam::{Vec3, Vec4};
+use minima_3d::bounds::Aabb;
+use minima_3d::lod::{MeshLod, generate_lods};
+use minima_3d::material::{MaterialParams, MaterialRegistry};
+use minima_3d::mesh::{CpuMesh, create_index_buffer};
+use minima_3d::model::{GpuMesh, Material, Model, recommended_xform};
+use minima_3d::node::{Node, Transform};
+use minima_3d::optimize::optimize_mesh;
+use minima_3d::vertex::VertexStreams;
+use minima_gltf::{
+    ImageKey, LoadOptions, MaterialKey, TextureCache, TextureKey, TextureSlot, decode_source,
+    load_source_image,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use wgpu::{Queue, util::DeviceExt};
+
+pub async fn load_obj_model(
+    device: &wgpu::Device,
+    queue: &Queue,
+    registry: &MaterialRegistry,
+    cache: &mut TextureCache,
+    path: &Path,
+    options: &LoadOptions,
+) -> Result<Model> {
+    let (models, mtl) = tobj::load_obj(
+        path,
+        &tobj::LoadOptions {
+            single_index: true,
+            triangulate: true,
+            ignore_points: true,
+            ignore_lines: true,
+        },
+    )
+    .with_context(|| format!("loading {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    // A missing or malformed .mtl would otherwise silently turn every material default.
+    let mtl = mtl.with_context(|| format!("loading materials for {}", path.display()))?;
+    let mut materials: Vec<Arc<Material>> = mtl
+        .iter()
+        .map(|m| load_material(device, queue, registry, cache, base, m))
+        .collect();
+    let mut default_material_ix = None;
+
+    let mut meshes = Vec::<GpuMesh>::new();
+    let mut nodes = Vec::<Node>::new();
+    let mut bounds = Aabb::EMPTY;
+    for model in models {
+        let mesh = model.mesh;
+        if mesh.indices.is_empty() {
+            continue;
+        }
+        let positions: Vec<[f32; 3]> = mesh
+            .positions
+            .chunks_exact(3)
+            .map(|p| [p[0], p[1], p[2]])
+            .collect();
+        let mesh_bounds = Aabb::from_points(&positions);
+        bounds = bounds.union(&mesh_bounds);
+
+        let mut streams = VertexStreams::new(positions);
+        streams.normals = Some(if mesh.normals.is_empty() {
+            smooth_normals(&streams.positions, &mesh.indices)
+        } else {
+            mesh.normals
+                .chunks_exact(3)
+                .map(|n| [n[0], n[1], n[2]])
+                .collect()
+        });
+        if !mesh.texcoords.is_empty() {
+            streams.uv0 = Some(
+                mesh.texcoords
+                    .chunks_exact(2)
+                    .map(|t| [t[0], 1.0 - t[1]])
+                    .collect(),
+            );
+        }
+        if !mesh.vertex_color.is_empty() {
+            streams.colors = Some(
+                mesh.vertex_color
+                    .chunks_exact(3)
+                    .map(|c| [c[0], c[1], c[2], 1.0])
+                    .collect(),
+            );
+        }
+        let mut cpu = CpuMesh::new(streams, mesh.indices);
+        if options.optimize_meshes {
+            optimize_mesh(&mut cpu);
+        }
+        cpu.lods = generate_lods(&cpu, &options.lod_levels);
+        let lods = cpu
+            .lods
+            .iter()
+            .map(|lod| MeshLod::upload(device, lod, cpu.vertices.len()))
+            .collect();
+
+        let vbuf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mesh_vbuf"),
+            contents: &cpu.vertices.interleave(),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let (ibuf, index_format) = create_index_buffer(device, &cpu.indices, cpu.vertices.len());
+
+        let material_id = match mesh.material_id.filter(|&ix| ix < materials.len()) {
+            Some(ix) => ix,
+            None => *default_material_ix.get_or_insert_with(|| {
+                materials.push(registry.default_material());
+                materials.len() - 1
+            }),
+        };
+        let node_ix = nodes.len();
+        nodes.push(Node {
+            name: Some(model.name),
+            transform: Transform::IDENTITY,
+            ..Node::default()
+        });
+        meshes.push(GpuMesh {
+            vbuf,
+            ibuf,
+            index_count: cpu.indices.len() as u32,
+            index_format,
+            material_id,
+            format: cpu.vertices.format(),
+            bounds: mesh_bounds,
+            cpu: options.retain_cpu_data.then(|| Arc::new(cpu)),
+            node: Some(node_ix),
+            skin: None,
+            morph: None,
+            lods,
+        });
+    }
+
+    Ok(Model {
+        meshes,
+        materials,
+        roots: (0..nodes.len()).collect(),
+        nodes,
+        skins: Vec::new(),
+        cameras: Vec::new(),
+        lights: Vec::new(),
+        animations: Vec::new(),
+        recommended_xform: recommended_xform(&bounds),
+        bounds,
+    })
+}
+
+// MTL texture names resolve against the OBJ's directory, not the .mtl's, and may use Windows
+// separators.
+pub fn texture_path(obj_dir: &Path, file: &str) -> PathBuf {
+    obj_dir.join(file.replace('\\', "/"))
+}
+
+fn load_material(
+    device: &wgpu::Device,
+    queue: &Queue,
+    registry: &MaterialRegistry,
+    cache: &mut TextureCache,
+    base: &Path,
+    m: &tobj::Material,
+) -> Arc<Material> {
+    let diffuse = m.diffuse.unwrap_or([1.0; 3]);
+    let params = MaterialParams {
+        base_color_factor: Vec4::new(
+            diffuse[0],
+            diffuse[1],
+            diffuse[2],
+            m.dissolve.unwrap_or(1.0),
+        ),
+        ..MaterialParams::default()
+    };
+    let texture_file = m.diffuse_texture.as_ref().map(|file| {
+        let file = texture_path(base, file);
+        file.canonicalize().unwrap_or(file)
+    });
+    let key = MaterialKey {
+        base_color: texture_file.clone().map(|file| TextureKey {
+            source: ImageKey::File(file),
+            srgb: TextureSlot::BaseColor.is_srgb(),
+        }),
+        emissive: None,
+        params: params.cache_key(),
+    };
+    cache.material(key.clone(), |cache| {
+        let texture = match (texture_file, key.base_color) {
+            (Some(file), Some(key)) => match std::fs::read(&file)
+                .map_err(anyhow::Error::from)
+                .and_then(|bytes| load_source_image(&bytes, device.features()))
+            {
+                Ok(image) => cache.texture(device, queue, key, || {
+                    decode_source(&image, TextureSlot::BaseColor)
+                }),
+                Err(_) => registry.fallbacks.missing.clone(),
+            },
+            _ => registry.fallbacks.white.clone(),
+        };
+        registry.create_material_with_params(
+            device,
+            texture,
+            registry.fallbacks.black.clone(),
+            params,
+        )
+    })
+}
+
+fn smooth_normals(positions: &[[f32; 3]], indices: &[u32]) -> Vec<[f32; 3]> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+    for tri in indices.chunks_exact(3) {
+        let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+        let n = (Vec3::from(positions[b]) - Vec3::from(positions[a]))
+            .cross(Vec3::from(positions[c]) - Vec3::from(positions[a]));
+        normals[a] += n;
+        normals[b] += n;
+        normals[c] += n;
+    }
+    normals
+        .into_iter()
+        .map(|n| n.normalize_or(Vec3::Y).to_array())
+        .collect()
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use minima_3d::{MaterialRegistry, Model};
+use minima_asset::AssetPipeline;
+use minima_gltf::{LoadOptions, TextureCache, load_gltf_model};
+use minima_obj::load_obj_model;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use wgpu::{Device, Queue};
+
+#[derive(Default)]
+pub struct ModelCache {
+    pub textures: TextureCache,
+    pub options: LoadOptions,
+    pub pipeline: Option<AssetPipeline>,
+    models: HashMap<PathBuf, Arc<Model>>,
+    cook_errors: Vec<(PathBuf, String)>,
+}
+
+impl ModelCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_options(options: LoadOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    // Loads start out with the pipeline's own mesh settings, so they reuse what it cooked.
+    pub fn with_pipeline(pipeline: AssetPipeline) -> Self {
+        Self {
+            options: pipeline.settings.load_options(),
+            pipeline: Some(pipeline),
+            ..Self::default()
+        }
+    }
+
+    pub async fn load(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        registry: &MaterialRegistry,
+        path: &Path,
+    ) -> Result<Arc<Model>> {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if let Some(model) = self.models.get(&key) {
+            return Ok(model.clone());
+        }
+        let cooked = match &self.pipeline {
+            Some(pipeline) if AssetPipeline::is_supported(path) => Some(
+                pipeline
+                    .load(
+                        device,
+                        queue,
+                        registry,
+                        &mut self.textures,
+                        path,
+                        &self.options,
+                    )
+                    .await,
+            ),
+            _ => None,
+        };
+        let model = Arc::new(match cooked {
+            Some(Ok(model)) => model,
+            cooked => {
+                // An unwritable or corrupt cache must not keep the source from loading.
+                if let Some(Err(err)) = cooked {
+                    self.cook_errors
+                        .push((path.to_path_buf(), format!("{err:#}")));
+                }
+                load_source(
+                    device,
+                    queue,
+                    registry,
+                    &mut self.textures,
+                    path,
+                    &self.options,
+                )
+                .await?
+            }
+        });
+        self.models.insert(key, model.clone());
+        Ok(model)
+    }
+
+    // Pipeline failures that were recovered from by loading the source directly.
+    pub fn cook_errors(&self) -> &[(PathBuf, String)] {
+        &self.cook_errors
+    }
+
+    pub fn model_count(&self) -> usize {
+        self.models.len()
+    }
+
+    pub fn purge_unused(&mut self) {
+        self.models.retain(|_, m| Arc::strong_count(m) > 1);
+        self.textures.purge_unused();
+    }
+}
+
+async fn load_source(
+    device: &Device,
+    queue: &Queue,
+    registry: &MaterialRegistry,
+    textures: &mut TextureCache,
+    path: &Path,
+    options: &LoadOptions,
+) -> Result<Model> {
+    let is_obj = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("obj"));
+    if is_obj {
+        load_obj_model(device, queue, registry, textures, path, options).await
+    } else {
+        load_gltf_model(device, queue, registry, textures, path, options).await
+    }
+}
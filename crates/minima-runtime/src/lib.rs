@@ -1,4 +1,15 @@
-use std::{path::Path, time::Instant};
+mod assets;
+
+pub use assets::ModelCache;
+pub use minima_3d::{ClearMode, RenderTarget, ViewRegion};
+pub use minima_asset::{AssetPipeline, CookJob, CookProgress, CookReport, ImportSettings};
+pub use minima_camera::{
+    CameraMatrices, CameraMode, CameraPath, ControllerSettings, FAR, FOV_Y, PathKey, PathPlayer,
+    PathSample, Projection, Ray, ScreenRect, SplineKind,
+};
+pub use minima_scene::{CameraObject, CameraTarget, Scene};
+
+use std::{path::Path, sync::Arc, time::Instant};
 
 use winit::{
     dpi::PhysicalSize,
@@ -8,20 +19,67 @@ use winit::{
 };
 
 use wgpu::{
-    Adapter, CommandEncoderDescriptor, Device, ExperimentalFeatures, Features, Instance, Limits,
-    MemoryHints, PowerPreference, Queue, RequestAdapterOptions, Surface, SurfaceConfiguration,
-    Texture, TextureFormat, TextureView, TextureViewDescriptor,
+    Adapter, CommandEncoderDescriptor, Device, ExperimentalFeatures, Instance, Limits, MemoryHints,
+    PowerPreference, Queue, RequestAdapterOptions, Surface, SurfaceConfiguration, Texture,
+    TextureFormat, TextureView, TextureViewDescriptor,
 };
 
 pub type RcWindow = std::sync::Arc<Window>;
 
-use minima_3d::{Layouts, Renderer3D, create_bind_group_layouts};
+use minima_3d::{
+    Camera, CameraProjection, CameraView, DepthMode, Layouts, Material, MaterialRegistry,
+    Renderer3D, create_bind_group_layouts,
+};
+use minima_anim::{Animator, PlaybackSettings};
 use minima_camera::{CameraController, OrbitCamera, update_camera_buffer};
-use minima_gltf::load_gltf_model;
+use minima_gltf::{compression_features, export_scene};
 
-use glam::Vec3;
+use glam::{Vec2, Vec3};
 
 const CAMERA_SPEED: f32 = 3.0;
+// Scene cameras on the main target with a higher priority draw over the editor camera.
+pub const MAIN_CAMERA_PRIORITY: i32 = 0;
+// Index of the rendered model in `Graphics::scene`; the follow camera tracks it by default.
+const MAIN_MODEL: usize = 0;
+
+struct CameraSlot {
+    object: CameraObject,
+    view: CameraView,
+    target: Option<RenderTarget>,
+    // Shows `target`; rebuilt only when the target is, so users keep one material per texture.
+    material: Option<Arc<Material>>,
+}
+
+impl CameraSlot {
+    fn new(device: &Device, layouts: &Layouts, object: CameraObject) -> Self {
+        Self {
+            object,
+            view: CameraView::new(device, layouts),
+            target: None,
+            material: None,
+        }
+    }
+
+    // Creates, resizes or drops the offscreen target so it matches the component.
+    fn sync(&mut self, device: &Device, registry: &MaterialRegistry, format: TextureFormat) {
+        match self.object.target {
+            CameraTarget::Main => {
+                self.target = None;
+                self.material = None;
+            }
+            CameraTarget::Texture { width, height } => {
+                let size = (width.max(1), height.max(1));
+                if self.target.as_ref().map(|t| (t.width, t.height)) != Some(size) {
+                    let target = RenderTarget::new(device, format, size.0, size.1);
+                    self.material = Some(Arc::new(target.material(device, registry)));
+                    self.target = Some(target);
+                }
+            }
+        }
+        self.view.region = self.object.region;
+        self.view.clear = self.object.clear;
+    }
+}
 
 pub struct Viewport {
     pub color: Texture,
@@ -86,7 +144,12 @@ impl Viewport {
     }
 }
 
-pub async fn create_graphics(window: RcWindow, proxy: EventLoopProxy<Graphics>) {
+// Startup loads go through `pipeline`, so they share the cache that project imports write.
+pub async fn create_graphics(
+    window: RcWindow,
+    proxy: EventLoopProxy<Graphics>,
+    pipeline: AssetPipeline,
+) {
     let instance = Instance::default();
     let surface = instance
         .create_surface(std::sync::Arc::clone(&window))
@@ -104,8 +167,10 @@ pub async fn create_graphics(window: RcWindow, proxy: EventLoopProxy<Graphics>)
     let (device, queue) = adapter
         .request_device(&wgpu::DeviceDescriptor {
             label: None,
-            required_features: Features::empty(),
-            required_limits: Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits()),
+            required_features: adapter.features() & compression_features(),
+            // Not the WebGL2 defaults: those allow no storage buffers, and skinning and morph
+            // targets read joints, deltas and weights from vertex-stage storage buffers.
+            required_limits: Limits::downlevel_defaults().using_resolution(adapter.limits()),
             memory_hints: MemoryHints::Performance,
             trace: Default::default(),
             experimental_features: ExperimentalFeatures::disabled(),
@@ -124,17 +189,35 @@ pub async fn create_graphics(window: RcWindow, proxy: EventLoopProxy<Graphics>)
 
     let layouts: Layouts = create_bind_group_layouts(&device);
 
-    let model = load_gltf_model(
+    let materials = Arc::new(MaterialRegistry::new(
         &device,
         &queue,
         &layouts.material_bgl,
-        Path::new("assets/BoomBox.glb"),
-    )
-    .await
-    .expect("Failed to load glTF model");
+    ));
+
+    let model_path = pipeline.source_dir.join("BoomBox.glb");
+    let mut models = ModelCache::with_pipeline(pipeline);
+    let model = models
+        .load(&device, &queue, &materials, &model_path)
+        .await
+        .expect("Failed to load glTF model");
+
+    // Clips come with the model; none plays until picked through `play_animation`.
+    let animator = Animator::new();
 
     let model_xform = model.recommended_xform;
 
+    // glTF cameras come in disabled so they don't take over the view until enabled.
+    let mut scene = Scene::new();
+    scene.add_model(model.clone(), model_xform);
+    let cameras = std::mem::take(&mut scene.cameras)
+        .into_iter()
+        .map(|mut object| {
+            object.active = false;
+            CameraSlot::new(&device, &layouts, object)
+        })
+        .collect();
+
     let viewport = Viewport::new(
         &device,
         surface_config.format,
@@ -151,15 +234,19 @@ pub async fn create_graphics(window: RcWindow, proxy: EventLoopProxy<Graphics>)
         model,
         model_xform,
         &layouts,
+        DepthMode::default(),
     );
 
     let camera = OrbitCamera::new(Vec3::new(0.0, 0.0, 0.0), 0.0_f32, 0.0_f32);
-    let controller = CameraController::new(CAMERA_SPEED);
+    let mut controller = CameraController::new(CAMERA_SPEED);
+    controller.follow.target_offset = Vec3::ZERO;
+    controller.follow.entity = MAIN_MODEL;
 
     update_camera_buffer(
         &queue,
-        &renderer.camera_buf,
+        &renderer.main_view.camera_buf,
         &camera,
+        renderer.depth_mode,
         surface_config.width,
         surface_config.height,
     );
@@ -175,13 +262,30 @@ pub async fn create_graphics(window: RcWindow, proxy: EventLoopProxy<Graphics>)
         renderer,
         camera,
         controller,
+        animator,
+        active_animation: None,
         viewport,
+        materials,
+        models,
+        layouts,
+        cameras,
+        scene,
+        path_player: None,
         last_frame_time: Instant::now(),
     };
 
     let _ = proxy.send_event(gfx);
 }
 
+// Collision rays for a follow camera tracking `scene.models[entity]`. Other models are what
+// block the view, so the followed one is skipped among them. Alone it still collides: rays
+// only hit front faces, so its own shell lets the camera out, and only geometry it wraps
+// around the camera pulls it in.
+fn follow_cast(scene: &Scene, entity: usize, ray: &Ray, max: f32) -> Option<f32> {
+    let exclude = (scene.models.len() > 1).then_some(entity);
+    scene.raycast_excluding(ray.origin, ray.direction, max, exclude)
+}
+
 #[allow(dead_code)]
 pub struct Graphics {
     pub(crate) window: RcWindow,
@@ -195,6 +299,14 @@ pub struct Graphics {
     renderer: Renderer3D,
     camera: OrbitCamera,
     controller: CameraController,
+    animator: Animator,
+    active_animation: Option<usize>,
+    materials: Arc<MaterialRegistry>,
+    models: ModelCache,
+    layouts: Layouts,
+    cameras: Vec<CameraSlot>,
+    scene: Scene,
+    path_player: Option<PathPlayer>,
     last_frame_time: Instant,
 }
 
@@ -226,8 +338,9 @@ impl Graphics {
 
         update_camera_buffer(
             &self.queue,
-            &self.renderer.camera_buf,
+            &self.renderer.main_view.camera_buf,
             &self.camera,
+            self.renderer.depth_mode,
             self.viewport.width,
             self.viewport.height,
         );
@@ -243,15 +356,89 @@ impl Graphics {
         if dt > 0.1 {
             dt = 0.1;
         }
-        self.controller.update(&mut self.camera, dt);
+        if let Some(player) = &mut self.path_player {
+            player.advance(&mut self.camera, dt);
+            self.controller.follow.deactivate();
+            if player.finished() {
+                self.path_player = None;
+            }
+        } else {
+            self.controller.update(&mut self.camera, dt);
+        }
+        let instance = &self.renderer.instance;
+        let main = &mut self.scene.models[MAIN_MODEL];
+        main.transform = instance.transform;
+        main.pose.world.clone_from(&instance.pose.world);
+        if self.path_player.is_none()
+            && self.camera.mode == CameraMode::Follow
+            && let Some(followed) = self.scene.models.get(self.controller.follow.entity)
+        {
+            let scene = &self.scene;
+            let entity = self.controller.follow.entity;
+            let target = followed
+                .transform
+                .transform_point3(followed.model.bounds.center());
+            self.controller
+                .follow
+                .update(&mut self.camera, target, dt, |ray, max| {
+                    follow_cast(scene, entity, ray, max)
+                });
+        }
+        self.animator.update(dt);
+        let instance = &mut self.renderer.instance;
+        self.animator.apply(&instance.model, &mut instance.pose);
+        instance.select_lods(&self.camera.projection.lod_view(self.camera.eye));
+        instance.update(&self.queue);
+        self.renderer.ensure_pipelines(&self.device, &self.layouts);
 
         update_camera_buffer(
             &self.queue,
-            &self.renderer.camera_buf,
+            &self.renderer.main_view.camera_buf,
             &self.camera,
+            self.renderer.depth_mode,
             self.viewport.width,
             self.viewport.height,
         );
+        let format = self.renderer.surface_format();
+        let main_size = (self.viewport.width, self.viewport.height);
+        for slot in &mut self.cameras {
+            slot.sync(&self.device, &self.materials, format);
+            if slot.object.active {
+                let (w, h) = slot
+                    .target
+                    .as_ref()
+                    .map_or(main_size, |t| (t.width, t.height));
+                let view_proj = slot
+                    .object
+                    .view_proj(slot.object.region.aspect(w, h), self.renderer.depth_mode);
+                // Each camera picks its own detail; the editor's choice can be wrong from afar.
+                let lod_view = slot
+                    .object
+                    .camera
+                    .projection
+                    .lod_view(slot.object.position());
+                self.renderer
+                    .instance
+                    .select_lods_into(&lod_view, &mut slot.view.lods);
+                self.renderer.prepare_view(
+                    &self.queue,
+                    &slot.view,
+                    view_proj,
+                    slot.object.position(),
+                );
+            }
+        }
+        // `None` is the editor camera; the sort is stable so it stays first among equal priorities.
+        let mut views = vec![(MAIN_CAMERA_PRIORITY, None)];
+        views.extend(
+            self.cameras
+                .iter()
+                .enumerate()
+                .filter(|(_, slot)| slot.object.active)
+                .map(|(i, slot)| (slot.object.priority, Some(i))),
+        );
+        views.sort_by_key(|&(priority, _)| priority);
+
         let frame = self
             .surface
             .get_current_texture()
@@ -262,8 +449,27 @@ impl Graphics {
         let mut encoder = self
             .device
             .create_command_encoder(&CommandEncoderDescriptor { label: None });
-        self.renderer
-            .render(&mut encoder, &self.viewport.color_view);
+        // Offscreen views go first so the main target can show them in the same frame.
+        for &(_, index) in &views {
+            if let Some(slot) = index.map(|i| &self.cameras[i])
+                && let Some(target) = &slot.target
+            {
+                self.renderer
+                    .render_view(&mut encoder, &target.view_target(), &slot.view);
+            }
+        }
+        let main = self.renderer.main_target(&self.viewport.color_view);
+        for &(_, index) in &views {
+            match index.map(|i| &self.cameras[i]) {
+                None => self
+                    .renderer
+                    .render_view(&mut encoder, &main, &self.renderer.main_view),
+                Some(slot) if slot.target.is_none() => {
+                    self.renderer.render_view(&mut encoder, &main, &slot.view)
+                }
+                Some(_) => {}
+            }
+        }
         overlay(self, &swap_view, &mut encoder);
         self.queue.submit(Some(encoder.finish()));
         frame.present();
@@ -277,6 +483,18 @@ impl Graphics {
     pub fn handle_device_event(&mut self, event: &DeviceEvent) {
         self.controller.handle_device_event(event, &mut self.camera);
     }
+    pub fn release_camera_input(&mut self) {
+        self.controller.release_all();
+    }
+
+    // Gamepad sticks in -1..1; see `CameraController::set_stick_input`.
+    pub fn set_gamepad_sticks(&mut self, movement: Vec2, look: Vec2) {
+        self.controller.set_stick_input(movement, look);
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
 
     pub fn window(&self) -> &Window {
         &self.window
@@ -290,6 +508,102 @@ impl Graphics {
         &self.queue
     }
 
+    pub fn materials(&self) -> &MaterialRegistry {
+        &self.materials
+    }
+
+    pub fn lod_debug(&self) -> bool {
+        self.renderer.instance.debug_lods
+    }
+
+    pub fn set_lod_debug(&mut self, enabled: bool) {
+        self.renderer.instance.debug_lods = enabled;
+    }
+
+    pub fn reverse_z(&self) -> bool {
+        self.renderer.depth_mode == DepthMode::Reverse
+    }
+
+    pub fn set_reverse_z(&mut self, enabled: bool) {
+        let mode = if enabled {
+            DepthMode::Reverse
+        } else {
+            DepthMode::Standard
+        };
+        self.renderer
+            .set_depth_mode(&self.device, &self.layouts, mode);
+    }
+
+    pub fn animator(&mut self) -> &mut Animator {
+        &mut self.animator
+    }
+
+    pub fn animation_names(&self) -> Vec<String> {
+        self.renderer
+            .instance
+            .model
+            .animations
+            .iter()
+            .enumerate()
+            .map(|(i, clip)| {
+                clip.name
+                    .clone()
+                    .unwrap_or_else(|| format!("Animation {i}"))
+            })
+            .collect()
+    }
+
+    pub fn active_animation(&self) -> Option<usize> {
+        self.active_animation
+    }
+
+    pub fn play_animation(&mut self, clip: Option<usize>) {
+        let instance = &mut self.renderer.instance;
+        let clip = clip.and_then(|ix| Some((ix, instance.model.animations.get(ix)?.clone())));
+        match &clip {
+            Some((_, clip)) => self
+                .animator
+                .play(clip.clone(), PlaybackSettings::default()),
+            None => {
+                self.animator.layer_mut(0).stop();
+                instance.pose.reset(&instance.model);
+            }
+        }
+        self.active_animation = clip.map(|(ix, _)| ix);
+    }
+
+    pub fn models(&self) -> &ModelCache {
+        &self.models
+    }
+
+    // Later loads read and write this pipeline's cache, e.g. once a project is opened.
+    pub fn set_asset_pipeline(&mut self, pipeline: AssetPipeline) {
+        self.models.options = pipeline.settings.load_options();
+        self.models.pipeline = Some(pipeline);
+    }
+
+    // Writes the model in its rest pose plus the scene camera components to .gltf or .glb.
+    pub fn export_scene(&self, path: &Path) -> anyhow::Result<()> {
+        let instance = &self.renderer.instance;
+        let mut scene = Scene::new();
+        scene.add_model(instance.model.clone(), instance.transform);
+        scene.cameras = self
+            .cameras
+            .iter()
+            .map(|slot| slot.object.clone())
+            .collect();
+        export_scene(&self.device, &self.queue, &self.materials, &scene, path)
+    }
+
+    // Cooks on a worker thread; poll the job from the frame loop.
+    pub fn cook_assets(&self, pipeline: &AssetPipeline) -> CookJob {
+        pipeline.spawn_cook_all(
+            self.device.clone(),
+            self.queue.clone(),
+            self.materials.clone(),
+        )
+    }
+
     pub fn surface_config(&self) -> &SurfaceConfiguration {
         &self.surface_config
     }
@@ -305,4 +619,358 @@ impl Graphics {
     pub fn pitch(&self) -> f32 {
         self.camera.pitch
     }
+
+    pub fn camera_mode(&self) -> CameraMode {
+        self.camera.mode
+    }
+
+    pub fn set_camera_mode(&mut self, mode: CameraMode) {
+        self.camera.set_mode(mode);
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.camera.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.camera.projection = projection;
+    }
+
+    pub fn controller_settings(&self) -> ControllerSettings {
+        self.controller.settings
+    }
+
+    pub fn set_controller_settings(&mut self, settings: ControllerSettings) {
+        self.controller.settings = settings;
+    }
+
+    pub fn play_camera_path(&mut self, path: CameraPath) {
+        self.path_player = Some(PathPlayer::new(path));
+    }
+
+    // Camera paths are sequence assets: the editor saves them and games play them back from disk.
+    pub fn load_camera_path(&self, path: &Path) -> anyhow::Result<CameraPath> {
+        CameraPath::load(path)
+    }
+
+    pub fn save_camera_path(&self, path: &Path, camera_path: &CameraPath) -> anyhow::Result<()> {
+        camera_path.save(path)
+    }
+
+    pub fn play_camera_path_asset(&mut self, path: &Path) -> anyhow::Result<()> {
+        let camera_path = self.load_camera_path(path)?;
+        self.play_camera_path(camera_path);
+        Ok(())
+    }
+
+    pub fn stop_camera_path(&mut self) {
+        self.path_player = None;
+    }
+
+    pub fn path_key(&self, time: f32) -> PathKey {
+        PathKey::from_camera(&self.camera, time)
+    }
+
+    pub fn camera_path_time(&self) -> Option<f32> {
+        self.path_player.as_ref().map(|p| p.time)
+    }
+
+    pub fn preview_camera_path(&mut self, path: &CameraPath, time: f32) {
+        if let Some(sample) = path.sample(time) {
+            sample.apply(&mut self.camera);
+        }
+    }
+
+    pub fn camera_matrices(&self) -> CameraMatrices {
+        CameraMatrices::new(
+            &self.camera,
+            self.renderer.depth_mode,
+            self.viewport.width,
+            self.viewport.height,
+        )
+    }
+
+    pub fn scene_cameras(&self) -> Vec<CameraObject> {
+        self.cameras
+            .iter()
+            .map(|slot| slot.object.clone())
+            .collect()
+    }
+
+    pub fn set_scene_cameras(&mut self, cameras: Vec<CameraObject>) {
+        self.cameras.truncate(cameras.len());
+        for (i, object) in cameras.into_iter().enumerate() {
+            match self.cameras.get_mut(i) {
+                Some(slot) => slot.object = object,
+                None => self
+                    .cameras
+                    .push(CameraSlot::new(&self.device, &self.layouts, object)),
+            }
+        }
+    }
+
+    pub fn add_scene_camera(&mut self, object: CameraObject) -> usize {
+        self.cameras
+            .push(CameraSlot::new(&self.device, &self.layouts, object));
+        self.cameras.len() - 1
+    }
+
+    pub fn remove_scene_camera(&mut self, index: usize) -> Option<CameraObject> {
+        (index < self.cameras.len()).then(|| self.cameras.remove(index).object)
+    }
+
+    // Offscreen target of a texture camera, available once it has been drawn at least once.
+    pub fn scene_camera_target(&self, index: usize) -> Option<&RenderTarget> {
+        self.cameras.get(index)?.target.as_ref()
+    }
+
+    // Material showing a texture camera's picture, once its target exists. The same material is
+    // returned until the target is resized; meshes using it are left out of that camera's view.
+    pub fn scene_camera_material(&self, index: usize) -> Option<Arc<Material>> {
+        self.cameras.get(index)?.material.clone()
+    }
+
+    // Draws one mesh of the model with `material`, e.g. a monitor showing `scene_camera_material`.
+    pub fn set_mesh_material(&mut self, mesh: usize, material: Arc<Material>) {
+        self.renderer.instance.set_material(mesh, material);
+    }
+
+    pub fn clear_mesh_material(&mut self, mesh: usize) {
+        self.renderer.instance.clear_material(mesh);
+    }
+
+    // A scene camera frozen at the current editor view, e.g. for a security monitor.
+    pub fn scene_camera_from_view(&self) -> CameraObject {
+        let projection = match self.camera.projection {
+            Projection::Perspective { fov_y, near, far } => CameraProjection::Perspective {
+                yfov: fov_y,
+                aspect_ratio: None,
+                znear: near,
+                zfar: far,
+            },
+            Projection::Orthographic { height, near, far } => CameraProjection::Orthographic {
+                xmag: height * 0.5,
+                ymag: height * 0.5,
+                znear: near,
+                zfar: far,
+            },
+        };
+        let camera = Camera {
+            name: None,
+            projection,
+        };
+        CameraObject::new(camera, self.camera_matrices().inv_view)
+    }
+
+    pub fn orbit_target(&self) -> Vec3 {
+        self.camera.target
+    }
+
+    pub fn orbit_distance(&self) -> f32 {
+        self.camera.distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Mat4;
+    use minima_3d::Model;
+    use minima_camera::FollowCamera;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // A unit cube around the origin, faces wound outwards.
+    const CUBE: &str = "v -0.5 -0.5 -0.5\nv 0.5 -0.5 -0.5\nv 0.5 0.5 -0.5\nv -0.5 0.5 -0.5\n\
+        v -0.5 -0.5 0.5\nv 0.5 -0.5 0.5\nv 0.5 0.5 0.5\nv -0.5 0.5 0.5\n\
+        f 1 4 3 2\nf 5 6 7 8\nf 1 5 8 4\nf 2 3 7 6\nf 1 2 6 5\nf 4 8 7 3\n";
+    // A wall at x = -3 facing the cube.
+    const WALL: &str = "v -3 -5 5\nv -3 -5 -5\nv -3 5 -5\nv -3 5 5\nf 1 2 3 4\n";
+
+    struct Gpu {
+        device: Device,
+        queue: Queue,
+        layouts: Layouts,
+        registry: MaterialRegistry,
+    }
+
+    fn gpu() -> Option<Gpu> {
+        let instance = Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&Default::default())).ok()?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&Default::default())).ok()?;
+        let layouts = create_bind_group_layouts(&device);
+        let registry = MaterialRegistry::new(&device, &queue, &layouts.material_bgl);
+        Some(Gpu {
+            device,
+            queue,
+            layouts,
+            registry,
+        })
+    }
+
+    fn load(gpu: &Gpu, objs: &[&str]) -> Vec<Arc<Model>> {
+        static DIRS: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "minima-runtime-{}-{}",
+            std::process::id(),
+            DIRS.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut cache = ModelCache::new();
+        let models = objs
+            .iter()
+            .enumerate()
+            .map(|(i, obj)| {
+                let path = dir.join(format!("{i}.obj"));
+                std::fs::write(&path, obj).unwrap();
+                pollster::block_on(cache.load(&gpu.device, &gpu.queue, &gpu.registry, &path))
+                    .unwrap()
+            })
+            .collect();
+        let _ = std::fs::remove_dir_all(&dir);
+        models
+    }
+
+    fn scene(objs: &[&str]) -> Option<Scene> {
+        let mut scene = Scene::new();
+        for model in load(&gpu()?, objs) {
+            scene.add_model(model, Mat4::IDENTITY);
+        }
+        Some(scene)
+    }
+
+    fn ray(origin: Vec3, direction: Vec3) -> Ray {
+        Ray { origin, direction }
+    }
+
+    #[test]
+    fn follow_rays_skip_the_followed_model_only_when_others_exist() {
+        let Some(scene) = scene(&[CUBE, WALL]) else {
+            return;
+        };
+        // From outside the cube towards it and the wall behind it.
+        let inward = ray(Vec3::new(5.0, 0.0, 0.0), Vec3::NEG_X);
+        let wall = follow_cast(&scene, 0, &inward, 20.0).unwrap();
+        assert!((wall - 8.0).abs() < 1e-4, "{wall}");
+        let cube = follow_cast(&scene, 1, &inward, 20.0).unwrap();
+        assert!((cube - 4.5).abs() < 1e-4, "{cube}");
+
+        // Alone, the cube lets a ray from its centre out through its own back faces.
+        let Some(alone) = self::scene(&[CUBE]) else {
+            return;
+        };
+        assert_eq!(
+            follow_cast(&alone, 0, &ray(Vec3::ZERO, Vec3::NEG_X), 20.0),
+            None
+        );
+        assert_eq!(
+            follow_cast(&alone, 0, &ray(Vec3::new(-5.0, 0.0, 0.0), Vec3::X), 20.0),
+            Some(4.5)
+        );
+    }
+
+    #[test]
+    fn follow_camera_is_pulled_in_front_of_scene_geometry() {
+        let Some(scene) = scene(&[CUBE, WALL]) else {
+            return;
+        };
+        let mut follow = FollowCamera::new(6.0);
+        follow.target_offset = Vec3::ZERO;
+        // Behind the cube along -x, past the wall, looking at it.
+        let mut camera = OrbitCamera::new(Vec3::new(-6.0, 0.0, 0.0), 0.0, 0.0);
+        let entity = follow.entity;
+        for _ in 0..10 {
+            follow.update(&mut camera, Vec3::ZERO, 1.0 / 60.0, |ray, max| {
+                follow_cast(&scene, entity, ray, max)
+            });
+        }
+        let distance = camera.eye.length();
+        assert!(
+            (distance - (3.0 - follow.collision_radius)).abs() < 1e-3,
+            "{distance}"
+        );
+    }
+
+    #[test]
+    fn texture_camera_material_is_kept_until_its_target_is_resized() {
+        let Some(gpu) = gpu() else {
+            return;
+        };
+        let format = TextureFormat::Rgba8Unorm;
+        let camera = Camera {
+            name: None,
+            projection: CameraProjection::Perspective {
+                yfov: FOV_Y,
+                aspect_ratio: None,
+                znear: 0.1,
+                zfar: Some(FAR),
+            },
+        };
+        let mut object = CameraObject::new(camera, Mat4::IDENTITY);
+        object.target = CameraTarget::Texture {
+            width: 64,
+            height: 32,
+        };
+        let mut slot = CameraSlot::new(&gpu.device, &gpu.layouts, object);
+        slot.sync(&gpu.device, &gpu.registry, format);
+        let first = slot.material.clone().unwrap();
+        assert!(first.samples(&slot.target.as_ref().unwrap().color));
+        slot.sync(&gpu.device, &gpu.registry, format);
+        assert!(Arc::ptr_eq(&first, slot.material.as_ref().unwrap()));
+
+        slot.object.target = CameraTarget::Texture {
+            width: 32,
+            height: 32,
+        };
+        slot.sync(&gpu.device, &gpu.registry, format);
+        let resized = slot.material.clone().unwrap();
+        assert!(!Arc::ptr_eq(&first, &resized));
+        assert!(resized.samples(&slot.target.as_ref().unwrap().color));
+
+        slot.object.target = CameraTarget::Main;
+        slot.sync(&gpu.device, &gpu.registry, format);
+        assert!(slot.target.is_none() && slot.material.is_none());
+    }
+
+    #[test]
+    fn monitor_meshes_are_left_out_of_their_own_camera() {
+        let Some(gpu) = gpu() else {
+            return;
+        };
+        let model = load(&gpu, &[CUBE]).remove(0);
+        let format = TextureFormat::Rgba8Unorm;
+        let mut renderer = Renderer3D::new(
+            &gpu.device,
+            &gpu.queue,
+            format,
+            64,
+            64,
+            model,
+            Mat4::IDENTITY,
+            &gpu.layouts,
+            DepthMode::Standard,
+        );
+        let own = renderer.instance.material(0).unwrap().clone();
+        let target = RenderTarget::new(&gpu.device, format, 64, 64);
+        let monitor = Arc::new(target.material(&gpu.device, &gpu.registry));
+        renderer.instance.set_material(0, monitor.clone());
+        assert!(Arc::ptr_eq(
+            renderer.instance.material(0).unwrap(),
+            &monitor
+        ));
+
+        // Sampling the target while drawing into it would fail validation.
+        let view = CameraView::new(&gpu.device, &gpu.layouts);
+        let main = Viewport::new(&gpu.device, format, 64, 64);
+        gpu.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let mut encoder = gpu.device.create_command_encoder(&Default::default());
+        renderer.render_view(&mut encoder, &target.view_target(), &view);
+        renderer.render_view(&mut encoder, &renderer.main_target(&main.color_view), &view);
+        gpu.queue.submit(Some(encoder.finish()));
+        let error = pollster::block_on(gpu.device.pop_error_scope());
+        assert!(error.is_none(), "{error:?}");
+
+        renderer.instance.clear_material(0);
+        assert!(Arc::ptr_eq(renderer.instance.material(0).unwrap(), &own));
+    }
 }
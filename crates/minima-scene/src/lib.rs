@@ -1,22 +1,161 @@
-use glam::Mat4;
-use minima_3d::Model;
+use glam::{Mat4, Vec3};
+use minima_3d::{Camera, ClearMode, DepthMode, Light, Model, Pose, ViewRegion, raycast_model};
 use std::sync::Arc;
 
 pub struct ModelInstance {
     pub model: Arc<Model>,
     pub transform: Mat4,
+    pub pose: Pose,
+}
+
+impl ModelInstance {
+    pub fn position(&self) -> Vec3 {
+        self.transform.w_axis.truncate()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum CameraTarget {
+    // Whatever the renderer presents: the swapchain, or the editor viewport.
+    #[default]
+    Main,
+    Texture {
+        width: u32,
+        height: u32,
+    },
+}
+
+// Views with a lower priority render first, so higher ones draw on top within the same target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraObject {
+    pub camera: Camera,
+    pub transform: Mat4,
+    pub target: CameraTarget,
+    pub region: ViewRegion,
+    pub priority: i32,
+    pub clear: ClearMode,
+    pub active: bool,
+}
+
+impl CameraObject {
+    pub fn new(camera: Camera, transform: Mat4) -> Self {
+        Self {
+            camera,
+            transform,
+            target: CameraTarget::Main,
+            region: ViewRegion::FULL,
+            priority: 0,
+            clear: ClearMode::default(),
+            active: true,
+        }
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.transform.w_axis.truncate()
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        -self.transform.z_axis.truncate().normalize_or_zero()
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        self.transform.inverse()
+    }
+
+    pub fn view_proj(&self, aspect: f32, depth: DepthMode) -> Mat4 {
+        self.camera.projection.matrix(aspect, depth) * self.view_matrix()
+    }
+}
+
+pub struct LightObject {
+    pub light: Light,
+    pub transform: Mat4,
+}
+
+impl LightObject {
+    pub fn position(&self) -> Vec3 {
+        self.transform.w_axis.truncate()
+    }
+
+    pub fn direction(&self) -> Vec3 {
+        -self.transform.z_axis.truncate().normalize_or_zero()
+    }
 }
 
 pub struct Scene {
     pub models: Vec<ModelInstance>,
+    pub cameras: Vec<CameraObject>,
+    pub lights: Vec<LightObject>,
 }
 
 impl Scene {
     pub fn new() -> Self {
-        Self { models: Vec::new() }
+        Self {
+            models: Vec::new(),
+            cameras: Vec::new(),
+            lights: Vec::new(),
+        }
     }
 
     pub fn add_model(&mut self, model: Arc<Model>, transform: Mat4) {
-        self.models.push(ModelInstance { model, transform });
+        let pose = Pose::rest(&model);
+        let mut stack = model.roots.clone();
+        while let Some(ix) = stack.pop() {
+            let node = &model.nodes[ix];
+            stack.extend_from_slice(&node.children);
+            let world = transform * pose.world[ix];
+            if let Some(camera) = node.camera.and_then(|c| model.cameras.get(c)) {
+                self.cameras.push(CameraObject::new(camera.clone(), world));
+            }
+            if let Some(light) = node.light.and_then(|l| model.lights.get(l)) {
+                self.lights.push(LightObject {
+                    light: light.clone(),
+                    transform: world,
+                });
+            }
+        }
+        self.models.push(ModelInstance {
+            model,
+            transform,
+            pose,
+        });
+    }
+
+    pub fn raycast(&self, origin: Vec3, dir: Vec3, max_distance: f32) -> Option<f32> {
+        self.raycast_excluding(origin, dir, max_distance, None)
+    }
+
+    // `exclude` skips one model instance, e.g. the entity a follow camera is tracking.
+    pub fn raycast_excluding(
+        &self,
+        origin: Vec3,
+        dir: Vec3,
+        max_distance: f32,
+        exclude: Option<usize>,
+    ) -> Option<f32> {
+        let mut nearest = None;
+        for (i, instance) in self.models.iter().enumerate() {
+            if exclude == Some(i) {
+                continue;
+            }
+            let max = nearest.unwrap_or(max_distance);
+            if let Some(t) = raycast_model(
+                &instance.model,
+                instance.transform,
+                &instance.pose.world,
+                origin,
+                dir,
+                max,
+            ) {
+                nearest = Some(t);
+            }
+        }
+        nearest
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
     }
 }
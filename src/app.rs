@@ -1,7 +1,12 @@
+use crate::camera_path::{CameraPathEditor, PathRequest};
 use crate::project::Project;
 use egui::Sense;
 use egui::load::SizedTexture;
-use minima_runtime::{Graphics, RcWindow, create_graphics};
+use minima_runtime::{
+    AssetPipeline, CameraMode, CameraObject, CameraTarget, ClearMode, ControllerSettings, CookJob,
+    CookReport, Graphics, Projection, Ray, RcWindow, ScreenRect, ViewRegion, create_graphics,
+};
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use winit::{
     application::ApplicationHandler,
@@ -13,9 +18,11 @@ use winit::{
 
 const FPS: u64 = 120;
 const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / FPS);
+const PREVIEW_WIDTH: f32 = 200.0;
+const PIP_REGION: ViewRegion = ViewRegion::new(0.68, 0.68, 0.3, 0.3);
 
 enum State {
-    Ready(ReadyState),
+    Ready(Box<ReadyState>),
     Init(Option<EventLoopProxy<Graphics>>),
 }
 
@@ -25,6 +32,8 @@ struct ReadyState {
     egui_state: egui_winit::State,
     egui_renderer: egui_wgpu::Renderer,
     viewport_tex_id: egui::TextureId,
+    // Keyed by scene camera index; the texture tells us when the target was recreated.
+    camera_previews: HashMap<usize, (egui::TextureId, wgpu::Texture)>,
 }
 
 pub struct NewProjectDialog {
@@ -45,22 +54,55 @@ impl NewProjectDialog {
     }
 }
 
+pub struct ExportDialog {
+    pub open: bool,
+    pub path_input: String,
+    pub requested: bool,
+    pub status: Option<Result<String, String>>,
+}
+
+impl ExportDialog {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            path_input: "scene.glb".into(),
+            requested: false,
+            status: None,
+        }
+    }
+}
+
 pub struct EditorUi {
     pub show_debug_panel: bool,
+    pub show_lod_colors: bool,
     pub camera_active: bool,
     pub cursor_grab_request: Option<bool>,
     pub current_project: Option<Project>,
     pub new_project: NewProjectDialog,
+    pub export: ExportDialog,
+    pub import_requested: bool,
+    pub import_job: Option<CookJob>,
+    pub import_report: Option<CookReport>,
+    pub cursor_ray: Option<Ray>,
+    pub camera_path: CameraPathEditor,
 }
 
 impl EditorUi {
     pub fn new() -> Self {
         Self {
             show_debug_panel: true,
+            show_lod_colors: false,
             camera_active: false,
             cursor_grab_request: None,
-            current_project: None,
+            // Started from inside a project, the editor loads and imports through its paths.
+            current_project: Project::open(".").ok(),
             new_project: NewProjectDialog::new(),
+            export: ExportDialog::new(),
+            import_requested: false,
+            import_job: None,
+            import_report: None,
+            cursor_ray: None,
+            camera_path: CameraPathEditor::new(),
         }
     }
 }
@@ -132,12 +174,68 @@ impl App {
             );
         }
     }
+    fn sync_camera_previews(ready: &mut ReadyState) -> HashMap<usize, SizedTexture> {
+        let mut previews = HashMap::new();
+        let count = ready.gfx.scene_cameras().len();
+        ready.camera_previews.retain(|&i, (id, _)| {
+            let keep = i < count;
+            if !keep {
+                ready.egui_renderer.free_texture(id);
+            }
+            keep
+        });
+        for i in 0..count {
+            let Some(target) = ready.gfx.scene_camera_target(i) else {
+                if let Some((id, _)) = ready.camera_previews.remove(&i) {
+                    ready.egui_renderer.free_texture(&id);
+                }
+                continue;
+            };
+            let texture = &target.color.texture;
+            let id = match ready.camera_previews.get(&i) {
+                Some((id, current)) if current == texture => *id,
+                stale => {
+                    if let Some((id, _)) = stale {
+                        ready.egui_renderer.free_texture(id);
+                    }
+                    let id = ready.egui_renderer.register_native_texture(
+                        ready.gfx.device(),
+                        &target.color.view,
+                        wgpu::FilterMode::Linear,
+                    );
+                    ready.camera_previews.insert(i, (id, texture.clone()));
+                    id
+                }
+            };
+            let size = egui::vec2(
+                PREVIEW_WIDTH,
+                PREVIEW_WIDTH * target.height as f32 / target.width as f32,
+            );
+            previews.insert(i, SizedTexture::new(id, size));
+        }
+        previews
+    }
+
     fn draw_editor(ready: &mut ReadyState, ui_state: &mut EditorUi) {
+        let camera_previews = Self::sync_camera_previews(ready);
         let raw_input = ready.egui_state.take_egui_input(ready.gfx.window());
         let viewport_tex_id = ready.viewport_tex_id;
         let cam_eye = ready.gfx.eye();
         let cam_yaw = ready.gfx.yaw();
         let cam_pitch = ready.gfx.pitch();
+        let cam_target = ready.gfx.orbit_target();
+        let cam_distance = ready.gfx.orbit_distance();
+        let mut camera_mode = ready.gfx.camera_mode();
+        let mut projection = ready.gfx.projection();
+        let mut reverse_z = ready.gfx.reverse_z();
+        let animation_names = ready.gfx.animation_names();
+        let mut active_animation = ready.gfx.active_animation();
+        let camera_matrices = ready.gfx.camera_matrices();
+        let mut controller_settings = ready.gfx.controller_settings();
+        let camera_key = ready.gfx.path_key(0.0);
+        let path_time = ready.gfx.camera_path_time();
+        let mut scene_cameras = ready.gfx.scene_cameras();
+        let view_camera = ready.gfx.scene_camera_from_view();
         let surface_cfg = ready.gfx.surface_config();
         let viewport_w = surface_cfg.width as f32;
         let viewport_h = surface_cfg.height as f32;
@@ -158,6 +256,29 @@ impl App {
                             ui.close();
                         }
 
+                        let can_import =
+                            ui_state.current_project.is_some() && ui_state.import_job.is_none();
+                        if ui
+                            .add_enabled(can_import, egui::Button::new("Import Assets"))
+                            .clicked()
+                        {
+                            ui_state.import_requested = true;
+                            ui.close();
+                        }
+
+                        if ui.button("Export Scene…").clicked() {
+                            if let Some(project) = &ui_state.current_project {
+                                ui_state.export.path_input = project
+                                    .root
+                                    .join("scene.glb")
+                                    .to_string_lossy()
+                                    .into_owned();
+                            }
+                            ui_state.export.open = true;
+                            ui_state.export.status = None;
+                            ui.close();
+                        }
+
                         ui.separator();
 
                         if ui.button("Quit").clicked() {
@@ -167,6 +288,13 @@ impl App {
 
                     ui.menu_button("View", |ui| {
                         ui.checkbox(&mut ui_state.show_debug_panel, "Show viewport debug panel");
+                        ui.checkbox(&mut ui_state.show_lod_colors, "Show LOD colors");
+                        ui.checkbox(&mut reverse_z, "Reverse-Z depth");
+                        ui.checkbox(&mut ui_state.camera_path.open, "Camera path editor");
+                        ui.separator();
+                        ui.radio_value(&mut camera_mode, CameraMode::Fly, "Fly camera");
+                        ui.radio_value(&mut camera_mode, CameraMode::Orbit, "Orbit camera");
+                        ui.radio_value(&mut camera_mode, CameraMode::Follow, "Follow camera");
                     });
 
                     ui.menu_button("Help", |ui| {
@@ -192,9 +320,49 @@ impl App {
                     if let Some(proj) = &ui_state.current_project {
                         ui.label(format!("Project: {}", proj.config.project.name));
                         ui.label(format!("Root: {}", proj.root.to_string_lossy()));
+                        if let Some(job) = &ui_state.import_job {
+                            let progress = job.progress();
+                            ui.separator();
+                            ui.add(
+                                egui::ProgressBar::new(
+                                    progress.done as f32 / progress.total.max(1) as f32,
+                                )
+                                .text(format!("Importing {}/{}", progress.done, progress.total)),
+                            );
+                            if let Some(current) = &progress.current {
+                                ui.label(current.to_string_lossy());
+                            }
+                        } else if let Some(report) = &ui_state.import_report {
+                            ui.separator();
+                            ui.label(format!(
+                                "Assets: {} cooked, {} up to date, {} failed",
+                                report.cooked.len(),
+                                report.up_to_date.len(),
+                                report.failed.len()
+                            ));
+                            for (path, err) in &report.failed {
+                                ui.colored_label(
+                                    egui::Color32::LIGHT_RED,
+                                    format!("{}: {err}", path.to_string_lossy()),
+                                );
+                            }
+                        }
                     } else {
                         ui.label("No project loaded.");
                     }
+
+                    ui.separator();
+                    ui.collapsing("Camera", |ui| {
+                        projection_ui(ui, &mut projection);
+                        ui.separator();
+                        controller_ui(ui, &mut controller_settings);
+                    });
+                    ui.collapsing("Scene Cameras", |ui| {
+                        scene_cameras_ui(ui, &mut scene_cameras, &view_camera, &camera_previews);
+                    });
+                    ui.collapsing("Animation", |ui| {
+                        animation_ui(ui, &animation_names, &mut active_animation);
+                    });
                 });
             egui::TopBottomPanel::bottom("debug_panel")
                 .resizable(true)
@@ -213,10 +381,25 @@ impl App {
                         ui.monospace(format!("{:.3} / {:.3}", cam_yaw, cam_pitch));
                     });
 
+                    if let Some(ray) = ui_state.cursor_ray {
+                        ui.horizontal(|ui| {
+                            ui.label("Cursor ray:");
+                            ui.monospace(format!("{:?} -> {:?}", ray.origin, ray.direction));
+                        });
+                    }
+
+                    if camera_mode == CameraMode::Orbit {
+                        ui.horizontal(|ui| {
+                            ui.label("Orbit target / distance:");
+                            ui.monospace(format!("{:?} / {:.3}", cam_target, cam_distance));
+                        });
+                    }
+
                     ui.separator();
                     ui.label(
                         "Double-click viewport to capture camera.\n\
-                         Esc to release.",
+                         Esc to release, Tab to switch fly / orbit / follow.\n\
+                         Orbit: left-drag rotate, middle-drag pan, scroll zoom.",
                     );
                 });
 
@@ -241,6 +424,20 @@ impl App {
                     let image = egui::Image::from_texture(sized).sense(Sense::click_and_drag());
                     let response = ui.add(image);
 
+                    let rect = ScreenRect::new(
+                        glam::Vec2::new(response.rect.min.x, response.rect.min.y),
+                        glam::Vec2::new(response.rect.width(), response.rect.height()),
+                    );
+                    ui_state.cursor_ray = response.hover_pos().and_then(|pos| {
+                        camera_matrices.pixel_to_ray(rect, glam::Vec2::new(pos.x, pos.y))
+                    });
+
+                    if ui_state.camera_path.open {
+                        ui_state
+                            .camera_path
+                            .draw_preview(ui.painter(), &camera_matrices, rect);
+                    }
+
                     if response.double_clicked() && !ui_state.camera_active {
                         ui_state.camera_active = true;
                         ui_state.cursor_grab_request = Some(true);
@@ -267,6 +464,51 @@ impl App {
                 }
             });
 
+            if ui_state.camera_path.open {
+                let assets = ui_state
+                    .current_project
+                    .as_ref()
+                    .map(|p| p.root.join(&p.config.paths.assets));
+                let mut open = true;
+                egui::Window::new("Camera Path")
+                    .open(&mut open)
+                    .default_width(360.0)
+                    .show(ctx, |ui| {
+                        ui_state
+                            .camera_path
+                            .ui(ui, camera_key, assets.as_deref(), path_time);
+                    });
+                ui_state.camera_path.open = open;
+            }
+
+            if ui_state.export.open {
+                let mut open = true;
+                egui::Window::new("Export Scene")
+                    .open(&mut open)
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        ui.label("Writes .glb, or .gltf with a .bin next to it.");
+                        ui.horizontal(|ui| {
+                            ui.label("Path:");
+                            ui.text_edit_singleline(&mut ui_state.export.path_input);
+                        });
+                        match &ui_state.export.status {
+                            Some(Ok(msg)) => {
+                                ui.label(msg);
+                            }
+                            Some(Err(err)) => {
+                                ui.colored_label(egui::Color32::RED, err);
+                            }
+                            None => {}
+                        }
+                        if ui.button("Export").clicked() {
+                            ui_state.export.requested = true;
+                        }
+                    });
+                ui_state.export.open = open;
+            }
+
             if ui_state.new_project.open {
                 egui::Window::new("New Project")
                     .collapsible(false)
@@ -330,6 +572,96 @@ impl App {
             }
         });
 
+        if ready.gfx.scene_cameras() != scene_cameras {
+            ready.gfx.set_scene_cameras(scene_cameras);
+        }
+        if ready.gfx.controller_settings() != controller_settings {
+            ready.gfx.set_controller_settings(controller_settings);
+        }
+        if ready.gfx.active_animation() != active_animation {
+            ready.gfx.play_animation(active_animation);
+        }
+        if ready.gfx.reverse_z() != reverse_z {
+            ready.gfx.set_reverse_z(reverse_z);
+        }
+        if ready.gfx.projection() != projection {
+            ready.gfx.set_projection(projection);
+        }
+        if ready.gfx.camera_mode() != camera_mode {
+            ready.gfx.set_camera_mode(camera_mode);
+        }
+        if ready.gfx.lod_debug() != ui_state.show_lod_colors {
+            ready.gfx.set_lod_debug(ui_state.show_lod_colors);
+        }
+
+        match ui_state.camera_path.request.take() {
+            Some(PathRequest::Play) => {
+                ready
+                    .gfx
+                    .play_camera_path(ui_state.camera_path.path.clone());
+            }
+            Some(PathRequest::Stop) => ready.gfx.stop_camera_path(),
+            Some(PathRequest::Scrub) => {
+                let editor = &ui_state.camera_path;
+                ready.gfx.preview_camera_path(&editor.path, editor.time);
+            }
+            Some(PathRequest::Save(file)) => {
+                let editor = &mut ui_state.camera_path;
+                editor.status = Some(match ready.gfx.save_camera_path(&file, &editor.path) {
+                    Ok(()) => format!("Saved {}", file.display()),
+                    Err(err) => format!("{err:#}"),
+                });
+            }
+            Some(PathRequest::Load(file)) => {
+                let editor = &mut ui_state.camera_path;
+                editor.status = Some(match ready.gfx.load_camera_path(&file) {
+                    Ok(path) => {
+                        editor.time = path.start_time();
+                        editor.path = path;
+                        format!("Loaded {}", file.display())
+                    }
+                    Err(err) => format!("{err:#}"),
+                });
+            }
+            None => {}
+        }
+
+        if std::mem::take(&mut ui_state.export.requested) {
+            let path = std::path::PathBuf::from(ui_state.export.path_input.trim());
+            ui_state.export.status = Some(
+                ready
+                    .gfx
+                    .export_scene(&path)
+                    .map(|()| format!("Exported {}", path.display()))
+                    .map_err(|err| format!("{err:#}")),
+            );
+        }
+
+        if let Some(pipeline) = ui_state
+            .current_project
+            .as_ref()
+            .map(Project::asset_pipeline)
+            && ready
+                .gfx
+                .models()
+                .pipeline
+                .as_ref()
+                .map(|p| (&p.source_dir, &p.cache_dir))
+                != Some((&pipeline.source_dir, &pipeline.cache_dir))
+        {
+            ready.gfx.set_asset_pipeline(pipeline);
+        }
+        if std::mem::take(&mut ui_state.import_requested)
+            && let Some(project) = &ui_state.current_project
+        {
+            let pipeline = project.asset_pipeline();
+            ui_state.import_job = Some(ready.gfx.cook_assets(&pipeline));
+        }
+        if let Some(report) = ui_state.import_job.as_mut().and_then(CookJob::try_finish) {
+            ui_state.import_job = None;
+            ui_state.import_report = Some(report);
+        }
+
         let egui::FullOutput {
             platform_output,
             textures_delta,
@@ -409,18 +741,22 @@ impl App {
 
 impl ApplicationHandler<Graphics> for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        if let State::Init(proxy) = &mut self.state {
-            if let Some(proxy) = proxy.take() {
-                let mut win_attr = Window::default_attributes();
-                win_attr = win_attr.with_title("Minima Editor");
-
-                let window: RcWindow = std::sync::Arc::new(
-                    event_loop
-                        .create_window(win_attr)
-                        .expect("create window err."),
-                );
-                pollster::block_on(create_graphics(window, proxy));
-            }
+        if let State::Init(proxy) = &mut self.state
+            && let Some(proxy) = proxy.take()
+        {
+            let mut win_attr = Window::default_attributes();
+            win_attr = win_attr.with_title("Minima Editor");
+
+            let window: RcWindow = std::sync::Arc::new(
+                event_loop
+                    .create_window(win_attr)
+                    .expect("create window err."),
+            );
+            let pipeline = self.ui.current_project.as_ref().map_or_else(
+                || AssetPipeline::new("assets", "cache"),
+                Project::asset_pipeline,
+            );
+            pollster::block_on(create_graphics(window, proxy, pipeline));
         }
     }
 
@@ -429,13 +765,14 @@ impl ApplicationHandler<Graphics> for App {
             App::init_egui_for_graphics(&graphics);
 
         graphics.request_redraw();
-        self.state = State::Ready(ReadyState {
+        self.state = State::Ready(Box::new(ReadyState {
             gfx: graphics,
             egui_ctx,
             egui_state,
             egui_renderer,
             viewport_tex_id,
-        });
+            camera_previews: HashMap::new(),
+        }));
     }
 
     fn new_events(&mut self, _event_loop: &ActiveEventLoop, _cause: StartCause) {
@@ -479,18 +816,30 @@ impl ApplicationHandler<Graphics> for App {
                         use winit::event::ElementState;
                         use winit::keyboard::{KeyCode, PhysicalKey};
 
-                        if let PhysicalKey::Code(KeyCode::Escape) = key_event.physical_key {
-                            if key_event.state == ElementState::Pressed
-                                && !key_event.repeat
-                                && self.ui.camera_active
-                            {
-                                self.ui.camera_active = false;
-                                self.ui.cursor_grab_request = Some(false);
-                                ready.gfx.request_redraw();
-                            }
+                        if let PhysicalKey::Code(KeyCode::Escape) = key_event.physical_key
+                            && key_event.state == ElementState::Pressed
+                            && !key_event.repeat
+                            && self.ui.camera_active
+                        {
+                            self.ui.camera_active = false;
+                            self.ui.cursor_grab_request = Some(false);
+                            ready.gfx.release_camera_input();
+                            ready.gfx.request_redraw();
                         }
                     }
-                    if self.ui.camera_active && !response.consumed {
+                    let pointer = matches!(
+                        other,
+                        WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. }
+                    );
+                    // Releases reach the controller even when the UI has the input, so nothing
+                    // pressed before capture ended stays held.
+                    let release = match &other {
+                        WindowEvent::MouseInput { state, .. } => !state.is_pressed(),
+                        WindowEvent::KeyboardInput { event, .. } => !event.state.is_pressed(),
+                        WindowEvent::Focused(focused) => !focused,
+                        _ => false,
+                    };
+                    if release || (self.ui.camera_active && (pointer || !response.consumed)) {
                         ready.gfx.handle_window_event(&other);
                     }
                 }
@@ -504,10 +853,10 @@ impl ApplicationHandler<Graphics> for App {
         _device_id: winit::event::DeviceId,
         event: DeviceEvent,
     ) {
-        if let State::Ready(ready) = &mut self.state {
-            if self.ui.camera_active {
-                ready.gfx.handle_device_event(&event);
-            }
+        if let State::Ready(ready) = &mut self.state
+            && self.ui.camera_active
+        {
+            ready.gfx.handle_device_event(&event);
         }
     }
 
@@ -515,3 +864,225 @@ impl ApplicationHandler<Graphics> for App {
         event_loop.set_control_flow(ControlFlow::WaitUntil(self.render_target));
     }
 }
+
+fn animation_ui(ui: &mut egui::Ui, names: &[String], active: &mut Option<usize>) {
+    if names.is_empty() {
+        ui.label("The model has no animations.");
+        return;
+    }
+    ui.radio_value(active, None, "None");
+    for (i, name) in names.iter().enumerate() {
+        ui.radio_value(active, Some(i), name);
+    }
+}
+
+fn projection_ui(ui: &mut egui::Ui, projection: &mut Projection) {
+    let (near, far) = match *projection {
+        Projection::Perspective { near, far, .. } => (near, far.unwrap_or(minima_runtime::FAR)),
+        Projection::Orthographic { near, far, .. } => (near, far),
+    };
+    let perspective = matches!(projection, Projection::Perspective { .. });
+    ui.horizontal(|ui| {
+        if ui.radio(perspective, "Perspective").clicked() && !perspective {
+            *projection = Projection::Perspective {
+                fov_y: minima_runtime::FOV_Y,
+                near,
+                far: Some(far),
+            };
+        }
+        if ui.radio(!perspective, "Orthographic").clicked() && perspective {
+            *projection = Projection::Orthographic {
+                height: 10.0,
+                near,
+                far,
+            };
+        }
+    });
+
+    match projection {
+        Projection::Perspective { fov_y, near, far } => {
+            ui.horizontal(|ui| {
+                ui.label("FOV:");
+                ui.drag_angle(fov_y);
+            });
+            *fov_y = fov_y.clamp(1f32.to_radians(), 179f32.to_radians());
+            ui.horizontal(|ui| {
+                ui.label("Near:");
+                ui.add(
+                    egui::DragValue::new(near)
+                        .speed(0.01)
+                        .range(0.001..=f32::MAX),
+                );
+            });
+            let mut infinite = far.is_none();
+            ui.checkbox(&mut infinite, "Infinite far plane");
+            if infinite {
+                *far = None;
+            } else {
+                let value = far.get_or_insert(minima_runtime::FAR);
+                ui.horizontal(|ui| {
+                    ui.label("Far:");
+                    ui.add(egui::DragValue::new(value).speed(1.0));
+                });
+                *value = value.max(*near + 0.001);
+            }
+        }
+        Projection::Orthographic { height, near, far } => {
+            ui.horizontal(|ui| {
+                ui.label("Height:");
+                ui.add(
+                    egui::DragValue::new(height)
+                        .speed(0.1)
+                        .range(0.01..=f32::MAX),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Near:");
+                ui.add(egui::DragValue::new(near).speed(0.01));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Far:");
+                ui.add(egui::DragValue::new(far).speed(1.0));
+            });
+            *far = far.max(*near + 0.001);
+        }
+    }
+}
+
+fn controller_ui(ui: &mut egui::Ui, settings: &mut ControllerSettings) {
+    let rows = [
+        ("Move speed:", &mut settings.base_speed, 0.1, 0.01),
+        ("Acceleration:", &mut settings.acceleration, 0.1, 0.0),
+        ("Deceleration:", &mut settings.deceleration, 0.1, 0.0),
+        (
+            "Rotation smoothing (s):",
+            &mut settings.rotation_smoothing,
+            0.005,
+            0.0,
+        ),
+        (
+            "Scroll speed factor:",
+            &mut settings.scroll_speed_factor,
+            0.01,
+            1.0,
+        ),
+        (
+            "Stick look speed (rad/s):",
+            &mut settings.stick_look_speed,
+            0.05,
+            0.0,
+        ),
+        ("Stick deadzone:", &mut settings.stick_deadzone, 0.01, 0.0),
+    ];
+    for (label, value, speed, min) in rows {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            ui.add(
+                egui::DragValue::new(value)
+                    .speed(speed)
+                    .range(min..=f32::MAX),
+            );
+        });
+    }
+}
+
+fn scene_cameras_ui(
+    ui: &mut egui::Ui,
+    cameras: &mut Vec<CameraObject>,
+    view_camera: &CameraObject,
+    previews: &HashMap<usize, SizedTexture>,
+) {
+    if ui.button("Add camera from view").clicked() {
+        cameras.push(CameraObject {
+            region: PIP_REGION,
+            priority: minima_runtime::MAIN_CAMERA_PRIORITY + 1,
+            ..view_camera.clone()
+        });
+    }
+
+    let mut remove = None;
+    for (i, camera) in cameras.iter_mut().enumerate() {
+        let name = camera
+            .camera
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Camera {i}"));
+        egui::CollapsingHeader::new(name).id_salt(i).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut camera.active, "Active");
+                if ui.small_button("Delete").clicked() {
+                    remove = Some(i);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Priority:");
+                ui.add(egui::DragValue::new(&mut camera.priority));
+            });
+
+            let offscreen = matches!(camera.target, CameraTarget::Texture { .. });
+            ui.horizontal(|ui| {
+                if ui.radio(!offscreen, "Main view").clicked() {
+                    camera.target = CameraTarget::Main;
+                }
+                if ui.radio(offscreen, "Texture").clicked() && !offscreen {
+                    camera.target = CameraTarget::Texture {
+                        width: 256,
+                        height: 256,
+                    };
+                }
+            });
+            if let CameraTarget::Texture { width, height } = &mut camera.target {
+                ui.horizontal(|ui| {
+                    ui.label("Size:");
+                    ui.add(egui::DragValue::new(width).range(1..=4096));
+                    ui.add(egui::DragValue::new(height).range(1..=4096));
+                });
+            }
+
+            let region = &mut camera.region;
+            ui.horizontal(|ui| {
+                ui.label("Region:");
+                for value in [
+                    &mut region.x,
+                    &mut region.y,
+                    &mut region.width,
+                    &mut region.height,
+                ] {
+                    ui.add(egui::DragValue::new(value).speed(0.005).range(0.0..=1.0));
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Clear:");
+                let color = matches!(camera.clear, ClearMode::Color(_));
+                if ui.radio(color, "Color").clicked() && !color {
+                    camera.clear = ClearMode::default();
+                }
+                if ui
+                    .radio(camera.clear == ClearMode::DepthOnly, "Depth")
+                    .clicked()
+                {
+                    camera.clear = ClearMode::DepthOnly;
+                }
+                if ui.radio(camera.clear == ClearMode::Keep, "None").clicked() {
+                    camera.clear = ClearMode::Keep;
+                }
+                if let ClearMode::Color(c) = &mut camera.clear {
+                    let mut rgb = [c.r as f32, c.g as f32, c.b as f32];
+                    if ui.color_edit_button_rgb(&mut rgb).changed() {
+                        c.r = rgb[0] as f64;
+                        c.g = rgb[1] as f64;
+                        c.b = rgb[2] as f64;
+                    }
+                }
+            });
+
+            if let Some(preview) = previews.get(&i) {
+                ui.image(*preview);
+            }
+        });
+    }
+    if let Some(i) = remove {
+        cameras.remove(i);
+    }
+}
@@ -0,0 +1,210 @@
+use minima_runtime::{CameraMatrices, CameraPath, PathKey, ScreenRect, SplineKind};
+use std::path::{Path, PathBuf};
+
+const PREVIEW_SEGMENTS: usize = 256;
+const KEY_SPACING: f32 = 2.0;
+
+pub enum PathRequest {
+    Play,
+    Stop,
+    Scrub,
+    Save(PathBuf),
+    Load(PathBuf),
+}
+
+pub struct CameraPathEditor {
+    pub open: bool,
+    pub path: CameraPath,
+    pub file: String,
+    pub time: f32,
+    pub request: Option<PathRequest>,
+    pub status: Option<String>,
+}
+
+impl CameraPathEditor {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            path: CameraPath::default(),
+            file: "camera_path.toml".into(),
+            time: 0.0,
+            request: None,
+            status: None,
+        }
+    }
+
+    // `camera` is the current view keyed at time 0; `assets` is the project asset dir, if any.
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        camera: PathKey,
+        assets: Option<&Path>,
+        playing: Option<f32>,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Spline:");
+            ui.radio_value(&mut self.path.kind, SplineKind::CatmullRom, "Catmull-Rom");
+            ui.radio_value(&mut self.path.kind, SplineKind::Bezier, "Bézier");
+        });
+        ui.checkbox(&mut self.path.looping, "Loop");
+
+        ui.horizontal(|ui| {
+            if ui.button("Add key from camera").clicked() {
+                let time = if self.path.keys.is_empty() {
+                    0.0
+                } else {
+                    self.path.end_time() + KEY_SPACING
+                };
+                self.path.keys.push(PathKey { time, ..camera });
+                self.path.auto_handle(self.path.keys.len() - 1);
+            }
+            if self.path.kind == SplineKind::Bezier && ui.button("Auto handles").clicked() {
+                self.path.auto_handles();
+            }
+        });
+
+        let bezier = self.path.kind == SplineKind::Bezier;
+        let mut remove = None;
+        let mut auto = None;
+        let mut resort = false;
+        egui::ScrollArea::vertical()
+            .max_height(180.0)
+            .show(ui, |ui| {
+                for (i, key) in self.path.keys.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("#{i}"));
+                        resort |= ui
+                            .add(egui::DragValue::new(&mut key.time).speed(0.05).suffix(" s"))
+                            .changed();
+                        ui.label("FOV:");
+                        ui.drag_angle(&mut key.fov_y);
+                        if ui.small_button("Set to camera").clicked() {
+                            *key = PathKey {
+                                time: key.time,
+                                in_handle: key.in_handle,
+                                out_handle: key.out_handle,
+                                ..camera
+                            };
+                        }
+                        if ui.small_button("Delete").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    if bezier {
+                        ui.horizontal(|ui| {
+                            ui.label("In:");
+                            vec3_ui(ui, &mut key.in_handle);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Out:");
+                            vec3_ui(ui, &mut key.out_handle);
+                            if ui.small_button("Auto").clicked() {
+                                auto = Some(i);
+                            }
+                        });
+                    }
+                }
+            });
+        if let Some(i) = auto {
+            self.path.auto_handle(i);
+        }
+        if let Some(i) = remove {
+            self.path.keys.remove(i);
+        }
+        if resort {
+            self.path.sort_keys();
+        }
+
+        ui.separator();
+        let (start, end) = (
+            self.path.start_time(),
+            self.path.start_time() + self.path.period(),
+        );
+        let slider = egui::Slider::new(&mut self.time, start..=end.max(start)).text("Time");
+        if ui.add(slider).changed() {
+            self.request = Some(PathRequest::Scrub);
+        }
+        ui.horizontal(|ui| {
+            if playing.is_some() {
+                if ui.button("Stop").clicked() {
+                    self.request = Some(PathRequest::Stop);
+                }
+            } else if ui
+                .add_enabled(self.path.keys.len() > 1, egui::Button::new("Play"))
+                .clicked()
+            {
+                self.request = Some(PathRequest::Play);
+            }
+            if let Some(time) = playing {
+                ui.monospace(format!("{time:.2} s"));
+            }
+        });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("File:");
+            ui.text_edit_singleline(&mut self.file);
+        });
+        ui.horizontal(|ui| {
+            let file = self.resolve(assets);
+            if ui.button("Save").clicked() {
+                self.request = Some(PathRequest::Save(file.clone()));
+            }
+            if ui.button("Load").clicked() {
+                self.request = Some(PathRequest::Load(file));
+            }
+        });
+        if let Some(status) = &self.status {
+            ui.label(status);
+        }
+    }
+
+    pub fn draw_preview(
+        &self,
+        painter: &egui::Painter,
+        matrices: &CameraMatrices,
+        rect: ScreenRect,
+    ) {
+        let to_screen = |p: glam::Vec3| {
+            matrices
+                .world_to_screen(rect, p)
+                .map(|s| egui::pos2(s.x, s.y))
+        };
+        let stroke = egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE);
+        let (start, duration) = (self.path.start_time(), self.path.period());
+        let points: Vec<_> = (0..=PREVIEW_SEGMENTS)
+            .map(|i| start + duration * i as f32 / PREVIEW_SEGMENTS as f32)
+            .map(|t| self.path.sample(t).and_then(|s| to_screen(s.position)))
+            .collect();
+        for pair in points.windows(2) {
+            if let [Some(a), Some(b)] = pair {
+                painter.line_segment([*a, *b], stroke);
+            }
+        }
+        for key in &self.path.keys {
+            if let Some(p) = to_screen(key.position) {
+                painter.circle_filled(p, 4.0, egui::Color32::WHITE);
+            }
+        }
+        if let Some(p) = self
+            .path
+            .sample(self.time)
+            .and_then(|s| to_screen(s.position))
+        {
+            painter.circle_stroke(p, 6.0, egui::Stroke::new(2.0, egui::Color32::YELLOW));
+        }
+    }
+
+    fn resolve(&self, assets: Option<&Path>) -> PathBuf {
+        match assets {
+            Some(dir) => dir.join(&self.file),
+            None => PathBuf::from(&self.file),
+        }
+    }
+}
+
+fn vec3_ui(ui: &mut egui::Ui, value: &mut glam::Vec3) {
+    for c in 0..3 {
+        ui.add(egui::DragValue::new(&mut value[c]).speed(0.01));
+    }
+}
@@ -1,4 +1,5 @@
 mod app;
+mod camera_path;
 mod project;
 
 use crate::app::App;
@@ -1,3 +1,4 @@
+use minima_runtime::AssetPipeline;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -12,6 +13,12 @@ pub struct PathsSection {
     pub assets: PathBuf,
     pub scenes: PathBuf,
     pub default_scene: PathBuf,
+    #[serde(default = "default_cache_dir")]
+    pub cache: PathBuf,
+}
+
+fn default_cache_dir() -> PathBuf {
+    PathBuf::from("cache")
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -34,7 +41,21 @@ pub struct Project {
     pub config: ProjectConfig,
 }
 
+const CONFIG_FILE: &str = "minima.project.toml";
+
 impl Project {
+    pub fn open(root: impl AsRef<Path>) -> std::io::Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        let text = std::fs::read_to_string(root.join(CONFIG_FILE))?;
+        let config = toml::from_str(&text).map_err(std::io::Error::other)?;
+        Ok(Project { root, config })
+    }
+
+    pub fn asset_pipeline(&self) -> AssetPipeline {
+        let paths = &self.config.paths;
+        AssetPipeline::new(self.root.join(&paths.assets), self.root.join(&paths.cache))
+    }
+
     pub fn create_scaffold(
         root: impl AsRef<Path>,
         name: &str,
@@ -57,6 +78,7 @@ impl Project {
                 assets: PathBuf::from("assets"),
                 scenes: PathBuf::from("scenes"),
                 default_scene: PathBuf::from("scenes/main.scene.json"),
+                cache: default_cache_dir(),
             },
             build: BuildSection {
                 profile: Some("release".into()),
@@ -65,7 +87,7 @@ impl Project {
         };
 
         let toml_str = toml::to_string_pretty(&config).expect("serialize project config");
-        fs::write(root.join("minima.project.toml"), toml_str)?;
+        fs::write(root.join(CONFIG_FILE), toml_str)?;
         let cargo_toml = format!(
             r#"[package]
 name = "{name_kebab}"
@@ -75,8 +97,11 @@ edition = "2024"
 [dependencies]
 minima-runtime = {{ path = "../../crates/minima-runtime" }}
 minima-3d      = {{ path = "../../crates/minima-3d" }}
+minima-anim    = {{ path = "../../crates/minima-anim" }}
+minima-asset   = {{ path = "../../crates/minima-asset" }}
 minima-camera  = {{ path = "../../crates/minima-camera" }}
 minima-gltf    = {{ path = "../../crates/minima-gltf" }}
+minima-obj     = {{ path = "../../crates/minima-obj" }}
 minima-scene   = {{ path = "../../crates/minima-scene" }}
 "#,
             name_kebab = name.to_lowercase().replace(' ', "_"),